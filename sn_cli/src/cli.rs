@@ -78,4 +78,18 @@ pub(crate) struct Opt {
     /// This may increase operation speed, but offers no guarantees that operations were successful.
     #[clap(global = true, short = 'n')]
     pub no_verify: bool,
+
+    /// Do not run the command through the daemon, even if one is running.
+    #[clap(long, global = true)]
+    pub no_daemon: bool,
+
+    /// Cache the chunks fetched from the network in the given directory.
+    ///
+    /// Chunks found in the cache are not fetched from the network again.
+    #[clap(long, global = true, value_name = "DIR")]
+    pub chunk_cache: Option<PathBuf>,
+
+    /// Maximum size of the chunk cache, in MB.
+    #[clap(long, global = true, default_value_t = 1024, requires = "chunk_cache")]
+    pub chunk_cache_size: u64,
 }
@@ -15,8 +15,12 @@ mod subcommands;
 use crate::{
     cli::Opt,
     subcommands::{
+        daemon::{daemon_cmd, run_on_daemon, DaemonClient},
         files::files_cmds,
+        gateway::gateway_cmd,
         gossipsub::gossipsub_cmds,
+        inspect::inspect_cmd,
+        name::name_cmds,
         register::register_cmds,
         wallet::{wallet_cmds, wallet_cmds_without_client, WalletCmds},
         SubCmd,
@@ -24,8 +28,11 @@ use crate::{
 };
 use bls::SecretKey;
 use clap::Parser;
-use color_eyre::Result;
-use sn_client::Client;
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use sn_client::{ChunkCache, Client, ClientConfig};
 #[cfg(feature = "metrics")]
 use sn_logging::{metrics::init_metrics, LogBuilder, LogFormat};
 use sn_peers_acquisition::parse_peers_args;
@@ -70,18 +77,33 @@ async fn main() -> Result<()> {
     println!("Built with git version: {}", sn_build_info::git_info());
 
     let client_data_dir_path = get_client_data_dir_path()?;
+
     // Perform actions that do not require us connecting to the network and return early
     if let SubCmd::Wallet(cmds) = &opt.cmd {
         if let WalletCmds::Address
         | WalletCmds::Balance { .. }
         | WalletCmds::Deposit { .. }
-        | WalletCmds::Create { .. } = cmds
+        | WalletCmds::Create { .. }
+        | WalletCmds::Encrypt
+        | WalletCmds::Decrypt = cmds
         {
             wallet_cmds_without_client(cmds, &client_data_dir_path).await?;
             return Ok(());
         }
     }
 
+    // Run the command through the daemon if one is running, skipping the connection to the network
+    if !opt.no_daemon {
+        if let Some(daemon) = DaemonClient::find(&client_data_dir_path).await {
+            if let SubCmd::Daemon { .. } = &opt.cmd {
+                bail!("A daemon is already running for {client_data_dir_path:?}");
+            }
+            if run_on_daemon(&daemon, &opt.cmd, &client_data_dir_path, !opt.no_verify).await? {
+                return Ok(());
+            }
+        }
+    }
+
     println!("Instantiating a SAFE client...");
     let secret_key = get_client_secret_key(&client_data_dir_path)?;
 
@@ -98,10 +120,27 @@ async fn main() -> Result<()> {
         Some(bootstrap_peers)
     };
 
-    let client = Client::new(secret_key, bootstrap_peers, opt.timeout).await?;
-
+    let mut config = ClientConfig::builder();
+    if let Some(timeout) = opt.timeout {
+        config.request_timeout(timeout);
+    }
+    if let Some(dir) = &opt.chunk_cache {
+        let max_size = opt
+            .chunk_cache_size
+            .checked_mul(1024 * 1024)
+            .ok_or_else(|| {
+                eyre!(
+                    "The chunk cache size of {} MiB is too large",
+                    opt.chunk_cache_size
+                )
+            })?;
+        config.chunk_cache(ChunkCache::open(dir, max_size)?);
+    }
     // default to verifying storage
-    let should_verify_store = !opt.no_verify;
+    config.verify_store(!opt.no_verify);
+    let client = Client::new(secret_key, bootstrap_peers, config.build()).await?;
+
+    let should_verify_store = client.config().verify_store();
 
     match opt.cmd {
         SubCmd::Wallet(cmds) => {
@@ -119,7 +158,17 @@ async fn main() -> Result<()> {
         SubCmd::Register(cmds) => {
             register_cmds(cmds, &client, &client_data_dir_path, should_verify_store).await?
         }
+        SubCmd::Name(cmds) => {
+            name_cmds(cmds, &client, &client_data_dir_path, should_verify_store).await?
+        }
         SubCmd::Gossipsub(cmds) => gossipsub_cmds(cmds, &client).await?,
+        SubCmd::Daemon { listen_addr } => {
+            daemon_cmd(listen_addr, &client, &client_data_dir_path).await?
+        }
+        SubCmd::Gateway { listen_addr } => {
+            gateway_cmd(listen_addr, &client, &client_data_dir_path).await?
+        }
+        SubCmd::Inspect { address } => inspect_cmd(address, &client).await?,
     };
 
     Ok(())
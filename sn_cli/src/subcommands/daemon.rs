@@ -0,0 +1,802 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    files::{print_upload_report, upload_and_record, FilesCmds, UploadOptions, UploadReport},
+    register::RegisterCmds,
+    wallet::{wallet_passphrase, WalletCmds},
+    SubCmd,
+};
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, HeaderMap, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sn_client::{Client, Error as ClientError, Files, WalletClient};
+use sn_protocol::{
+    safe_url::{parse_chunk_address, parse_register_address},
+    NetworkAddress,
+};
+use sn_transfers::{parse_main_pubkey, LocalWallet, NanoTokens, Transfer};
+use std::{
+    convert::Infallible,
+    fs,
+    io::Write,
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use xor_name::XorName;
+
+/// File in the client data dir holding the address of the running daemon.
+const DAEMON_ADDR_FILE: &str = "daemon_addr";
+/// File in the client data dir holding the token the daemon requires on every request.
+/// Only the user running the daemon can read it.
+const DAEMON_TOKEN_FILE: &str = "daemon_token";
+/// Header carrying the token of the daemon.
+const DAEMON_TOKEN_HEADER: &str = "x-safe-daemon-token";
+
+// JSON-RPC error codes.
+const INVALID_REQUEST: i64 = -32600;
+const SERVER_ERROR: i64 = -32000;
+
+/// Operations exposed by the daemon, as the `method` and `params` of a JSON-RPC 2.0 request.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum DaemonRequest {
+    Status,
+    FilesUpload {
+        path: PathBuf,
+        verify_store: bool,
+        options: UploadOptions,
+    },
+    /// Download the file at `address` into the client data dir, as `name`.
+    FilesDownload {
+        address: String,
+        name: String,
+    },
+    RegisterCreate {
+        name: String,
+        verify_store: bool,
+    },
+    RegisterEdit {
+        address: String,
+        entry: String,
+        verify_store: bool,
+    },
+    RegisterGet {
+        address: String,
+    },
+    WalletSend {
+        amount: String,
+        to: String,
+        verify_store: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    id: u64,
+    #[serde(flatten)]
+    request: DaemonRequest,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RpcResponse {
+    jsonrpc: String,
+    id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    fn new(id: Option<u64>, result: std::result::Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+struct Daemon {
+    client: Client,
+    files_api: Files,
+    root_dir: PathBuf,
+    token: String,
+    // Asked for once when starting, as the requests are served without a terminal to ask on.
+    wallet_passphrase: Option<String>,
+    // Operations spending from the wallet are run one at a time, so they do not race each other.
+    wallet_lock: Mutex<()>,
+}
+
+/// Keep the client connected and serve JSON-RPC requests on `listen_addr` until interrupted.
+///
+/// The address is recorded in the client data dir, so that other invocations of the CLI
+/// can find the daemon and run their commands through it.
+pub(crate) async fn daemon_cmd(
+    listen_addr: SocketAddr,
+    client: &Client,
+    root_dir: &Path,
+) -> Result<()> {
+    let wallet_passphrase = wallet_passphrase(root_dir)?;
+
+    // A fresh token for every run, so that only who can read the client data dir can use it.
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let token_file = root_dir.join(DAEMON_TOKEN_FILE);
+    write_private_file(&token_file, token.as_bytes())
+        .wrap_err_with(|| format!("Failed to write the daemon token to {token_file:?}"))?;
+
+    let daemon = Arc::new(Daemon {
+        client: client.clone(),
+        files_api: Files::new(client.clone(), root_dir.to_path_buf())
+            .with_wallet_passphrase(wallet_passphrase.clone()),
+        root_dir: root_dir.to_path_buf(),
+        token,
+        wallet_passphrase,
+        wallet_lock: Mutex::new(()),
+    });
+
+    let make_service = make_service_fn(move |_conn| {
+        let daemon = daemon.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let daemon = daemon.clone();
+                async move { Ok::<_, Infallible>(daemon.handle_http(req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&listen_addr)
+        .wrap_err_with(|| format!("Failed to bind the daemon to {listen_addr}"))?
+        .serve(make_service);
+    let local_addr = server.local_addr();
+
+    let addr_file = root_dir.join(DAEMON_ADDR_FILE);
+    fs::write(&addr_file, local_addr.to_string())?;
+    info!("Daemon listening on http://{local_addr}");
+    println!("Daemon listening on http://{local_addr}, press Ctrl-C to stop it");
+
+    let result = server
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+
+    for file in [addr_file, token_file] {
+        if let Err(err) = fs::remove_file(&file) {
+            warn!("Failed to remove the daemon file {file:?}: {err}");
+        }
+    }
+    println!("Daemon stopped");
+    result.wrap_err("The daemon stopped unexpectedly")
+}
+
+impl Daemon {
+    async fn handle_http(&self, req: Request<Body>) -> Response<Body> {
+        if let Err((status, message)) = check_http_request(req.method(), req.headers(), &self.token)
+        {
+            warn!("Rejected a daemon request: {message}");
+            let mut resp = Response::new(Body::from(message));
+            *resp.status_mut() = status;
+            return resp;
+        }
+
+        let response = match hyper::body::to_bytes(req.into_body()).await {
+            Ok(body) => match parse_rpc_request(&body) {
+                Ok(rpc) => {
+                    debug!("Daemon request {}: {:?}", rpc.id, rpc.request);
+                    let result = self.handle(rpc.request).await.map_err(|err| {
+                        warn!("Daemon request {} failed: {err:?}", rpc.id);
+                        RpcError {
+                            code: SERVER_ERROR,
+                            message: format!("{err:?}"),
+                        }
+                    });
+                    RpcResponse::new(Some(rpc.id), result)
+                }
+                Err(error) => RpcResponse::new(None, Err(error)),
+            },
+            Err(err) => RpcResponse::new(
+                None,
+                Err(RpcError {
+                    code: INVALID_REQUEST,
+                    message: format!("Failed to read the request: {err}"),
+                }),
+            ),
+        };
+
+        match serde_json::to_vec(&response) {
+            Ok(bytes) => {
+                let mut resp = Response::new(Body::from(bytes));
+                if let Ok(value) = "application/json".parse() {
+                    let _ = resp
+                        .headers_mut()
+                        .insert(hyper::header::CONTENT_TYPE, value);
+                }
+                resp
+            }
+            Err(err) => {
+                let mut resp = Response::new(Body::from(err.to_string()));
+                *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                resp
+            }
+        }
+    }
+
+    async fn handle(&self, request: DaemonRequest) -> Result<Value> {
+        match request {
+            DaemonRequest::Status => {
+                let wallet =
+                    LocalWallet::load_from(&self.root_dir, self.wallet_passphrase.as_deref())?;
+                Ok(json!({
+                    "wallet_address": hex::encode(wallet.address().to_bytes()),
+                    "balance": wallet.balance().to_string(),
+                }))
+            }
+            DaemonRequest::FilesUpload {
+                path,
+                verify_store,
+                options,
+            } => {
+                let path = check_upload_path(&self.root_dir, &path)?;
+                let _wallet_guard = self.wallet_lock.lock().await;
+                let report = upload_and_record(
+                    &self.files_api,
+                    &path,
+                    &self.root_dir,
+                    verify_store,
+                    &options,
+                )
+                .await?;
+                Ok(serde_json::to_value(report)?)
+            }
+            DaemonRequest::FilesDownload { address, name } => {
+                let address = parse_chunk_address(&address)?;
+                let path = self.root_dir.join(check_file_name(&name)?);
+                let bytes_written = self
+                    .files_api
+                    .download_to_path(address, &path, false)
+                    .await?;
+                Ok(json!({ "bytes_written": bytes_written }))
+            }
+            DaemonRequest::RegisterCreate { name, verify_store } => {
+                let _wallet_guard = self.wallet_lock.lock().await;
+                let wallet =
+                    LocalWallet::load_from(&self.root_dir, self.wallet_passphrase.as_deref())?;
+                let mut wallet_client = WalletClient::new(self.client.clone(), wallet);
+                let meta = XorName::from_content(name.as_bytes());
+                let (register, cost) = self
+                    .client
+                    .create_and_pay_for_register(meta, &mut wallet_client, verify_store)
+                    .await?;
+                let address = NetworkAddress::from_register_address(*register.address());
+                Ok(json!({
+                    "address": address.to_string(),
+                    "cost": cost.to_string(),
+                }))
+            }
+            DaemonRequest::RegisterEdit {
+                address,
+                entry,
+                verify_store,
+            } => {
+                let address = parse_register_address(&address)?;
+                let mut register = self.client.get_register(address).await?;
+                match register.write_online(entry.as_bytes(), verify_store).await {
+                    Ok(()) => {}
+                    Err(ClientError::ContentBranchDetected(_)) => {
+                        register
+                            .write_merging_branches_online(entry.as_bytes(), verify_store)
+                            .await?
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+                Ok(json!({}))
+            }
+            DaemonRequest::RegisterGet { address } => {
+                let address = parse_register_address(&address)?;
+                let register = self.client.get_register(address).await?;
+                let entries: Vec<_> = register
+                    .read()
+                    .into_iter()
+                    .map(|(hash, entry)| {
+                        json!({
+                            "hash": format!("{hash:?}"),
+                            "value": hex::encode(entry),
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "entries": entries }))
+            }
+            DaemonRequest::WalletSend {
+                amount,
+                to,
+                verify_store,
+            } => {
+                let _wallet_guard = self.wallet_lock.lock().await;
+                let amount = NanoTokens::from_str(&amount)
+                    .map_err(|err| eyre!("The amount cannot be parsed: {err:?}"))?;
+                let to = parse_main_pubkey(to)?;
+                let from =
+                    LocalWallet::load_from(&self.root_dir, self.wallet_passphrase.as_deref())?;
+                let cash_note =
+                    sn_client::send(from, amount, to, &self.client, verify_store).await?;
+                let wallet =
+                    LocalWallet::load_from(&self.root_dir, self.wallet_passphrase.as_deref())?;
+                Ok(json!({
+                    "transfer": Transfer::transfers_from_cash_note(cash_note)?.to_hex()?,
+                    "balance": wallet.balance().to_string(),
+                }))
+            }
+        }
+    }
+}
+
+/// Resolve the path of an upload, which must not be within the client data dir,
+/// as that holds the keys of the client and its wallet.
+fn check_upload_path(root_dir: &Path, path: &Path) -> Result<PathBuf> {
+    if !path.is_absolute() {
+        bail!("The path to upload must be absolute, got {path:?}");
+    }
+    let path = path
+        .canonicalize()
+        .wrap_err_with(|| format!("Cannot upload {path:?}"))?;
+    let root_dir = root_dir
+        .canonicalize()
+        .unwrap_or_else(|_| root_dir.to_path_buf());
+    if path.starts_with(&root_dir) || root_dir.starts_with(&path) {
+        bail!("The daemon does not upload the client data dir, nor anything within it");
+    }
+    Ok(path)
+}
+
+/// Check the transport level of a request: only JSON `POST` requests from non-browser clients
+/// knowing the token of the daemon are served.
+///
+/// Browsers always send an `Origin` header along with cross-origin `POST` requests, hence
+/// rejecting those, and anything which is not JSON, keeps web pages from reaching the daemon.
+fn check_http_request(
+    method: &Method,
+    headers: &HeaderMap,
+    token: &str,
+) -> std::result::Result<(), (StatusCode, &'static str)> {
+    if method != Method::POST {
+        return Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only JSON-RPC POST requests are supported",
+        ));
+    }
+    if headers.contains_key(header::ORIGIN) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Requests from web pages are not allowed",
+        ));
+    }
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map_or(false, |mime| {
+            mime.trim().eq_ignore_ascii_case("application/json")
+        });
+    if !is_json {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Requests must be of type application/json",
+        ));
+    }
+    let given_token = headers
+        .get(DAEMON_TOKEN_HEADER)
+        .map(|value| value.as_bytes())
+        .unwrap_or_default();
+    if !constant_time_eq(given_token, token.as_bytes()) {
+        return Err((StatusCode::UNAUTHORIZED, "Missing or invalid daemon token"));
+    }
+    Ok(())
+}
+
+// Compare without returning early, so the time taken does not tell how much of a token matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn parse_rpc_request(body: &[u8]) -> std::result::Result<RpcRequest, RpcError> {
+    let rpc = serde_json::from_slice::<RpcRequest>(body).map_err(|err| RpcError {
+        code: INVALID_REQUEST,
+        message: format!("Invalid request: {err}"),
+    })?;
+    if rpc.jsonrpc != "2.0" {
+        return Err(RpcError {
+            code: INVALID_REQUEST,
+            message: format!("Unsupported JSON-RPC version {}", rpc.jsonrpc),
+        });
+    }
+    Ok(rpc)
+}
+
+/// Check that `name` is a plain file name, so that a download can not be written outside of
+/// the client data dir.
+fn check_file_name(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(path),
+        _ => bail!("{name:?} is not a plain file name"),
+    }
+}
+
+/// Write `contents` to a file only the current user can read and write.
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    // The permissions are only set on creation, so a leftover file is replaced.
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    let mut options = fs::OpenOptions::new();
+    let _ = options.write(true).create_new(true);
+    // On Unix systems, make sure only the current user can read/write.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let _ = options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// Client of a running daemon.
+pub(crate) struct DaemonClient {
+    url: String,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl DaemonClient {
+    /// Connect to the daemon serving `root_dir`, if one is running.
+    pub(crate) async fn find(root_dir: &Path) -> Option<Self> {
+        let addr = fs::read_to_string(root_dir.join(DAEMON_ADDR_FILE)).ok()?;
+        let token = fs::read_to_string(root_dir.join(DAEMON_TOKEN_FILE)).ok()?;
+        let daemon = Self {
+            url: format!("http://{}", addr.trim()),
+            token: token.trim().to_string(),
+            http: reqwest::Client::new(),
+        };
+        match daemon.call(DaemonRequest::Status).await {
+            Ok(_) => Some(daemon),
+            Err(err) => {
+                warn!("A daemon address was recorded, but the daemon is not responding: {err:?}");
+                None
+            }
+        }
+    }
+
+    async fn call(&self, request: DaemonRequest) -> Result<Value> {
+        let body = serde_json::to_string(&RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            request,
+        })?;
+        let response = self
+            .http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(DAEMON_TOKEN_HEADER, &self.token)
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let response = response.text().await?;
+        if !status.is_success() {
+            bail!("The daemon rejected the command with {status}: {response}");
+        }
+        let response: RpcResponse = serde_json::from_str(&response)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => bail!("The daemon failed to run the command: {}", error.message),
+            (Some(result), None) => Ok(result),
+            (None, None) => bail!("The daemon returned an empty response"),
+        }
+    }
+}
+
+/// Run `cmd` through the daemon if it supports it.
+///
+/// Returns `false` if the command has to be run with a local client instead.
+pub(crate) async fn run_on_daemon(
+    daemon: &DaemonClient,
+    cmd: &SubCmd,
+    root_dir: &Path,
+    verify_store: bool,
+) -> Result<bool> {
+    match cmd {
+        SubCmd::Files(FilesCmds::Upload {
+            path,
+            batch_size,
+            show_holders,
+            private,
+            resume,
+        }) => {
+            let path = path
+                .canonicalize()
+                .wrap_err_with(|| format!("Cannot upload {path:?}"))?;
+            let options = UploadOptions {
+                batch_size: *batch_size,
+                show_holders: *show_holders,
+                private: *private,
+                resume: *resume,
+            };
+            println!("Uploading {path:?} through the daemon...");
+            let result = daemon
+                .call(DaemonRequest::FilesUpload {
+                    path: path.clone(),
+                    verify_store,
+                    options: options.clone(),
+                })
+                .await?;
+            let report: UploadReport = serde_json::from_value(result)?;
+            print_upload_report(&path, &options, &report);
+        }
+        SubCmd::Files(FilesCmds::Download {
+            file_name: Some(name),
+            file_addr: Some(address),
+            folder: false,
+            private: false,
+            ..
+        }) => {
+            let path = root_dir.join(check_file_name(name)?);
+            println!("Downloading {name} from {address} through the daemon");
+            let result = daemon
+                .call(DaemonRequest::FilesDownload {
+                    address: address.clone(),
+                    name: name.clone(),
+                })
+                .await?;
+            println!(
+                "Saved {name} ({} bytes) at {}",
+                result["bytes_written"].as_u64().unwrap_or_default(),
+                path.display()
+            );
+        }
+        SubCmd::Register(RegisterCmds::Create { name }) => {
+            let result = daemon
+                .call(DaemonRequest::RegisterCreate {
+                    name: name.clone(),
+                    verify_store,
+                })
+                .await?;
+            println!(
+                "Successfully created register '{name}' at {} for {}!",
+                result["address"].as_str().unwrap_or_default(),
+                result["cost"].as_str().unwrap_or_default()
+            );
+        }
+        SubCmd::Register(RegisterCmds::Edit {
+            address,
+            use_name: false,
+            entry,
+        }) => {
+            let _ = daemon
+                .call(DaemonRequest::RegisterEdit {
+                    address: address.clone(),
+                    entry: entry.clone(),
+                    verify_store,
+                })
+                .await?;
+            println!("Edited Register at {address} with: {entry}");
+        }
+        SubCmd::Register(RegisterCmds::Get {
+            addresses,
+            use_name: false,
+        }) => {
+            for address in addresses {
+                let result = daemon
+                    .call(DaemonRequest::RegisterGet {
+                        address: address.clone(),
+                    })
+                    .await?;
+                println!("Register entries at {address}:");
+                for entry in result["entries"].as_array().into_iter().flatten() {
+                    let bytes = hex::decode(entry["value"].as_str().unwrap_or_default())?;
+                    let data_str = match String::from_utf8(bytes.clone()) {
+                        Ok(data_str) => data_str,
+                        Err(_) => format!("{bytes:?}"),
+                    };
+                    println!("{}: {data_str}", entry["hash"].as_str().unwrap_or_default());
+                }
+            }
+        }
+        SubCmd::Wallet(WalletCmds::Send { amount, to }) => {
+            let result = daemon
+                .call(DaemonRequest::WalletSend {
+                    amount: amount.clone(),
+                    to: to.clone(),
+                    verify_store,
+                })
+                .await?;
+            println!("Sent {amount} to {to}");
+            println!(
+                "New wallet balance is {}.",
+                result["balance"].as_str().unwrap_or_default()
+            );
+            println!("The encrypted transfer has been successfully created.");
+            println!(
+                "Please share this to the recipient:\n\n{}\n",
+                result["transfer"].as_str().unwrap_or_default()
+            );
+            println!("The recipient can then use the 'receive' command to claim the funds.");
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "a-token";
+
+    fn json_headers(token: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(
+            header::CONTENT_TYPE,
+            "application/json; charset=utf-8".parse().unwrap(),
+        );
+        if let Some(token) = token {
+            let _ = headers.insert(DAEMON_TOKEN_HEADER, token.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn accepts_json_posts_carrying_the_token() {
+        let headers = json_headers(Some(TOKEN));
+        assert!(check_http_request(&Method::POST, &headers, TOKEN).is_ok());
+    }
+
+    #[test]
+    fn rejects_requests_without_the_right_token() {
+        for token in [None, Some("another-token"), Some("a-token-")] {
+            let headers = json_headers(token);
+            let status = check_http_request(&Method::POST, &headers, TOKEN).map_err(|e| e.0);
+            assert_eq!(status, Err(StatusCode::UNAUTHORIZED), "token {token:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_requests_from_browsers() {
+        let mut headers = json_headers(Some(TOKEN));
+        let _ = headers.insert(header::ORIGIN, "http://example.com".parse().unwrap());
+        let status = check_http_request(&Method::POST, &headers, TOKEN).map_err(|e| e.0);
+        assert_eq!(status, Err(StatusCode::FORBIDDEN));
+
+        let mut headers = json_headers(Some(TOKEN));
+        let _ = headers.insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        let status = check_http_request(&Method::POST, &headers, TOKEN).map_err(|e| e.0);
+        assert_eq!(status, Err(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+
+        let status =
+            check_http_request(&Method::GET, &json_headers(Some(TOKEN)), TOKEN).map_err(|e| e.0);
+        assert_eq!(status, Err(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    #[test]
+    fn parses_requests_into_their_operation() {
+        let body = br#"{
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "files_upload",
+            "params": {
+                "path": "/tmp/data",
+                "verify_store": true,
+                "options": { "batch_size": 4, "show_holders": false, "private": true, "resume": false }
+            }
+        }"#;
+        let rpc = parse_rpc_request(body).map_err(|e| e.message).unwrap();
+        assert_eq!(rpc.id, 7);
+        match rpc.request {
+            DaemonRequest::FilesUpload {
+                path,
+                verify_store,
+                options,
+            } => {
+                assert_eq!(path, PathBuf::from("/tmp/data"));
+                assert!(verify_store);
+                assert_eq!(options.batch_size, 4);
+                assert!(options.private);
+                assert!(!options.resume);
+            }
+            other => panic!("Unexpected request {other:?}"),
+        }
+
+        let body = br#"{ "jsonrpc": "2.0", "id": 1, "method": "status" }"#;
+        let rpc = parse_rpc_request(body).map_err(|e| e.message).unwrap();
+        assert!(matches!(rpc.request, DaemonRequest::Status));
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let bodies: [&[u8]; 3] = [
+            br#"{ "jsonrpc": "2.0", "id": 1, "method": "format_disk" }"#,
+            br#"{ "jsonrpc": "1.0", "id": 1, "method": "status" }"#,
+            br#"{ "jsonrpc": "2.0", "id": 1, "method": "files_download", "params": {} }"#,
+        ];
+        for body in bodies {
+            let error = parse_rpc_request(body).err().map(|e| e.code);
+            assert_eq!(error, Some(INVALID_REQUEST));
+        }
+    }
+
+    #[test]
+    fn downloads_are_kept_within_the_data_dir() {
+        assert!(check_file_name("file.txt").is_ok());
+        for name in ["", ".", "..", "../file.txt", "dir/file.txt", "/etc/passwd"] {
+            assert!(check_file_name(name).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn uploads_of_the_data_dir_are_refused() -> Result<()> {
+        let root_dir = tempfile::tempdir()?;
+        let other_dir = tempfile::tempdir()?;
+        let daemon_root = root_dir.path().to_path_buf();
+        fs::write(daemon_root.join("secret_key"), "key")?;
+
+        let is_allowed = |path: &Path| check_upload_path(&daemon_root, path).is_ok();
+        assert!(is_allowed(other_dir.path()));
+        assert!(!is_allowed(root_dir.path()));
+        assert!(!is_allowed(&root_dir.path().join("secret_key")));
+        assert!(!is_allowed(
+            &other_dir
+                .path()
+                .join("..")
+                .join(root_dir.path().file_name().unwrap())
+        ));
+        assert!(!is_allowed(Path::new("relative/path")));
+        Ok(())
+    }
+
+    #[test]
+    fn token_file_is_private() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(DAEMON_TOKEN_FILE);
+        fs::write(&path, "stale token")?;
+        write_private_file(&path, TOKEN.as_bytes())?;
+        assert_eq!(fs::read_to_string(&path)?, TOKEN);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        }
+        Ok(())
+    }
+}
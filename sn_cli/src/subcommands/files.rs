@@ -6,19 +6,25 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::wallet::{ChunkedFile, BATCH_SIZE};
-use bytes::Bytes;
+use super::wallet::{wallet_passphrase, ChunkedFile};
 use clap::Parser;
 use color_eyre::{
-    eyre::{bail, eyre, Error},
+    eyre::{bail, eyre, Error, WrapErr},
     Help, Result,
 };
 use indicatif::{ProgressBar, ProgressStyle};
-use libp2p::futures::future::join_all;
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use sn_client::{Client, Files};
-use sn_protocol::storage::{Chunk, ChunkAddress};
-use sn_transfers::NanoTokens;
+use serde::{Deserialize, Serialize};
+use sn_client::{
+    ChunkUploadState, Client, ClientEvent, Error as ClientError, Files, PrivateDataMap,
+    UploadJournal, UploadSummary, VersionedFile, DEFAULT_BATCH_SIZE,
+};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    safe_url::{parse_chunk_address, parse_register_address},
+    storage::{ChunkAddress, RegisterAddress},
+    NetworkAddress,
+};
 use std::{
     collections::BTreeMap,
     fs,
@@ -28,10 +34,17 @@ use std::{
     time::{Duration, Instant},
 };
 use tempfile::tempdir;
-use tokio::task::JoinHandle;
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
 use walkdir::WalkDir;
 use xor_name::XorName;
 
+/// Directory in the wallet dir holding the data maps of private uploads.
+const PRIVATE_DATA_MAPS_DIR: &str = "private_data_maps";
+/// File in the wallet dir listing the private uploads as `<data map id>: <file name>`.
+const PRIVATE_UPLOADED_FILES: &str = "private_uploaded_files";
+/// Directory in the wallet dir holding the journals and chunks of unfinished uploads.
+const UPLOAD_JOURNALS_DIR: &str = "upload_journals";
+
 #[derive(Parser, Debug)]
 pub enum FilesCmds {
     Upload {
@@ -42,12 +55,44 @@ pub enum FilesCmds {
         path: PathBuf,
         /// The batch_size to split chunks into parallely handling batches
         /// during payment and upload processing.
-        #[clap(long, default_value_t = BATCH_SIZE)]
+        #[clap(long, default_value_t = DEFAULT_BATCH_SIZE)]
         batch_size: usize,
         /// Flagging whether to show the holders of the uploaded chunks.
         /// Default to be not showing.
         #[clap(long, name = "show_holders", default_value = "false")]
         show_holders: bool,
+        /// Keep the data maps of the uploaded files off the network.
+        ///
+        /// The data maps are stored in the local wallet dir instead,
+        /// and are required to download the files again.
+        #[clap(long, default_value = "false")]
+        private: bool,
+        /// Resume a previously interrupted upload of the same path.
+        ///
+        /// Chunks which were already uploaded are skipped,
+        /// and payments still held in the wallet are reused.
+        #[clap(long, default_value = "false")]
+        resume: bool,
+    },
+    /// Estimate the cost of uploading a file or directory, without paying for it.
+    Estimate {
+        /// The location of the file(s) to estimate the upload cost of.
+        ///
+        /// Can be a file or a directory.
+        #[clap(name = "path", value_name = "PATH")]
+        path: PathBuf,
+    },
+    /// Upload a file and publish it as the latest version of a versioned file.
+    Publish {
+        /// The location of the file to publish.
+        #[clap(name = "path", value_name = "PATH")]
+        path: PathBuf,
+        /// The register holding the versions, as a `safe://register/` URL or a legacy hex string.
+        ///
+        /// Any other value is taken as the name of a register of yours,
+        /// which is created if it does not exist yet.
+        #[clap(long, value_name = "REGISTER")]
+        versioned: String,
     },
     Download {
         /// The name to apply to the downloaded file.
@@ -57,7 +102,9 @@ pub enum FilesCmds {
         /// If neither are, all the files uploaded by the current user will be downloaded again.
         #[clap(name = "name")]
         file_name: Option<String>,
-        /// The hex address of a file.
+        /// The address of a file, either as a `safe://chunk/` URL or as a legacy hex string.
+        ///
+        /// For private files, this is the id of the locally stored data map instead.
         ///
         /// If the address argument is used, the name argument must also be supplied.
         ///
@@ -68,6 +115,13 @@ pub enum FilesCmds {
         /// Default to be not showing.
         #[clap(long, name = "show_holders", default_value = "false")]
         show_holders: bool,
+        /// Treat the address as the root of an uploaded folder,
+        /// and download the whole tree into a directory with the given name.
+        #[clap(long, default_value = "false")]
+        folder: bool,
+        /// Treat the address as the id of a locally stored private data map.
+        #[clap(long, default_value = "false", conflicts_with = "folder")]
+        private: bool,
     },
 }
 
@@ -82,21 +136,35 @@ pub(crate) async fn files_cmds(
             path,
             batch_size,
             show_holders,
+            private,
+            resume,
         } => {
             upload_files(
                 path,
                 client,
                 wallet_dir_path,
                 verify_store,
-                batch_size,
-                show_holders,
+                UploadOptions {
+                    batch_size,
+                    show_holders,
+                    private,
+                    resume,
+                },
             )
             .await?
         }
+        FilesCmds::Estimate { path } => {
+            estimate_upload_cost(&path, client, wallet_dir_path).await?
+        }
+        FilesCmds::Publish { path, versioned } => {
+            publish_versioned_file(&path, &versioned, client, wallet_dir_path, verify_store).await?
+        }
         FilesCmds::Download {
             file_name,
             file_addr,
             show_holders,
+            folder,
+            private,
         } => {
             if (file_name.is_some() && file_addr.is_none())
                 || (file_addr.is_some() && file_name.is_none())
@@ -112,21 +180,37 @@ pub(crate) async fn files_cmds(
             let file_api: Files = Files::new(client, wallet_dir_path.to_path_buf());
 
             match (file_name, file_addr) {
-                (Some(name), Some(address)) => {
-                    let bytes = hex::decode(address).expect("Input address is not a hex string");
-                    download_file(
+                (Some(name), Some(id)) if private => {
+                    download_private_file(
                         &file_api,
-                        &XorName(
-                            bytes
-                                .try_into()
-                                .expect("Failed to parse XorName from hex string"),
-                        ),
+                        &id,
                         &name,
                         wallet_dir_path,
+                        wallet_dir_path,
                         show_holders,
                     )
                     .await
                 }
+                (Some(name), Some(address)) => {
+                    let address = parse_chunk_address(&address)
+                        .wrap_err("Could not parse the file address")
+                        .suggestion(
+                            "Provide the address as a `safe://chunk/` URL or a 64 character hex string",
+                        )?;
+                    if folder {
+                        download_folder(&file_api, address, &name, wallet_dir_path, show_holders)
+                            .await
+                    } else {
+                        download_file(
+                            &file_api,
+                            address.xorname(),
+                            &name,
+                            wallet_dir_path,
+                            show_holders,
+                        )
+                        .await
+                    }
+                }
                 _ => {
                     println!("Attempting to download all files uploaded by the current user...");
                     download_files(&file_api, wallet_dir_path, show_holders).await?
@@ -139,33 +223,34 @@ pub(crate) async fn files_cmds(
 
 /// Chunk all the files in the provided `files_path`
 /// `chunks_dir` is used to store the results of the self-encryption process
+/// If `private` is set, the data maps are not packed into chunks, and the files are
+/// keyed by the hash of their data map rather than their head address.
 pub(super) async fn chunk_path(
     file_api: &Files,
     files_path: &Path,
     chunks_dir: &Path,
+    private: bool,
 ) -> Result<BTreeMap<XorName, ChunkedFile>> {
     trace!("Starting to chunk {files_path:?} now.");
     let now = Instant::now();
 
-    let files_to_chunk = WalkDir::new(files_path)
-        .into_iter()
-        .flatten()
-        .filter_map(|entry| {
-            if !entry.file_type().is_file() {
-                return None;
-            }
+    let mut files_to_chunk = vec![];
+    for entry in WalkDir::new(files_path) {
+        // An unreadable entry fails the upload, rather than silently leaving files out.
+        let entry = entry.wrap_err_with(|| format!("Failed to walk {files_path:?}"))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
 
-            if let Some(file_name) = entry.file_name().to_str() {
-                Some((file_name.to_string(), entry.into_path()))
-            } else {
-                println!(
-                    "Skipping file {:?} as it is not valid UTF-8.",
-                    entry.file_name()
-                );
-                None
-            }
-        })
-        .collect::<Vec<_>>();
+        if let Some(file_name) = entry.file_name().to_str() {
+            files_to_chunk.push((file_name.to_string(), entry.into_path()));
+        } else {
+            println!(
+                "Skipping file {:?} as it is not valid UTF-8.",
+                entry.file_name()
+            );
+        }
+    }
 
     let total_files = files_to_chunk.len();
     let progress_bar = get_progress_bar(total_files as u64)?;
@@ -186,10 +271,19 @@ pub(super) async fn chunk_path(
                 }
             };
 
-            match file_api.chunk_file(path, &file_chunks_dir) {
-                Ok((file_addr, _size, chunks)) => {
+            let chunked = if private {
+                chunk_file_private(file_api, path, &file_chunks_dir)
+            } else {
+                file_api
+                    .chunk_file(path, &file_chunks_dir)
+                    .map(|(file_addr, _size, chunks)| (file_addr, chunks, None))
+                    .map_err(Error::from)
+            };
+
+            match chunked {
+                Ok((file_addr, chunks, private_data_map)) => {
                     progress_bar.clone().inc(1);
-                    Some((file_addr, ChunkedFile {file_name: file_name.clone(), chunks}))
+                    Some((file_addr, ChunkedFile {file_name: file_name.clone(), chunks, private_data_map}))
                 }
                 Err(err) => {
                     println!("Skipping file {path:?} as it could not be chunked: {err:?}");
@@ -209,6 +303,154 @@ pub(super) async fn chunk_path(
     Ok(chunked_files)
 }
 
+/// Chunk a file without packing its data map, returning the hash of the data map as its local id.
+fn chunk_file_private(
+    file_api: &Files,
+    path: &Path,
+    chunks_dir: &Path,
+) -> Result<(XorName, Vec<(XorName, PathBuf)>, Option<PrivateDataMap>)> {
+    let (data_map, chunks) = file_api.chunk_file_private(path, chunks_dir)?;
+    let id = XorName::from_content(&data_map.to_bytes()?);
+    Ok((id, chunks, Some(data_map)))
+}
+
+/// Print the cost of uploading `files_path`, with a breakdown per chunk.
+async fn estimate_upload_cost(
+    files_path: &Path,
+    client: Client,
+    wallet_dir_path: &Path,
+) -> Result<()> {
+    let file_api: Files = Files::new(client, wallet_dir_path.to_path_buf());
+    println!("Estimating the cost of uploading {files_path:?}...");
+
+    let estimate = file_api
+        .estimate_upload_cost(files_path)
+        .await
+        .wrap_err("Failed to estimate the upload cost")?;
+
+    println!("**************************************");
+    println!("*         Cost per chunk             *");
+    println!("**************************************");
+    for (name, cost) in estimate.chunk_costs.iter() {
+        println!("{}: {cost}", ChunkAddress::new(*name));
+    }
+    for name in estimate.failed_chunks.iter() {
+        println!("{}: no quote received", ChunkAddress::new(*name));
+    }
+
+    println!("**************************************");
+    println!("*         Estimated Cost             *");
+    println!("**************************************");
+    println!(
+        "Storage cost for {} chunks: {}",
+        estimate.chunk_costs.len(),
+        estimate.storage_cost
+    );
+    println!("Network royalties: {}", estimate.royalties_fees);
+    println!("Total: {}", estimate.total_cost()?);
+    if !estimate.failed_chunks.is_empty() {
+        println!(
+            "{} chunks could not be quoted and are not included in the total.",
+            estimate.failed_chunks.len()
+        );
+    }
+    info!(
+        "Estimated upload cost of {files_path:?}: {} for {} chunks",
+        estimate.total_cost()?,
+        estimate.chunk_costs.len()
+    );
+
+    Ok(())
+}
+
+/// Flags of the upload command, on top of the globally set `verify_store`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(super) struct UploadOptions {
+    pub(super) batch_size: usize,
+    pub(super) show_holders: bool,
+    pub(super) private: bool,
+    pub(super) resume: bool,
+}
+
+/// What an upload did, as reported to the user once it is done.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct UploadReport {
+    /// States of the chunks of the resumed session when it was picked up, if one was resumed.
+    resumed: Option<Vec<(ChunkUploadState, usize)>>,
+    summary: UploadSummary,
+    elapsed: Duration,
+    /// Chunks left to be uploaded by running the upload again with `--resume`.
+    unfinished: usize,
+    uploaded: Vec<UploadedEntry>,
+}
+
+/// An entry of an upload, as recorded in the wallet dir.
+#[derive(Debug, Serialize, Deserialize)]
+enum UploadedEntry {
+    File { path: String, address: ChunkAddress },
+    PrivateFile { path: String, id: String },
+    Folder { name: String, address: ChunkAddress },
+}
+
+/// Upload the file at `path` and publish it as the latest version of the versioned file
+/// backed by `register`, creating the register first if it is given by name and does not exist.
+async fn publish_versioned_file(
+    path: &Path,
+    register: &str,
+    client: Client,
+    wallet_dir_path: &Path,
+    verify_store: bool,
+) -> Result<()> {
+    if !path.is_file() {
+        bail!("{path:?} is not a file, only single files can be published as versions");
+    }
+    let file_api = Files::new(client.clone(), wallet_dir_path.to_path_buf())
+        .with_wallet_passphrase(wallet_passphrase(wallet_dir_path)?);
+
+    // Temp folder to hold SE chunks, which is cleaned up automatically once out of scope.
+    let chunks_dir = tempdir()?;
+    let (head_address, _size, chunks) = file_api.chunk_file(path, chunks_dir.path())?;
+    let cost = file_api.pay_and_upload_chunks(chunks, verify_store).await?;
+    let file_address = ChunkAddress::new(head_address);
+    println!("Uploaded {path:?} to {file_address} for {cost}");
+
+    let mut versioned_file = match parse_register_address(register) {
+        Ok(address) => VersionedFile::retrieve(&client, address)
+            .await
+            .wrap_err("Could not retrieve the register of the versioned file")?,
+        Err(_) => {
+            let meta = XorName::from_content(register.as_bytes());
+            let address = RegisterAddress::new(meta, client.signer_pk());
+            match VersionedFile::retrieve(&client, address).await {
+                Ok(versioned_file) => versioned_file,
+                Err(ClientError::Protocol(ProtocolError::RegisterNotFound(_))) => {
+                    debug!("Creating register '{register}' as it does not exist yet");
+                    let mut wallet_client = file_api.wallet()?;
+                    let (versioned_file, cost) =
+                        VersionedFile::create(&client, meta, &mut wallet_client, verify_store)
+                            .await?;
+                    println!("Created register '{register}' for {cost}");
+                    versioned_file
+                }
+                Err(error) => {
+                    return Err(error)
+                        .wrap_err("Could not retrieve the register of the versioned file")
+                }
+            }
+        }
+    };
+
+    versioned_file
+        .publish_version(file_address, verify_store)
+        .await?;
+    let versions = versioned_file.history()?.len();
+    println!(
+        "Published {path:?} as version {versions} of {}",
+        NetworkAddress::from_register_address(*versioned_file.address())
+    );
+    Ok(())
+}
+
 /// Given a file or directory, upload either the file or all the files in the directory. Optionally
 /// verify if the data was stored successfully.
 async fn upload_files(
@@ -216,313 +458,324 @@ async fn upload_files(
     client: Client,
     wallet_dir_path: &Path,
     verify_store: bool,
-    batch_size: usize,
-    show_holders: bool,
+    options: UploadOptions,
 ) -> Result<()> {
+    let file_api: Files = Files::new(client.clone(), wallet_dir_path.to_path_buf())
+        .with_wallet_passphrase(wallet_passphrase(wallet_dir_path)?);
+
+    let progress_bar = get_stored_chunks_progress_bar()?;
+    let progress_task = track_stored_chunks(&client, progress_bar.clone());
+    let result = upload_and_record(
+        &file_api,
+        &files_path,
+        wallet_dir_path,
+        verify_store,
+        &options,
+    )
+    .await;
+    progress_task.abort();
+    progress_bar.finish_and_clear();
+    let report = result
+        .wrap_err("Failed to upload the chunks")
+        .suggestion("Run the upload again with `--resume` to carry on from where it stopped")?;
+
+    print_upload_report(&files_path, &options, &report);
+    Ok(())
+}
+
+/// Upload the file, or the files of the directory, at `files_path` through an upload session
+/// kept in the wallet dir, then record what was uploaded in the wallet dir.
+///
+/// This is what the upload command runs, whether locally or through the daemon.
+pub(super) async fn upload_and_record(
+    file_api: &Files,
+    files_path: &Path,
+    wallet_dir_path: &Path,
+    verify_store: bool,
+    options: &UploadOptions,
+) -> Result<UploadReport> {
     debug!(
-        "Uploading file(s) from {:?}, will verify?: {verify_store}",
-        files_path
+        "Uploading file(s) from {files_path:?}, will verify?: {verify_store}, private?: {}",
+        options.private
     );
 
-    let file_api: Files = Files::new(client.clone(), wallet_dir_path.to_path_buf());
+    // The chunks are kept within the upload session dir until all of them are done,
+    // so that an interrupted upload can be resumed.
+    let journals_dir = wallet_dir_path.join(UPLOAD_JOURNALS_DIR);
+    let previous_journal = if options.resume {
+        UploadJournal::load(&journals_dir, files_path)?
+    } else {
+        None
+    };
+    let resumed = previous_journal
+        .as_ref()
+        .map(|journal| journal.summary().into_iter().collect());
+    let mut journal = match previous_journal {
+        Some(journal) => journal,
+        None => UploadJournal::create(&journals_dir, files_path)?,
+    };
 
-    // Temp folder to hold SE chunks, which is cleaned up automatically once out of scope.
-    let temp_dir = tempdir()?;
+    let now = Instant::now();
+    let (uploaded_content, summary) =
+        upload_content(file_api, files_path, &mut journal, verify_store, options).await?;
+    let elapsed = now.elapsed();
 
-    // Payment shall always be verified.
-    let chunked_files = chunk_path(&file_api, &files_path, temp_dir.path()).await?;
+    // Only once every chunk made it to the network the session is no longer needed.
+    let unfinished = journal.chunks_in_state(ChunkUploadState::Pending).len()
+        + journal.chunks_in_state(ChunkUploadState::Paid).len();
+    if unfinished == 0 {
+        journal.remove()?;
+    }
 
-    let uploaded_file_info = chunked_files
-        .iter()
-        .map(|(file_addr, chunked_file)| (*file_addr, chunked_file.file_name.clone()))
-        .collect::<Vec<_>>();
+    let uploaded = record_uploaded_content(files_path, wallet_dir_path, uploaded_content)?;
+    Ok(UploadReport {
+        resumed,
+        summary,
+        elapsed,
+        unfinished,
+        uploaded,
+    })
+}
 
-    let chunks_to_upload = chunked_files
-        .into_iter()
-        .flat_map(|(_, chunk)| chunk.chunks)
-        .collect::<Vec<_>>();
+/// Record the uploaded content in the wallet dir, so that it can be downloaded again later.
+fn record_uploaded_content(
+    files_path: &Path,
+    wallet_dir_path: &Path,
+    uploaded_content: UploadedContent,
+) -> Result<Vec<UploadedEntry>> {
+    let mut uploaded = vec![];
+    match uploaded_content {
+        UploadedContent::Folder { root_addr } => {
+            // Only the manifest is recorded, the files within it are downloaded through it.
+            let name = files_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| files_path.to_string_lossy().to_string());
+            uploaded.push(UploadedEntry::Folder {
+                name,
+                address: root_addr,
+            });
+        }
+        UploadedContent::Files(uploaded_files) => {
+            let private_data_maps_path = wallet_dir_path.join(PRIVATE_DATA_MAPS_DIR);
+            for (addr, file_name, private_data_map) in uploaded_files {
+                match private_data_map {
+                    Some(data_map) => {
+                        // The data map is only ever stored locally, under its id.
+                        let id = hex::encode(addr);
+                        fs::create_dir_all(&private_data_maps_path)?;
+                        fs::write(private_data_maps_path.join(&id), data_map.to_bytes()?)?;
+                        uploaded.push(UploadedEntry::PrivateFile {
+                            path: file_name,
+                            id,
+                        });
+                    }
+                    None => uploaded.push(UploadedEntry::File {
+                        path: file_name,
+                        address: ChunkAddress::new(addr),
+                    }),
+                }
+            }
+        }
+    }
 
-    let progress_bar = get_progress_bar(chunks_to_upload.len() as u64)?;
-    println!("Input was split into {} chunks", chunks_to_upload.len());
-    println!("Will now attempt to upload them...");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(wallet_dir_path.join("uploaded_files"))?;
+    let mut private_file = None;
+    for entry in uploaded.iter() {
+        match entry {
+            UploadedEntry::File { path, address } => writeln!(file, "{address}: {path}")?,
+            // A trailing separator marks the entry as a folder manifest.
+            UploadedEntry::Folder { name, address } => writeln!(file, "{address}: {name}/")?,
+            UploadedEntry::PrivateFile { path, id } => {
+                if private_file.is_none() {
+                    private_file = Some(
+                        fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(wallet_dir_path.join(PRIVATE_UPLOADED_FILES))?,
+                    );
+                }
+                if let Some(private_file) = private_file.as_mut() {
+                    writeln!(private_file, "{id}: {path}")?;
+                }
+            }
+        }
+    }
+    if let Some(mut private_file) = private_file {
+        private_file.flush()?;
+    }
+    file.flush()?;
 
-    let mut total_cost = NanoTokens::zero();
-    let mut final_balance = file_api.wallet()?.balance();
-    let now = Instant::now();
-    for chunks_batch in chunks_to_upload.chunks(batch_size) {
-        // pay for and verify payment... if we don't verify here, chunks uploads will surely fail
-        let (cost, new_balance) = match file_api
-            .pay_for_chunks(chunks_batch.iter().map(|(name, _)| *name).collect())
-            .await
-        {
-            Ok((cost, new_balance)) => (cost, new_balance),
-            Err(error) => {
-                warn!(
-                    "Failed to pay for chunks. Validation steps should retry this batch: {error}"
-                );
-                continue;
+    Ok(uploaded)
+}
+
+/// Print what the upload of `files_path` did.
+pub(super) fn print_upload_report(
+    files_path: &Path,
+    options: &UploadOptions,
+    report: &UploadReport,
+) {
+    match &report.resumed {
+        Some(states) => {
+            println!("Resumed the previous upload of {files_path:?}");
+            for (state, count) in states {
+                println!("{count} chunks were already {state:?}");
             }
-        };
-        final_balance = new_balance;
-        total_cost = total_cost
-            .checked_add(cost)
-            .ok_or_else(|| eyre!("Unable to add cost to total cost"))?;
-
-        // Verification will be carried out later on, if being asked to.
-        // Hence no need to carry out verification within the first attempt.
-        // Just to check there were no odd errors during upload.
-        for result in join_all(upload_chunks_in_parallel(
-            file_api.clone(),
-            chunks_batch.to_vec(),
-            false,
-            progress_bar.clone(),
-            show_holders,
-        ))
-        .await
-        {
-            let _ = result?;
         }
+        None if options.resume => {
+            println!("No previous upload of {files_path:?} to resume, started a new one");
+        }
+        None => {}
     }
 
-    progress_bar.finish_and_clear();
-    let elapsed = now.elapsed();
+    let summary = &report.summary;
     println!(
         "Uploaded {} chunks in {}",
-        chunks_to_upload.len(),
-        format_elapsed_time(elapsed)
+        summary.uploaded_chunks,
+        format_elapsed_time(report.elapsed)
     );
     info!(
         "Uploaded {} chunks in {}",
-        chunks_to_upload.len(),
-        format_elapsed_time(elapsed)
+        summary.uploaded_chunks,
+        format_elapsed_time(report.elapsed)
     );
     println!("**************************************");
     println!("*          Payment Details           *");
     println!("**************************************");
+    if summary.reused_payments > 0 {
+        println!(
+            "Reused existing payments for {} chunks",
+            summary.reused_payments
+        );
+    }
     println!(
-        "Made payment of {total_cost} for {} chunks",
-        chunks_to_upload.len()
+        "Made payment of {} for {} chunks",
+        summary.cost, summary.paid_chunks
     );
-    println!("New wallet balance: {final_balance}");
+    println!("New wallet balance: {}", summary.new_balance);
     info!(
-        "Made payment of {total_cost} for {} chunks",
-        chunks_to_upload.len()
+        "Made payment of {} for {} chunks",
+        summary.cost, summary.paid_chunks
     );
-    info!("New wallet balance: {final_balance}");
-
-    // If we are not verifying, we can skip this
-    if verify_store {
-        println!("**************************************");
-        println!("*            Verification            *");
-        println!("**************************************");
-
-        let mut data_to_verify_or_repay = chunks_to_upload;
-        while !data_to_verify_or_repay.is_empty() {
-            trace!(
-                "Verifying and potentially topping up payment of {:?} chunks",
-                data_to_verify_or_repay.len()
-            );
-            data_to_verify_or_repay = verify_and_repay_if_needed(
-                file_api.clone(),
-                data_to_verify_or_repay,
-                batch_size,
-                show_holders,
-            )
-            .await?;
-        }
+    if summary.existing_chunks > 0 {
+        println!(
+            "Skipped {} chunks already on the network, saving {}",
+            summary.existing_chunks, summary.saved_cost
+        );
+        info!(
+            "Skipped {} chunks already on the network, saving {}",
+            summary.existing_chunks, summary.saved_cost
+        );
+    }
+    info!("New wallet balance: {}", summary.new_balance);
+    if report.unfinished > 0 {
+        println!(
+            "{} chunks could not be uploaded. Run the upload again with `--resume` to retry them.",
+            report.unfinished
+        );
     }
-
-    progress_bar.finish_and_clear();
 
     println!("**************************************");
     println!("*          Uploaded Files            *");
     println!("**************************************");
-    let file_names_path = wallet_dir_path.join("uploaded_files");
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .append(true)
-        .open(file_names_path)?;
-    for (addr, file_name) in uploaded_file_info.iter() {
-        println!("Uploaded {} to {:x}", file_name, addr);
-        info!("Uploaded {} to {:x}", file_name, addr);
-        writeln!(file, "{:x}: {}", addr, file_name)?;
+    for entry in report.uploaded.iter() {
+        match entry {
+            UploadedEntry::File { path, address } => {
+                println!("Uploaded {path} to {address}");
+                info!("Uploaded {path} to {address}");
+            }
+            UploadedEntry::PrivateFile { path, id } => {
+                println!("Uploaded {path} privately, its data map is stored locally as {id}");
+                info!("Uploaded {path} privately with data map id {id}");
+            }
+            UploadedEntry::Folder { name, address } => {
+                println!("Uploaded folder {name} to {address}");
+                println!(
+                    "The whole folder can be downloaded with 'files download --folder <name> {address}'"
+                );
+                info!("Uploaded folder {name} to {address}");
+            }
+        }
     }
-    file.flush()?;
+}
 
-    Ok(())
+/// What an upload produced, to be reported and recorded once it is done.
+enum UploadedContent {
+    /// A directory, as the address of the manifest of its tree.
+    Folder { root_addr: ChunkAddress },
+    /// Individual files, as their address, or data map id if private, name and private data map.
+    Files(Vec<(XorName, String, Option<PrivateDataMap>)>),
 }
 
-/// Store all chunks from chunk_paths (assuming payments have already been made and are in our local wallet).
-/// If verify_store is true, we will attempt to fetch all chunks from the network and check they are stored.
-///
-/// This spawns a task for each chunk to be uploaded, returns those handles.
+/// Chunk the content at `files_path` into the session of `journal`, and upload it.
 ///
-fn upload_chunks_in_parallel(
-    file_api: Files,
-    chunks_paths: Vec<(XorName, PathBuf)>,
+/// Uploading a directory also stores a manifest of its tree, so that the whole tree can be
+/// downloaded from a single address. A manifest would expose the files of a private upload,
+/// hence those are uploaded as individual files.
+async fn upload_content(
+    file_api: &Files,
+    files_path: &Path,
+    journal: &mut UploadJournal,
     verify_store: bool,
-    progress_bar: ProgressBar,
-    show_holders: bool,
-) -> Vec<JoinHandle<Result<()>>> {
-    let mut upload_handles = Vec::new();
-    for (name, path) in chunks_paths.into_iter() {
-        let file_api = file_api.clone();
-        let progress_bar = progress_bar.clone();
-
-        // Spawn a task for each chunk to be uploaded
-        let handle = tokio::spawn(upload_chunk(
-            file_api,
-            (name, path),
-            verify_store,
-            progress_bar,
-            show_holders,
-        ));
-        upload_handles.push(handle);
+    options: &UploadOptions,
+) -> Result<(UploadedContent, UploadSummary)> {
+    if files_path.is_dir() && !options.private {
+        println!("Chunking and uploading the files of {files_path:?}...");
+        let (root_addr, _manifest, summary) = file_api
+            .upload_folder(
+                files_path,
+                journal,
+                options.batch_size,
+                verify_store,
+                options.show_holders,
+            )
+            .await?;
+        return Ok((UploadedContent::Folder { root_addr }, summary));
     }
 
-    // Return the handles immediately without awaiting their completion
-    upload_handles
-}
+    let chunked_files =
+        chunk_path(file_api, files_path, &journal.chunks_dir(), options.private).await?;
+    let mut uploaded_files = vec![];
+    let mut chunks = vec![];
+    for (file_addr, chunked_file) in chunked_files {
+        uploaded_files.push((
+            file_addr,
+            chunked_file.file_name,
+            chunked_file.private_data_map,
+        ));
+        chunks.extend(chunked_file.chunks);
+    }
+    println!("Input was split into {} chunks", chunks.len());
+    journal.add_chunks(chunks)?;
 
-/// Store chunks from chunk_paths (assuming payments have already been made and are in our local wallet).
-/// If verify_store is true, we will attempt to fetch the chunks from the network to verify it is stored.
-async fn upload_chunk(
-    file_api: Files,
-    chunk: (XorName, PathBuf),
-    verify_store: bool,
-    progress_bar: ProgressBar,
-    show_holders: bool,
-) -> Result<()> {
-    let (_, path) = chunk;
-    let chunk = Chunk::new(Bytes::from(fs::read(path)?));
-    file_api
-        .get_local_payment_and_upload_chunk(chunk, verify_store, show_holders)
+    let summary = file_api
+        .upload_journaled_chunks(
+            journal,
+            options.batch_size,
+            verify_store,
+            options.show_holders,
+        )
         .await?;
-    progress_bar.inc(1);
-    Ok(())
+    Ok((UploadedContent::Files(uploaded_files), summary))
 }
 
-/// Verify if chunks exist on the network.
-/// Repay if they don't.
-/// Return a list of files which had to be repaid, but are not yet reverified.
-async fn verify_and_repay_if_needed(
-    file_api: Files,
-    chunks_paths: Vec<(XorName, PathBuf)>,
-    batch_size: usize,
-    show_holders: bool,
-) -> Result<Vec<(XorName, PathBuf)>> {
-    let total_chunks = chunks_paths.len();
-
-    println!("{total_chunks} chunks to be checked and repaid if required");
-
-    let progress_bar = get_progress_bar(total_chunks as u64)?;
-    let now = Instant::now();
-    let mut failed_chunks = Vec::new();
-    for chunks_batch in chunks_paths.chunks(batch_size) {
-        // now we try and get batched chunks, keep track of any that fail
-        // Iterate over each uploaded chunk
-        let mut verify_handles = Vec::new();
-        for (name, path) in chunks_batch.iter().cloned() {
-            let file_api = file_api.clone();
-            let pb = progress_bar.clone();
-
-            // Spawn a new task to fetch each chunk concurrently
-            let handle = tokio::spawn(async move {
-                let chunk_address: ChunkAddress = ChunkAddress::new(name);
-                // make sure the chunk is stored
-                let res = file_api.client().verify_chunk_stored(chunk_address).await;
-
-                pb.inc(1);
-                Ok::<_, Error>(((chunk_address, path), res.is_err()))
-            });
-            verify_handles.push(handle);
-        }
-
-        // Await all fetch tasks and collect the results
-        let verify_results = join_all(verify_handles).await;
-
-        // Check for any errors during fetch
-        for result in verify_results {
-            if let ((chunk_addr, path), true) = result?? {
-                warn!("Failed to fetch a chunk {chunk_addr:?}");
-                // This needs to be NetAddr to allow for repayment
-                failed_chunks.push((chunk_addr, path));
+/// Advance `progress_bar` for every chunk the client stores, until the returned task is aborted.
+fn track_stored_chunks(client: &Client, progress_bar: ProgressBar) -> JoinHandle<()> {
+    let mut events = client.events_channel();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(ClientEvent::ChunksStored(chunks)) => progress_bar.inc(chunks.len() as u64),
+                Ok(_) => {}
+                // Missing some events only makes the progress bar lag behind.
+                Err(ClientError::EventsReceiver(RecvError::Lagged(_))) => {}
+                Err(_) => break,
             }
         }
-    }
-
-    progress_bar.finish_and_clear();
-    let elapsed = now.elapsed();
-    println!("Verified {total_chunks:?} chunks in {elapsed:?}");
-    let now = Instant::now();
-
-    let total_failed_chunks = failed_chunks
-        .iter()
-        .map(|(addr, path)| (*addr.xorname(), path.clone()))
-        .collect::<Vec<_>>();
-
-    if total_failed_chunks.is_empty() {
-        println!("Verification complete: all chunks paid and stored");
-        return Ok(total_failed_chunks);
-    }
-
-    let num_of_failed_chunks = failed_chunks.len();
-    println!("{num_of_failed_chunks} chunks were not stored. Repaying them in batches.");
-    let progress_bar = get_progress_bar(total_chunks as u64)?;
-
-    // If there were any failed chunks, we need to repay them
-    for failed_chunks_batch in failed_chunks.chunks(batch_size) {
-        let mut wallet = file_api.wallet()?;
-
-        // Now we pay again or top up, depending on the new current store cost is
-        match wallet
-            .pay_for_storage(
-                failed_chunks_batch
-                    .iter()
-                    .map(|(addr, _path)| sn_protocol::NetworkAddress::ChunkAddress(*addr)),
-            )
-            .await
-        {
-            Ok(_new_cost) => {}
-            Err(error) => {
-                error!("Failed to repay for record storage: {failed_chunks_batch:?}: {error:?}");
-            }
-        };
-
-        // outcome here is not important as we'll verify this later
-        let upload_file_api = file_api.clone();
-        let ongoing_uploads = upload_chunks_in_parallel(
-            upload_file_api,
-            failed_chunks_batch
-                .iter()
-                .cloned()
-                .map(|(addr, path)| (*addr.xorname(), path))
-                .collect(),
-            false,
-            progress_bar.clone(),
-            show_holders,
-        );
-
-        // Now we've batched all payments, we can await all uploads to happen in parallel
-        let upload_results = join_all(ongoing_uploads).await;
-
-        // lets check there were no odd errors during upload
-        for result in upload_results {
-            if let Err(error) = result? {
-                error!("Error uploading chunk during repayment: {error}");
-            };
-        }
-    }
-
-    let elapsed = now.elapsed();
-    println!(
-        "Repaid and re-uploaded {:?} chunks in {elapsed:?}",
-        chunks_paths.len()
-    );
-    println!("{total_failed_chunks:?} were not yet verified...");
-
-    Ok(total_failed_chunks)
+    })
 }
 
 async fn download_files(file_api: &Files, root_dir: &Path, show_holders: bool) -> Result<()> {
@@ -530,31 +783,63 @@ async fn download_files(file_api: &Files, root_dir: &Path, show_holders: bool) -
     let download_path = root_dir.join("downloaded_files");
     std::fs::create_dir_all(download_path.as_path())?;
 
-    let file = std::fs::File::open(&uploaded_files_path)?;
-    let reader = BufReader::new(file);
     let mut uploaded_files = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
+    let lines = if uploaded_files_path.exists() {
+        BufReader::new(std::fs::File::open(&uploaded_files_path)?)
+            .lines()
+            .collect::<std::io::Result<Vec<_>>>()?
+    } else {
+        vec![]
+    };
+    for line in lines {
         let parts: Vec<&str> = line.split(": ").collect();
 
         if parts.len() == 2 {
-            let xor_name_hex = parts[0];
+            // Entries written by older versions hold the hex xorname rather than the URL form.
+            let address = parse_chunk_address(parts[0])?;
             let file_name = parts[1];
 
-            let bytes = hex::decode(xor_name_hex)?;
-            let xor_name_bytes: [u8; 32] = bytes
-                .try_into()
-                .expect("Failed to parse XorName from hex string");
-            let xor_name = XorName(xor_name_bytes);
-
-            uploaded_files.push((xor_name, file_name.to_string()));
+            uploaded_files.push((*address.xorname(), file_name.to_string()));
         } else {
             println!("Skipping malformed line: {}", line);
         }
     }
 
     for (xorname, file_name) in uploaded_files.iter() {
-        download_file(file_api, xorname, file_name, &download_path, show_holders).await;
+        if let Some(folder_name) = file_name.strip_suffix('/') {
+            download_folder(
+                file_api,
+                ChunkAddress::new(*xorname),
+                folder_name,
+                &download_path,
+                show_holders,
+            )
+            .await;
+        } else {
+            download_file(file_api, xorname, file_name, &download_path, show_holders).await;
+        }
+    }
+
+    let private_uploaded_files_path = root_dir.join(PRIVATE_UPLOADED_FILES);
+    if private_uploaded_files_path.exists() {
+        let reader = BufReader::new(std::fs::File::open(&private_uploaded_files_path)?);
+        for line in reader.lines() {
+            let line = line?;
+            match line.split_once(": ") {
+                Some((id, file_name)) => {
+                    download_private_file(
+                        file_api,
+                        id,
+                        file_name,
+                        root_dir,
+                        &download_path,
+                        show_holders,
+                    )
+                    .await
+                }
+                None => println!("Skipping malformed line: {line}"),
+            }
+        }
     }
 
     Ok(())
@@ -578,17 +863,80 @@ async fn download_file(
     download_path: &Path,
     show_holders: bool,
 ) {
-    println!("Downloading {file_name} from {:64x}", xorname);
-    debug!("Downloading {file_name} from {:64x}", xorname);
+    let address = ChunkAddress::new(*xorname);
+    println!("Downloading {file_name} from {address}");
+    debug!("Downloading {file_name} from {address}");
     let downloaded_file_path = download_path.join(file_name);
-    match file_api
-        .read_bytes(
-            ChunkAddress::new(*xorname),
-            Some(downloaded_file_path.clone()),
-            show_holders,
-        )
-        .await
+    let now = Instant::now();
+    // Chunks are decrypted and written out as they arrive, rather than holding the whole file.
+    let result = file_api
+        .download_to_path(address, &downloaded_file_path, show_holders)
+        .await;
+    match result {
+        Ok(bytes_written) => {
+            debug!(
+                "Saved {file_name} ({bytes_written} bytes) at {} in {:?}",
+                downloaded_file_path.to_string_lossy(),
+                now.elapsed()
+            );
+            println!(
+                "Saved {file_name} ({bytes_written} bytes) at {} in {}",
+                downloaded_file_path.to_string_lossy(),
+                format_elapsed_time(now.elapsed())
+            );
+        }
+        Err(error) => {
+            error!("Error downloading {file_name:?}: {error}");
+            println!("Error downloading {file_name:?}: {error}")
+        }
+    }
+}
+
+/// Whether `id` has the form of a data map id, i.e. a hex encoded `XorName`.
+fn is_data_map_id(id: &str) -> bool {
+    id.len() == 2 * xor_name::XOR_NAME_LEN && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Download a private file using the data map stored under `id` in the wallet dir at `root_dir`.
+async fn download_private_file(
+    file_api: &Files,
+    id: &str,
+    file_name: &str,
+    root_dir: &Path,
+    download_path: &Path,
+    show_holders: bool,
+) {
+    // The id names a file within the data maps dir, so it must not be able to point elsewhere.
+    if !is_data_map_id(id) {
+        error!("Invalid private data map id {id:?} for {file_name}");
+        println!("Invalid private data map id {id:?} for {file_name}, expected 64 hex characters");
+        return;
+    }
+    println!("Downloading private file {file_name} using data map {id}");
+    debug!("Downloading private file {file_name} using data map {id}");
+    let downloaded_file_path = download_path.join(file_name);
+
+    let data_map_path = root_dir.join(PRIVATE_DATA_MAPS_DIR).join(id);
+    let data_map = match fs::read(&data_map_path)
+        .map_err(Error::from)
+        .and_then(|bytes| PrivateDataMap::from_bytes(&bytes).map_err(Error::from))
     {
+        Ok(data_map) => data_map,
+        Err(error) => {
+            error!("Error reading the private data map {data_map_path:?}: {error}");
+            println!("Error reading the private data map {data_map_path:?}: {error}");
+            return;
+        }
+    };
+
+    let result = match fs::create_dir_all(download_path) {
+        Ok(()) => file_api
+            .read_private(data_map, Some(downloaded_file_path.clone()), show_holders)
+            .await
+            .map_err(Error::from),
+        Err(error) => Err(error.into()),
+    };
+    match result {
         Ok(_) => {
             debug!(
                 "Saved {file_name} at {}",
@@ -600,12 +948,55 @@ async fn download_file(
             );
         }
         Err(error) => {
-            error!("Error downloading {file_name:?}: {error}");
-            println!("Error downloading {file_name:?}: {error}")
+            error!("Error downloading private file {file_name:?}: {error}");
+            println!("Error downloading private file {file_name:?}: {error}")
         }
     }
 }
 
+async fn download_folder(
+    file_api: &Files,
+    address: ChunkAddress,
+    folder_name: &str,
+    download_path: &Path,
+    show_holders: bool,
+) {
+    println!("Downloading folder {folder_name} from {address}");
+    debug!("Downloading folder {folder_name} from {address}");
+    let downloaded_folder_path = download_path.join(folder_name);
+    match file_api
+        .download_folder(address, &downloaded_folder_path, show_holders)
+        .await
+    {
+        Ok(manifest) => {
+            debug!(
+                "Saved {} files of folder {folder_name} at {}",
+                manifest.len(),
+                downloaded_folder_path.to_string_lossy()
+            );
+            println!(
+                "Saved {} files of folder {folder_name} at {}",
+                manifest.len(),
+                downloaded_folder_path.to_string_lossy()
+            );
+        }
+        Err(error) => {
+            error!("Error downloading folder {folder_name:?}: {error}");
+            println!("Error downloading folder {folder_name:?}: {error}")
+        }
+    }
+}
+
+fn get_stored_chunks_progress_bar() -> Result<ProgressBar> {
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} [{elapsed_precise}] {pos} chunks stored")?,
+    );
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    Ok(progress_bar)
+}
+
 fn get_progress_bar(length: u64) -> Result<ProgressBar> {
     let progress_bar = ProgressBar::new(length);
     progress_bar.set_style(
@@ -616,3 +1007,25 @@ fn get_progress_bar(length: u64) -> Result<ProgressBar> {
     progress_bar.enable_steady_tick(Duration::from_millis(100));
     Ok(progress_bar)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_upload_is_recorded_by_its_manifest_only() -> Result<()> {
+        let wallet_dir = tempdir()?;
+        let root_addr = ChunkAddress::new(XorName([1; 32]));
+
+        let uploaded = record_uploaded_content(
+            Path::new("/some/docs"),
+            wallet_dir.path(),
+            UploadedContent::Folder { root_addr },
+        )?;
+
+        assert_eq!(uploaded.len(), 1);
+        let recorded = fs::read_to_string(wallet_dir.path().join("uploaded_files"))?;
+        assert_eq!(recorded, format!("{root_addr}: docs/\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use color_eyre::{eyre::WrapErr, Result};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use percent_encoding::percent_decode_str;
+use serde_json::json;
+use sn_client::{Client, Error as ClientError, Files, OpenedFile};
+use sn_protocol::{
+    safe_url::{parse_chunk_address, parse_register_address},
+    storage::ChunkAddress,
+};
+use std::{convert::Infallible, net::SocketAddr, path::Path};
+
+// Files are fetched and sent out in segments of this size, so they are never held in memory whole.
+const SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+enum GatewayError {
+    BadRequest(String),
+    NotFound(String),
+    RangeNotSatisfiable(u64),
+    Network(ClientError),
+    Internal(String),
+}
+
+impl From<ClientError> for GatewayError {
+    fn from(error: ClientError) -> Self {
+        Self::Network(error)
+    }
+}
+
+impl From<hyper::http::Error> for GatewayError {
+    fn from(error: hyper::http::Error) -> Self {
+        Self::Internal(error.to_string())
+    }
+}
+
+type GatewayResult = std::result::Result<Response<Body>, GatewayError>;
+
+/// Serve data from the network over HTTP on `listen_addr`, until the process is stopped.
+///
+/// Routes:
+/// - `GET /chunk/<address>`: raw content of a chunk
+/// - `GET /file/<address>`: content of a file, honouring single `Range` requests
+/// - `GET /register/<address>`: entries of a register, as JSON
+/// - `GET /folder/<address>`: files of a folder manifest, as JSON
+/// - `GET /folder/<address>/<path>`: content of a file within a folder, honouring `Range`
+pub(crate) async fn gateway_cmd(
+    listen_addr: SocketAddr,
+    client: &Client,
+    root_dir: &Path,
+) -> Result<()> {
+    let files_api = Files::new(client.clone(), root_dir.to_path_buf());
+
+    let make_service = make_service_fn(move |_conn| {
+        let files_api = files_api.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let files_api = files_api.clone();
+                async move { Ok::<_, Infallible>(handle_request(&files_api, req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&listen_addr)
+        .wrap_err_with(|| format!("Failed to bind the gateway to {listen_addr}"))?
+        .serve(make_service);
+    info!("Gateway listening on http://{}", server.local_addr());
+    println!("Gateway listening on http://{}", server.local_addr());
+
+    server.await.wrap_err("The gateway stopped unexpectedly")?;
+    Ok(())
+}
+
+async fn handle_request(files_api: &Files, req: Request<Body>) -> Response<Body> {
+    let path = req.uri().path().to_string();
+    debug!("Gateway request: {} {path}", req.method());
+
+    route_request(files_api, &path, &req)
+        .await
+        .unwrap_or_else(|error| error_response(&path, error))
+}
+
+async fn route_request(files_api: &Files, path: &str, req: &Request<Body>) -> GatewayResult {
+    if req.method() != Method::GET {
+        return Err(GatewayError::BadRequest(
+            "Only GET requests are supported".to_string(),
+        ));
+    }
+
+    let path = path.trim_start_matches('/');
+    let (route, rest) = path.split_once('/').unwrap_or((path, ""));
+    match route {
+        "chunk" => serve_chunk(files_api, rest).await,
+        "file" => serve_file(files_api, parse_chunk(rest)?, req).await,
+        "register" => serve_register(files_api.client(), rest).await,
+        "folder" => serve_folder(files_api, rest, req).await,
+        _ => Err(GatewayError::NotFound(format!("Unknown route /{route}"))),
+    }
+}
+
+fn error_response(path: &str, error: GatewayError) -> Response<Body> {
+    let (status, message) = match error {
+        GatewayError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+        GatewayError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+        GatewayError::RangeNotSatisfiable(size) => {
+            let mut resp = Response::new(Body::from("Requested range not satisfiable"));
+            *resp.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            if let Ok(value) = format!("bytes */{size}").parse() {
+                let _ = resp.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            return resp;
+        }
+        GatewayError::Network(error) => (
+            StatusCode::BAD_GATEWAY,
+            format!("Failed to get the data from the network: {error}"),
+        ),
+        GatewayError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+    };
+    warn!("Gateway request for {path} failed with {status}: {message}");
+
+    let mut resp = Response::new(Body::from(message));
+    *resp.status_mut() = status;
+    resp
+}
+
+fn parse_chunk(address: &str) -> std::result::Result<ChunkAddress, GatewayError> {
+    parse_chunk_address(address)
+        .map_err(|err| GatewayError::BadRequest(format!("Invalid chunk address: {err}")))
+}
+
+async fn serve_chunk(files_api: &Files, address: &str) -> GatewayResult {
+    let address = parse_chunk(address)?;
+    let chunk = files_api.client().get_chunk(address, false).await?;
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, chunk.value().len())
+        .body(Body::from(chunk.value().clone()))?)
+}
+
+async fn serve_file(
+    files_api: &Files,
+    address: ChunkAddress,
+    req: &Request<Body>,
+) -> GatewayResult {
+    // The data map is fetched once, then used for both the size and every segment.
+    let file = files_api.open_file(address).await?;
+    let size = file.size();
+    let range = match req.headers().get(header::RANGE) {
+        Some(value) => {
+            let value = value
+                .to_str()
+                .map_err(|_| GatewayError::BadRequest("Invalid Range header".to_string()))?;
+            Some(parse_range(value, size).ok_or(GatewayError::RangeNotSatisfiable(size))?)
+        }
+        None => None,
+    };
+
+    let (start, end) = range.unwrap_or((0, size.saturating_sub(1)));
+    let len = if size == 0 { 0 } else { end - start + 1 };
+
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, len);
+    if range.is_some() {
+        builder = builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{size}"));
+    }
+
+    Ok(builder.body(stream_file(files_api.clone(), address, file, start, len))?)
+}
+
+/// Stream `len` bytes of the opened `file` at `address`, starting at `start`, one segment at a time.
+fn stream_file(
+    files_api: Files,
+    address: ChunkAddress,
+    file: OpenedFile,
+    start: u64,
+    len: u64,
+) -> Body {
+    let (mut sender, body) = Body::channel();
+
+    let _handle = tokio::spawn(async move {
+        let end = start + len;
+        let mut position = start;
+        while position < end {
+            let length = SEGMENT_SIZE.min(end - position);
+            let bytes = match files_api
+                .read_opened_file(&file, position as usize, length as usize)
+                .await
+            {
+                Ok(bytes) if !bytes.is_empty() => bytes,
+                Ok(_) => {
+                    warn!("File {address:?} ended at {position} before the expected {end} bytes");
+                    sender.abort();
+                    return;
+                }
+                Err(error) => {
+                    warn!("Failed to read file {address:?} at {position}: {error}");
+                    sender.abort();
+                    return;
+                }
+            };
+
+            position += bytes.len() as u64;
+            if sender.send_data(bytes).await.is_err() {
+                debug!("Gateway client went away while streaming {address:?}");
+                return;
+            }
+        }
+    });
+
+    body
+}
+
+/// Parse a single `bytes=` range against a file of `size` bytes, into inclusive `(start, end)`.
+///
+/// Returns `None` if the range can not be satisfied, including when several ranges are requested.
+fn parse_range(header: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let last = size.checked_sub(1)?;
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = if start.is_empty() {
+        // suffix range, i.e. the last `n` bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        (size.saturating_sub(suffix), last)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            last
+        } else {
+            end.parse::<u64>().ok()?.min(last)
+        };
+        (start, end)
+    };
+
+    (start <= end).then_some((start, end))
+}
+
+async fn serve_register(client: &Client, address: &str) -> GatewayResult {
+    let address = parse_register_address(address)
+        .map_err(|err| GatewayError::BadRequest(format!("Invalid register address: {err}")))?;
+    let register = client.get_register(address).await?;
+
+    let entries: Vec<_> = register
+        .read()
+        .into_iter()
+        .map(|(hash, entry)| {
+            json!({
+                "hash": hex::encode(hash.0),
+                "value": hex::encode(entry),
+            })
+        })
+        .collect();
+    let body = json!({
+        "address": address.to_string(),
+        "owner": hex::encode(register.owner().to_bytes()),
+        "entries": entries,
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))?)
+}
+
+async fn serve_folder(files_api: &Files, rest: &str, req: &Request<Body>) -> GatewayResult {
+    let (address, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let address = parse_chunk(address)?;
+    let manifest = files_api.get_folder_manifest(address).await?;
+
+    let path = percent_decode_str(path)
+        .decode_utf8()
+        .map_err(|_| GatewayError::BadRequest("Invalid path encoding".to_string()))?;
+    let path = path.trim_matches('/');
+
+    if path.is_empty() {
+        let entries: Vec<_> = manifest
+            .iter()
+            .map(|(path, entry)| {
+                json!({
+                    "path": path,
+                    "address": entry.address.to_string(),
+                    "size": entry.size,
+                })
+            })
+            .collect();
+        return Ok(Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "files": entries }).to_string()))?);
+    }
+
+    let entry = manifest
+        .get(path)
+        .ok_or_else(|| GatewayError::NotFound(format!("No file at {path} in the folder")))?;
+    serve_file(files_api, entry.address, req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_range;
+
+    #[test]
+    fn parse_range_handles_bounded_open_and_suffix_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=100-", 1000), Some((100, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        // Ranges are clamped to the end of the file.
+        assert_eq!(parse_range("bytes=900-5000", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-5000", 1000), Some((0, 999)));
+    }
+
+    #[test]
+    fn parse_range_rejects_unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=10-5", 1000), None);
+        assert_eq!(parse_range("bytes=-0", 1000), None);
+        assert_eq!(parse_range("bytes=0-10", 0), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-10", 1000), None);
+        assert_eq!(parse_range("bytes=a-b", 1000), None);
+    }
+}
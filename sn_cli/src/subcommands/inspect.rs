@@ -0,0 +1,81 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use chrono::{DateTime, Local};
+use color_eyre::{eyre::WrapErr, Help, Result};
+use sn_client::Client;
+use sn_protocol::{
+    safe_url::{parse_chunk_address, SAFE_URL_SCHEME},
+    NetworkAddress,
+};
+
+pub(crate) async fn inspect_cmd(address: String, client: &Client) -> Result<()> {
+    let address = parse_network_address(&address)?;
+    println!("Inspecting the close group of {address}...");
+
+    let infos = client.get_record_info(address).await?;
+    println!(
+        "{:<54} {:<5} {:<22} {:>8} {:<19} {:<8}",
+        "Holder", "Held", "Kind", "Size", "Stored at", "In range"
+    );
+
+    let mut failed = 0;
+    for info in infos {
+        let info = match info {
+            Ok(info) => info,
+            Err(err) => {
+                warn!("A holder failed to return record info: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        let holder = info
+            .holder
+            .as_peer_id()
+            .map(|peer_id| peer_id.to_string())
+            .unwrap_or_else(|| format!("{:?}", info.holder));
+        let kind = info
+            .kind
+            .map(|kind| format!("{kind:?}"))
+            .unwrap_or_else(|| "-".to_string());
+        let stored_at = info
+            .stored_at
+            .map(|time| {
+                DateTime::<Local>::from(time)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "-".to_string());
+
+        println!(
+            "{holder:<54} {:<5} {kind:<22} {:>8} {stored_at:<19} {:<8}",
+            info.is_held, info.size, info.within_distance_range
+        );
+    }
+
+    if failed > 0 {
+        println!("{failed} holder(s) did not respond.");
+    }
+
+    Ok(())
+}
+
+/// Accept any `safe://` URL, or a legacy hex string which is taken to be a chunk address.
+fn parse_network_address(address: &str) -> Result<NetworkAddress> {
+    if address.trim().starts_with(SAFE_URL_SCHEME) {
+        return address
+            .parse::<NetworkAddress>()
+            .wrap_err("Could not parse the address");
+    }
+
+    let chunk_address = parse_chunk_address(address)
+        .wrap_err("Could not parse the address")
+        .suggestion("Provide a `safe://` URL, or a 64 character hex string for a chunk")?;
+    Ok(NetworkAddress::from_chunk_address(chunk_address))
+}
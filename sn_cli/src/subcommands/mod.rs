@@ -5,12 +5,17 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+pub(crate) mod daemon;
 pub(crate) mod files;
+pub(crate) mod gateway;
 pub(crate) mod gossipsub;
+pub(crate) mod inspect;
+pub(crate) mod name;
 pub(crate) mod register;
 pub(crate) mod wallet;
 
 use clap::Subcommand;
+use std::net::SocketAddr;
 
 #[derive(Subcommand, Debug)]
 pub(super) enum SubCmd {
@@ -23,7 +28,34 @@ pub(super) enum SubCmd {
     #[clap(name = "register", subcommand)]
     /// Commands for register management
     Register(register::RegisterCmds),
+    #[clap(name = "name", subcommand)]
+    /// Commands for human readable names pointing to files, folders and registers
+    Name(name::NameCmds),
     #[clap(name = "gossipsub", subcommand)]
     /// Commands for gossipsub management
     Gossipsub(gossipsub::GossipsubCmds),
+    #[clap(name = "daemon")]
+    /// Keep a client connected and serve files, register and wallet commands over local JSON-RPC
+    ///
+    /// While it is running, other invocations of the CLI run their commands through it,
+    /// skipping the connection to the network.
+    Daemon {
+        /// The address to listen on. A random port is picked by default.
+        #[clap(long, default_value = "127.0.0.1:0")]
+        listen_addr: SocketAddr,
+    },
+    #[clap(name = "gateway")]
+    /// Serve chunks, files, registers and folders from the network over local HTTP
+    Gateway {
+        /// The address to listen on.
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        listen_addr: SocketAddr,
+    },
+    #[clap(name = "inspect")]
+    /// Ask every holder in the close group of an address what it knows about the record there
+    Inspect {
+        /// The address to inspect, as a `safe://` URL or a legacy hex chunk address.
+        #[clap(name = "address")]
+        address: String,
+    },
 }
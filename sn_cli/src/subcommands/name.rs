@@ -0,0 +1,140 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{register::parse_public_key, wallet::wallet_passphrase};
+use clap::Subcommand;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result, Section,
+};
+use sn_client::{name_register_address, name_url, Client, NameTarget, WalletClient};
+use sn_protocol::{
+    safe_url::{parse_chunk_address, parse_register_address, SAFE_URL_SCHEME},
+    NetworkAddress,
+};
+use sn_transfers::LocalWallet;
+use std::path::Path;
+
+#[derive(Subcommand, Debug)]
+pub enum NameCmds {
+    /// Register a new name pointing to a file, folder or register.
+    Register {
+        /// The name to register.
+        #[clap(name = "name")]
+        name: String,
+        /// The address the name points to, as a `safe://chunk/` or `safe://register/` URL.
+        #[clap(name = "target")]
+        target: String,
+        /// Treat a chunk address as the root of an uploaded folder rather than a file.
+        #[clap(long, default_value = "false")]
+        folder: bool,
+    },
+    /// Resolve a name to the address it currently points to.
+    Resolve {
+        /// The name to resolve, or its `safe://name/` URL as printed when it was registered.
+        #[clap(name = "name")]
+        name: String,
+        /// The hex encoded public key of the owner of the name. Defaults to your own key.
+        ///
+        /// Not needed when resolving a `safe://name/` URL, which carries its owner.
+        #[clap(long)]
+        owner: Option<String>,
+    },
+    /// Point one of your names to a new address.
+    Update {
+        /// The name to update.
+        #[clap(name = "name")]
+        name: String,
+        /// The new address the name points to, as a `safe://chunk/` or `safe://register/` URL.
+        #[clap(name = "target")]
+        target: String,
+        /// Treat a chunk address as the root of an uploaded folder rather than a file.
+        #[clap(long, default_value = "false")]
+        folder: bool,
+    },
+}
+
+pub(crate) async fn name_cmds(
+    cmds: NameCmds,
+    client: &Client,
+    root_dir: &Path,
+    verify_store: bool,
+) -> Result<()> {
+    match cmds {
+        NameCmds::Register {
+            name,
+            target,
+            folder,
+        } => {
+            let target = parse_target(&target, folder)?;
+            let wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())
+                .wrap_err_with(|| format!("Unable to read wallet file in {root_dir:?}"))
+                .suggestion(
+                    "If you have an old wallet file, it may no longer be compatible. Try removing it",
+                )?;
+            let mut wallet_client = WalletClient::new(client.clone(), wallet);
+
+            let cost = client
+                .register_name(&name, target, &mut wallet_client, verify_store)
+                .await?;
+            println!("Successfully registered '{name}' pointing to {target} for {cost:?}!");
+            println!(
+                "Others can resolve it at {}",
+                name_url(&name, client.signer_pk())
+            );
+        }
+        NameCmds::Resolve { name, owner } => {
+            let target = if name.starts_with(SAFE_URL_SCHEME) {
+                if owner.is_some() {
+                    bail!("The owner of a name is part of its URL, and can not be given as well");
+                }
+                client.resolve_name_url(&name).await?
+            } else {
+                let owner = match owner {
+                    Some(owner) => parse_public_key(&owner)?,
+                    None => client.signer_pk(),
+                };
+                client.resolve_name(&name, owner).await?
+            };
+            println!("'{name}' points to {target}");
+        }
+        NameCmds::Update {
+            name,
+            target,
+            folder,
+        } => {
+            let target = parse_target(&target, folder)?;
+            client.update_name(&name, target, verify_store).await?;
+            let address = name_register_address(&name, client.signer_pk());
+            println!(
+                "Updated '{name}' at {} to point to {target}",
+                NetworkAddress::from_register_address(address)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Parse the address a name points to, which is either a chunk or a register address.
+fn parse_target(target: &str, folder: bool) -> Result<NameTarget> {
+    if let Ok(address) = parse_chunk_address(target) {
+        return Ok(if folder {
+            NameTarget::Folder(address)
+        } else {
+            NameTarget::File(address)
+        });
+    }
+
+    match parse_register_address(target) {
+        Ok(_) if folder => bail!("A register address can not be the root of a folder"),
+        Ok(address) => Ok(NameTarget::Register(address)),
+        Err(_) => Err(eyre!("Could not parse the target address {target}")).suggestion(
+            "Provide the address as a `safe://chunk/` or `safe://register/` URL, or a legacy hex string",
+        ),
+    }
+}
@@ -6,13 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::wallet::wallet_passphrase;
 use bls::PublicKey;
 use clap::Subcommand;
-use color_eyre::{eyre::WrapErr, Result, Section};
-use sn_client::{Client, Error as ClientError, WalletClient};
-use sn_protocol::storage::RegisterAddress;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result, Section,
+};
+use libp2p::futures::{pin_mut, StreamExt};
+use sn_client::{Client, ClientRegister, Error as ClientError, WalletClient};
+use sn_protocol::{safe_url::parse_register_address, storage::RegisterAddress, NetworkAddress};
 use sn_transfers::LocalWallet;
-use std::path::Path;
+use std::{collections::BTreeSet, path::Path};
 use xor_name::XorName;
 
 #[derive(Subcommand, Debug)]
@@ -25,7 +30,7 @@ pub enum RegisterCmds {
         name: String,
     },
     Edit {
-        /// The address of the register to edit.
+        /// The address of the register to edit, as a `safe://register/` URL or a legacy hex string.
         #[clap(name = "address")]
         address: String,
         /// If you are the owner, the name of the register can be used as a shorthand to the address,
@@ -38,7 +43,7 @@ pub enum RegisterCmds {
         entry: String,
     },
     Get {
-        /// The register addresses to get.
+        /// The register addresses to get, as `safe://register/` URLs or legacy hex strings.
         #[clap(name = "addresses")]
         addresses: Vec<String>,
         /// If you are the owner, the name of the register can be used as a shorthand to the address,
@@ -47,6 +52,54 @@ pub enum RegisterCmds {
         #[clap(name = "name", short = 'n')]
         use_name: bool,
     },
+    /// Allow a user to write to one of your registers.
+    Grant {
+        /// The address of the register, as a `safe://register/` URL or a legacy hex string.
+        #[clap(name = "address")]
+        address: String,
+        /// The hex encoded public key of the user.
+        #[clap(name = "user")]
+        user: String,
+        /// Use this flag if you are providing the register name instead of the address
+        #[clap(name = "name", short = 'n')]
+        use_name: bool,
+    },
+    /// Stop allowing a user to write to one of your registers.
+    ///
+    /// Entries the user wrote before are kept.
+    Revoke {
+        /// The address of the register, as a `safe://register/` URL or a legacy hex string.
+        #[clap(name = "address")]
+        address: String,
+        /// The hex encoded public key of the user.
+        #[clap(name = "user")]
+        user: String,
+        /// Use this flag if you are providing the register name instead of the address
+        #[clap(name = "name", short = 'n')]
+        use_name: bool,
+    },
+    /// Print the whole history of a register as a graph, latest entries first.
+    Log {
+        /// The address of the register, as a `safe://register/` URL or a legacy hex string.
+        #[clap(name = "address")]
+        address: String,
+        /// If you are the owner, the name of the register can be used as a shorthand to the address,
+        /// as we can derive the address from the public key + name
+        /// Use this flag if you are providing the register name instead of the address
+        #[clap(name = "name", short = 'n')]
+        use_name: bool,
+    },
+    /// Print the entries written to a register as they are notified, until interrupted.
+    Watch {
+        /// The address of the register, as a `safe://register/` URL or a legacy hex string.
+        #[clap(name = "address")]
+        address: String,
+        /// If you are the owner, the name of the register can be used as a shorthand to the address,
+        /// as we can derive the address from the public key + name
+        /// Use this flag if you are providing the register name instead of the address
+        #[clap(name = "name", short = 'n')]
+        use_name: bool,
+    },
 }
 
 pub(crate) async fn register_cmds(
@@ -68,6 +121,34 @@ pub(crate) async fn register_cmds(
             addresses,
             use_name,
         } => get_registers(addresses, use_name, client).await?,
+        RegisterCmds::Grant {
+            address,
+            user,
+            use_name,
+        } => {
+            let user = parse_public_key(&user)?;
+            edit_permissions(address, use_name, client, verify_store, |register| {
+                register.grant_write(user)
+            })
+            .await?;
+            println!("Granted write access to {}", hex::encode(user.to_bytes()));
+        }
+        RegisterCmds::Revoke {
+            address,
+            user,
+            use_name,
+        } => {
+            let user = parse_public_key(&user)?;
+            edit_permissions(address, use_name, client, verify_store, |register| {
+                register.revoke_write(user)
+            })
+            .await?;
+            println!("Revoked write access of {}", hex::encode(user.to_bytes()));
+        }
+        RegisterCmds::Log { address, use_name } => log_register(address, use_name, client).await?,
+        RegisterCmds::Watch { address, use_name } => {
+            watch_register(address, use_name, client).await?
+        }
     }
     Ok(())
 }
@@ -79,7 +160,7 @@ async fn create_register(
     verify_store: bool,
 ) -> Result<()> {
     trace!("Starting to pay for Register storage");
-    let wallet = LocalWallet::load_from(root_dir)
+    let wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())
         .wrap_err("Unable to read wallet file in {path:?}")
         .suggestion(
             "If you have an old wallet file, it may no longer be compatible. Try removing it",
@@ -92,16 +173,11 @@ async fn create_register(
         .create_and_pay_for_register(meta, &mut wallet_client, verify_store)
         .await?;
 
+    let address = NetworkAddress::from_register_address(*register.address());
     if cost.is_zero() {
-        println!(
-            "Register '{name}' already exists at {}!",
-            register.address()
-        );
+        println!("Register '{name}' already exists at {address}!");
     } else {
-        println!(
-            "Successfully created register '{name}' at {} for {cost:?}!",
-            register.address()
-        );
+        println!("Successfully created register '{name}' at {address} for {cost:?}!");
     }
     Ok(())
 }
@@ -177,6 +253,90 @@ async fn get_registers(addresses: Vec<String>, use_name: bool, client: &Client)
     Ok(())
 }
 
+async fn edit_permissions(
+    address_str: String,
+    use_name: bool,
+    client: &Client,
+    verify_store: bool,
+    change: impl FnOnce(&mut ClientRegister) -> std::result::Result<(), ClientError>,
+) -> Result<()> {
+    let (address, printing_name) = parse_addr(&address_str, use_name, client.signer_pk())?;
+    println!("Trying to retrieve Register {printing_name}");
+    let mut register = client.get_register(address).await?;
+    if register.owner() != client.signer_pk() {
+        bail!("Only the owner of Register {printing_name} can change its permissions");
+    }
+
+    change(&mut register)?;
+    register.push(verify_store).await?;
+    Ok(())
+}
+
+async fn log_register(address_str: String, use_name: bool, client: &Client) -> Result<()> {
+    let (address, printing_name) = parse_addr(&address_str, use_name, client.signer_pk())?;
+    println!("Trying to retrieve Register {printing_name}");
+    let register = client.get_register(address).await?;
+
+    let latest: BTreeSet<_> = register.read().into_iter().map(|(hash, _)| hash).collect();
+    let history = register.history();
+    println!("Register {printing_name} has {} entries", history.len());
+
+    for (hash, bytes) in history.into_iter().rev() {
+        let data_str = match String::from_utf8(bytes.clone()) {
+            Ok(data_str) => data_str,
+            Err(_) => format!("{bytes:?}"),
+        };
+        let marker = if latest.contains(&hash) {
+            " (latest)"
+        } else {
+            ""
+        };
+        println!("* {}{marker} {data_str}", short_hash(&hash.0));
+
+        let children = register.children(hash)?;
+        if !children.is_empty() {
+            let children: Vec<_> = children.iter().map(|child| short_hash(&child.0)).collect();
+            println!("|  written atop {}", children.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+async fn watch_register(address_str: String, use_name: bool, client: &Client) -> Result<()> {
+    let (address, printing_name) = parse_addr(&address_str, use_name, client.signer_pk())?;
+    println!("Trying to retrieve Register {printing_name}");
+    let register = client.get_register(address).await?;
+
+    let entries = register.watch()?;
+    pin_mut!(entries);
+    println!("Watching Register {printing_name} for new entries...");
+    while let Some((hash, bytes)) = entries.next().await {
+        let data_str = match String::from_utf8(bytes.clone()) {
+            Ok(data_str) => data_str,
+            Err(_) => format!("{bytes:?}"),
+        };
+        println!("* {} {data_str}", short_hash(&hash.0));
+    }
+
+    println!("Stopped watching Register {printing_name}");
+    Ok(())
+}
+
+/// Abbreviated hex of an entry hash, for display.
+fn short_hash(hash: &[u8; 32]) -> String {
+    hex::encode(&hash[..4])
+}
+
+/// Parse a hex encoded public key.
+pub(super) fn parse_public_key(key: &str) -> Result<PublicKey> {
+    let bytes: [u8; bls::PK_SIZE] = hex::decode(key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| eyre!("{key} is not a hex encoded public key"))?;
+    PublicKey::from_bytes(bytes).wrap_err("Not a valid public key")
+}
+
 /// Parse str and return the address and the register info for printing
 fn parse_addr(
     address_str: &str,
@@ -187,15 +347,24 @@ fn parse_addr(
         debug!("Parsing address as name");
         let user_metadata = XorName::from_content(address_str.as_bytes());
         let addr = RegisterAddress::new(user_metadata, pk);
-        Ok((addr, format!("'{address_str}' at {addr}")))
+        Ok((
+            addr,
+            format!(
+                "'{address_str}' at {}",
+                NetworkAddress::from_register_address(addr)
+            ),
+        ))
     } else {
-        debug!("Parsing address as hex");
-        let addr = RegisterAddress::from_hex(address_str)
-            .wrap_err("Could not parse hex string")
+        debug!("Parsing address as URL or hex");
+        let addr = parse_register_address(address_str)
+            .wrap_err("Could not parse register address")
             .suggestion(
                 "If getting a register by name, use the `-n` flag eg:\n
         safe register get -n <register-name>",
             )?;
-        Ok((addr, format!("at {address_str}")))
+        Ok((
+            addr,
+            format!("at {}", NetworkAddress::from_register_address(addr)),
+        ))
     }
 }
@@ -8,8 +8,11 @@
 
 use bls::SecretKey;
 use clap::Parser;
-use color_eyre::{eyre::eyre, Result};
-use sn_client::{Client, Error as ClientError, Files};
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use sn_client::{Client, Error as ClientError, Files, PrivateDataMap, DEFAULT_BATCH_SIZE};
 use sn_transfers::{
     parse_main_pubkey, Error as TransferError, LocalWallet, MainSecretKey, NanoTokens, Transfer,
     WalletError,
@@ -25,8 +28,9 @@ use xor_name::XorName;
 
 use super::files::chunk_path;
 
-// Defines the size of batch for the parallel uploading of chunks and correspondent payments.
-pub(crate) const BATCH_SIZE: usize = 20;
+/// Environment variable the passphrase of an encrypted wallet is read from,
+/// instead of asking for it.
+const WALLET_PASSPHRASE_ENV: &str = "SAFE_WALLET_PASSPHRASE";
 
 // Please do not remove the blank lines in these doc comments.
 // They are used for inserting line breaks when the help menu is rendered in the UI.
@@ -66,6 +70,9 @@ pub enum WalletCmds {
         /// Hex-encoded main secret key
         #[clap(name = "sk")]
         sk: String,
+        /// Encrypt the main secret key with a passphrase, which is asked for.
+        #[clap(long)]
+        encrypt: bool,
     },
     /// Get tokens from a faucet.
     GetFaucet {
@@ -104,9 +111,16 @@ pub enum WalletCmds {
         path: PathBuf,
         /// The batch_size to split chunks into parallely handling batches
         /// during payment and upload processing.
-        #[clap(long, default_value_t = BATCH_SIZE)]
+        #[clap(long, default_value_t = DEFAULT_BATCH_SIZE)]
         batch_size: usize,
     },
+    /// Encrypt the main secret key of the wallet with a passphrase.
+    ///
+    /// The passphrase is then asked for by the commands loading the wallet,
+    /// unless it is set in the SAFE_WALLET_PASSPHRASE environment variable.
+    Encrypt,
+    /// Store the main secret key of the wallet in plain text again.
+    Decrypt,
 }
 
 pub(crate) async fn wallet_cmds_without_client(cmds: &WalletCmds, root_dir: &Path) -> Result<()> {
@@ -131,18 +145,29 @@ pub(crate) async fn wallet_cmds_without_client(cmds: &WalletCmds, root_dir: &Pat
             Ok(())
         }
         WalletCmds::Deposit { stdin, cash_note } => deposit(root_dir, *stdin, cash_note.clone()),
-        WalletCmds::Create { sk } => {
+        WalletCmds::Create { sk, encrypt } => {
             let main_sk = match SecretKey::from_hex(sk) {
                 Ok(sk) => MainSecretKey::new(sk),
                 Err(err) => return Err(eyre!("Failed to parse hex-encoded SK: {err:?}")),
             };
             let main_pubkey = main_sk.main_pubkey();
-            let local_wallet = LocalWallet::load_from_main_key(root_dir, main_sk)?;
+            let passphrase = if *encrypt {
+                if LocalWallet::is_encrypted(root_dir) {
+                    bail!("The wallet is already encrypted");
+                }
+                Some(ask_new_passphrase()?)
+            } else {
+                wallet_passphrase(root_dir)?
+            };
+            let local_wallet =
+                LocalWallet::load_from_main_key(root_dir, main_sk, passphrase.as_deref())?;
             let balance = local_wallet.balance();
             println!("Wallet created (balance {balance}) for main public key: {main_pubkey:?}.");
 
             Ok(())
         }
+        WalletCmds::Encrypt => encrypt(root_dir),
+        WalletCmds::Decrypt => decrypt(root_dir),
         cmd => Err(eyre!("{cmd:?} requires us to be connected to the Network")),
     }
 }
@@ -153,24 +178,44 @@ pub(crate) async fn wallet_cmds(
     wallet_dir_path: &Path,
     verify_store: bool,
 ) -> Result<()> {
+    // Every command here loads the wallet, so its passphrase is asked for once up front.
+    let passphrase = wallet_passphrase(wallet_dir_path)?;
     match cmds {
         WalletCmds::Send { amount, to } => {
-            send(amount, to, client, wallet_dir_path, verify_store).await?
+            send(
+                amount,
+                to,
+                client,
+                wallet_dir_path,
+                passphrase.as_deref(),
+                verify_store,
+            )
+            .await?
         }
         WalletCmds::Receive { file, transfer } => {
-            receive(transfer, file, client, wallet_dir_path).await?
+            receive(
+                transfer,
+                file,
+                client,
+                wallet_dir_path,
+                passphrase.as_deref(),
+            )
+            .await?
+        }
+        WalletCmds::GetFaucet { url } => {
+            get_faucet(wallet_dir_path, passphrase.as_deref(), client, url.clone()).await?
         }
-        WalletCmds::GetFaucet { url } => get_faucet(wallet_dir_path, client, url.clone()).await?,
         WalletCmds::Pay {
             path,
             batch_size: _,
         } => {
-            let file_api: Files = Files::new(client.clone(), wallet_dir_path.to_path_buf());
+            let file_api: Files = Files::new(client.clone(), wallet_dir_path.to_path_buf())
+                .with_wallet_passphrase(passphrase);
 
             // Temp folder to hold SE chunks, which is cleaned up automatically once out of scope.
             let temp_dir = tempdir()?;
 
-            let chunked_files = chunk_path(&file_api, &path, temp_dir.path()).await?;
+            let chunked_files = chunk_path(&file_api, &path, temp_dir.path(), false).await?;
 
             let all_chunks: Vec<_> = chunked_files
                 .values()
@@ -189,21 +234,74 @@ pub(crate) async fn wallet_cmds(
     Ok(())
 }
 
+/// Get the passphrase of the wallet if its main key is encrypted, from the
+/// `WALLET_PASSPHRASE_ENV` environment variable or else by asking for it.
+pub(crate) fn wallet_passphrase(root_dir: &Path) -> Result<Option<String>> {
+    if !LocalWallet::is_encrypted(root_dir) {
+        return Ok(None);
+    }
+    let passphrase = match std::env::var(WALLET_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password("Enter the passphrase of your wallet: ")?,
+    };
+    Ok(Some(passphrase))
+}
+
 fn address(root_dir: &Path) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir)?;
+    let wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())?;
     let address_hex = hex::encode(wallet.address().to_bytes());
     println!("{address_hex}");
     Ok(())
 }
 
 fn balance(root_dir: &Path) -> Result<NanoTokens> {
-    let wallet = LocalWallet::try_load_from(root_dir)?;
+    let wallet = LocalWallet::try_load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())?;
     let balance = wallet.balance();
     Ok(balance)
 }
 
-async fn get_faucet(root_dir: &Path, client: &Client, url: String) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir)?;
+fn encrypt(root_dir: &Path) -> Result<()> {
+    if LocalWallet::is_encrypted(root_dir) {
+        bail!("The wallet is already encrypted");
+    }
+    let wallet = LocalWallet::load_from(root_dir, None)?;
+
+    let passphrase = ask_new_passphrase()?;
+    wallet.encrypt_main_key(&passphrase)?;
+    println!("The main secret key of the wallet is now encrypted with your passphrase.");
+    Ok(())
+}
+
+/// Ask for a new passphrase for the wallet, twice to be sure it was typed as intended.
+fn ask_new_passphrase() -> Result<String> {
+    let passphrase = rpassword::prompt_password("Enter a new passphrase for the wallet: ")?;
+    if passphrase.is_empty() {
+        bail!("The passphrase can not be empty");
+    }
+    let confirmation = rpassword::prompt_password("Enter the passphrase again: ")?;
+    if passphrase != confirmation {
+        bail!("The passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+fn decrypt(root_dir: &Path) -> Result<()> {
+    if !LocalWallet::is_encrypted(root_dir) {
+        bail!("The wallet is not encrypted");
+    }
+    let wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())?;
+    wallet.decrypt_main_key()?;
+    println!("The main secret key of the wallet is now stored in plain text.");
+    Ok(())
+}
+
+async fn get_faucet(
+    root_dir: &Path,
+    passphrase: Option<&str>,
+    client: &Client,
+    url: String,
+) -> Result<()> {
+    let wallet = LocalWallet::load_from(root_dir, passphrase)?;
     let address_hex = hex::encode(wallet.address().to_bytes());
     let url = if !url.contains("://") {
         format!("{}://{}", "http", url)
@@ -217,7 +315,7 @@ async fn get_faucet(root_dir: &Path, client: &Client, url: String) -> Result<()>
     let is_ok = response.status().is_success();
     let body = response.text().await?;
     if is_ok {
-        receive(body, false, client, root_dir).await?;
+        receive(body, false, client, root_dir, passphrase).await?;
         println!("Successfully got tokens from faucet.");
     } else {
         println!(
@@ -237,7 +335,7 @@ fn deposit(root_dir: &Path, read_from_stdin: bool, cash_note: Option<String>) ->
         return deposit_from_cash_note_hex(root_dir, cash_note_hex);
     }
 
-    let mut wallet = LocalWallet::load_from(root_dir)?;
+    let mut wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())?;
 
     let previous_balance = wallet.balance();
 
@@ -264,7 +362,7 @@ fn read_cash_note_from_stdin(root_dir: &Path) -> Result<()> {
 }
 
 fn deposit_from_cash_note_hex(root_dir: &Path, input: String) -> Result<()> {
-    let mut wallet = LocalWallet::load_from(root_dir)?;
+    let mut wallet = LocalWallet::load_from(root_dir, wallet_passphrase(root_dir)?.as_deref())?;
     let cash_note = sn_transfers::CashNote::from_hex(input.trim())?;
 
     let old_balance = wallet.balance();
@@ -280,9 +378,10 @@ async fn send(
     to: String,
     client: &Client,
     root_dir: &Path,
+    passphrase: Option<&str>,
     verify_store: bool,
 ) -> Result<()> {
-    let from = LocalWallet::load_from(root_dir)?;
+    let from = LocalWallet::load_from(root_dir, passphrase)?;
     let amount = match NanoTokens::from_str(&amount) {
         Ok(amount) => amount,
         Err(_) => {
@@ -300,7 +399,7 @@ async fn send(
 
     let cash_note = match sn_client::send(from, amount, to, client, verify_store).await {
         Ok(cash_note) => {
-            let wallet = LocalWallet::load_from(root_dir)?;
+            let wallet = LocalWallet::load_from(root_dir, passphrase)?;
             println!("Sent {amount:?} to {to:?}");
             println!("New wallet balance is {}.", wallet.balance());
             cash_note
@@ -332,7 +431,13 @@ async fn send(
     Ok(())
 }
 
-async fn receive(transfer: String, is_file: bool, client: &Client, root_dir: &Path) -> Result<()> {
+async fn receive(
+    transfer: String,
+    is_file: bool,
+    client: &Client,
+    root_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<()> {
     let transfer = if is_file {
         std::fs::read_to_string(transfer)?.trim().to_string()
     } else {
@@ -350,7 +455,7 @@ async fn receive(transfer: String, is_file: bool, client: &Client, root_dir: &Pa
     println!("Successfully parsed transfer.");
 
     println!("Verifying transfer with the Network...");
-    let mut wallet = LocalWallet::load_from(root_dir)?;
+    let mut wallet = LocalWallet::load_from(root_dir, passphrase)?;
     let cashnotes = match client.receive(&transfer, &wallet).await {
         Ok(cashnotes) => cashnotes,
         Err(err) => {
@@ -371,4 +476,6 @@ async fn receive(transfer: String, is_file: bool, client: &Client, root_dir: &Pa
 pub(super) struct ChunkedFile {
     pub file_name: String,
     pub chunks: Vec<(XorName, PathBuf)>,
+    /// Set for private uploads, whose data map is kept locally instead of being uploaded.
+    pub private_data_map: Option<PrivateDataMap>,
 }
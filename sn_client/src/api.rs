@@ -6,9 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "open-metrics")]
+use super::metrics::ClientMetrics;
 use super::{
     error::{Error, Result},
-    Client, ClientEvent, ClientEventsChannel, ClientEventsReceiver, ClientRegister, WalletClient,
+    Client, ClientConfig, ClientEvent, ClientEventsChannel, ClientEventsReceiver, ClientRegister,
+    WalletClient,
 };
 use bls::{PublicKey, SecretKey, Signature};
 use indicatif::ProgressBar;
@@ -21,10 +24,10 @@ use libp2p::{
 use prometheus_client::registry::Registry;
 use sn_networking::{
     multiaddr_is_global, Error as NetworkError, GetQuorum, NetworkBuilder, NetworkEvent,
-    CLOSE_GROUP_SIZE,
 };
 use sn_protocol::{
     error::Error as ProtocolError,
+    messages::{Query, QueryResponse, RecordInfo, Request, Response},
     storage::{
         try_deserialize_record, try_serialize_record, Chunk, ChunkAddress, RecordHeader,
         RecordKind, RegisterAddress, SpendAddress,
@@ -41,15 +44,15 @@ use tokio::task::spawn;
 use tracing::trace;
 use xor_name::XorName;
 
-/// The timeout duration for the client to receive any response from the network.
-const INACTIVITY_TIMEOUT: std::time::Duration = tokio::time::Duration::from_secs(30);
+/// Wait after putting a record before verifying it is stored, to give its holders time to store it.
+const PUT_VERIFICATION_WAIT: Duration = Duration::from_millis(500);
 
 impl Client {
-    /// Instantiate a new client.
+    /// Instantiate a new client, tuned by the given `config`.
     pub async fn new(
         signer: SecretKey,
         peers: Option<Vec<Multiaddr>>,
-        req_response_timeout: Option<Duration>,
+        config: ClientConfig,
     ) -> Result<Self> {
         // If any of our contact peers has a global address, we'll assume we're in a global network.
         let local = match peers {
@@ -63,12 +66,17 @@ impl Client {
         let mut network_builder =
             NetworkBuilder::new(Keypair::generate_ed25519(), local, std::env::temp_dir());
 
-        if let Some(request_timeout) = req_response_timeout {
+        if let Some(request_timeout) = config.request_timeout() {
             network_builder.request_timeout(request_timeout);
         }
 
         #[cfg(feature = "open-metrics")]
-        network_builder.metrics_registry(Registry::default());
+        let metrics = {
+            let mut metrics_registry = Registry::default();
+            let metrics = ClientMetrics::new(&mut metrics_registry);
+            network_builder.metrics_registry(metrics_registry);
+            metrics
+        };
 
         let (network, mut network_event_receiver, swarm_driver) = network_builder.build_client()?;
         info!("Client constructed network and swarm_driver");
@@ -80,6 +88,9 @@ impl Client {
             signer,
             peers_added: 0,
             progress: Some(Self::setup_connection_progress()),
+            config,
+            #[cfg(feature = "open-metrics")]
+            metrics,
         };
 
         // subscribe to our events channel first, so we don't have intermittent
@@ -108,9 +119,10 @@ impl Client {
 
         // spawn task to wait for NetworkEvent and check for inactivity
         let mut client_clone = client.clone();
+        let inactivity_timeout = client.config.inactivity_timeout();
         let _event_handler = spawn(async move {
             loop {
-                match tokio::time::timeout(INACTIVITY_TIMEOUT, network_event_receiver.recv()).await
+                match tokio::time::timeout(inactivity_timeout, network_event_receiver.recv()).await
                 {
                     Ok(event) => {
                         let the_event = match event {
@@ -136,7 +148,7 @@ impl Client {
                         debug!("Client inactivity... waiting for a network event");
                         if let Err(error) = client_clone
                             .events_channel
-                            .broadcast(ClientEvent::InactiveClient(INACTIVITY_TIMEOUT))
+                            .broadcast(ClientEvent::InactiveClient(inactivity_timeout))
                         {
                             error!("Error broadcasting inactive client event: {error}");
                         }
@@ -145,10 +157,26 @@ impl Client {
             }
         });
 
-        // loop to connect to the network
+        // loop to connect to the network, giving up after the connection timeout if any
+        let connection_deadline = client
+            .config
+            .connection_timeout()
+            .map(|timeout| (timeout, tokio::time::Instant::now() + timeout));
         let mut is_connected = false;
         loop {
-            match client_events_rx.recv().await {
+            let event = match connection_deadline {
+                Some((timeout, deadline)) => {
+                    match tokio::time::timeout_at(deadline, client_events_rx.recv()).await {
+                        Ok(event) => event,
+                        Err(_elapsed) => {
+                            error!("Client could not connect to the network within {timeout:?}");
+                            return Err(Error::ConnectionTimeout(timeout));
+                        }
+                    }
+                }
+                None => client_events_rx.recv().await,
+            };
+            match event {
                 Ok(ClientEvent::ConnectedToNetwork) => {
                     is_connected = true;
                     info!("Client connected to the Network {is_connected:?}.");
@@ -207,7 +235,8 @@ impl Client {
                 // it may take some time to fill up the RT.
                 // To avoid such delay may fail the query with RecordNotFound,
                 // wait till certain amount of peers populated into RT
-                if self.peers_added >= CLOSE_GROUP_SIZE {
+                let min_peers = self.config.min_peers();
+                if self.peers_added >= min_peers {
                     if let Some(progress) = &self.progress {
                         progress.finish_with_message("Connected to the Network");
                         // Remove the progress bar
@@ -217,14 +246,11 @@ impl Client {
                     self.events_channel
                         .broadcast(ClientEvent::ConnectedToNetwork)?;
                 } else {
-                    debug!(
-                        "{}/{CLOSE_GROUP_SIZE} initial peers found.",
-                        self.peers_added
-                    );
+                    debug!("{}/{min_peers} initial peers found.", self.peers_added);
 
                     if let Some(progress) = &self.progress {
                         progress.set_message(format!(
-                            "{}/{CLOSE_GROUP_SIZE} initial peers found.",
+                            "{}/{min_peers} initial peers found.",
                             self.peers_added
                         ));
                     }
@@ -253,6 +279,12 @@ impl Client {
         self.events_channel.subscribe()
     }
 
+    // Broadcast an event reporting the progress of an operation.
+    // Having no one listening for progress is not an error.
+    pub(crate) fn broadcast_progress(&self, event: ClientEvent) {
+        let _ = self.events_channel.broadcast(event);
+    }
+
     /// Sign the given data
     pub fn sign<T: AsRef<[u8]>>(&self, data: T) -> Signature {
         self.signer.sign(data)
@@ -268,6 +300,11 @@ impl Client {
         self.signer.public_key()
     }
 
+    /// Return the config the client was instantiated with
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
     /// Get a register from network
     pub async fn get_signed_register_from_network(
         &self,
@@ -385,15 +422,61 @@ impl Client {
             None
         };
 
-        Ok(self
-            .network
-            .put_record(record, record_to_verify, expected_holders)
-            .await?)
+        self.put_record(record, record_to_verify, expected_holders)
+            .await
+    }
+
+    /// Put `record` to the network, retrying as per the put retry policy of the config.
+    ///
+    /// If `record_to_verify` or `expected_holders` are given, every put is followed by verifying
+    /// the record is stored, as per the verification retry policy of the config.
+    pub(crate) async fn put_record(
+        &self,
+        record: Record,
+        record_to_verify: Option<Record>,
+        expected_holders: HashSet<PeerId>,
+    ) -> Result<()> {
+        let pretty_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+        self.config
+            .put_retry()
+            .run("Putting record", || async {
+                self.network.put_record_once(record.clone()).await?;
+                if record_to_verify.is_none() && expected_holders.is_empty() {
+                    return Ok(());
+                }
+
+                tokio::time::sleep(PUT_VERIFICATION_WAIT).await;
+                trace!("attempting to verify {pretty_key:?}");
+                let _ = self
+                    .config
+                    .verification_retry()
+                    .run("Verifying record was stored", || async {
+                        Ok(self
+                            .network
+                            .get_record_from_network(
+                                record.key.clone(),
+                                record_to_verify.clone(),
+                                self.config.verification_quorum(),
+                                false,
+                                expected_holders.clone(),
+                            )
+                            .await?)
+                    })
+                    .await?;
+                Ok(())
+            })
+            .await
     }
 
     /// Retrieve a `Chunk` from the kad network.
     pub async fn get_chunk(&self, address: ChunkAddress, show_holders: bool) -> Result<Chunk> {
         info!("Getting chunk: {address:?}");
+        // Showing the holders requires going to the network anyway.
+        if !show_holders {
+            if let Some(chunk) = self.config.chunk_cache().and_then(|c| c.get(&address)) {
+                return Ok(chunk);
+            }
+        }
         let key = NetworkAddress::from_chunk_address(address).to_record_key();
 
         let expected_holders = if show_holders {
@@ -410,12 +493,27 @@ impl Client {
         };
 
         let record = self
-            .network
-            .get_record_from_network(key, None, GetQuorum::One, true, expected_holders)
+            .config
+            .get_retry()
+            .run("Getting chunk", || async {
+                Ok(self
+                    .network
+                    .get_record_from_network(
+                        key.clone(),
+                        None,
+                        self.config.get_quorum(),
+                        true,
+                        expected_holders.clone(),
+                    )
+                    .await?)
+            })
             .await?;
         let header = RecordHeader::from_record(&record)?;
         if let RecordKind::Chunk = header.kind {
             let chunk: Chunk = try_deserialize_record(&record)?;
+            if let Some(cache) = self.config.chunk_cache() {
+                cache.put(&chunk);
+            }
             Ok(chunk)
         } else {
             Err(ProtocolError::RecordKindMismatch(RecordKind::Chunk).into())
@@ -427,8 +525,20 @@ impl Client {
         info!("Verifying chunk: {address:?}");
         let key = NetworkAddress::from_chunk_address(address).to_record_key();
         let record = self
-            .network
-            .get_record_from_network(key, None, GetQuorum::All, false, Default::default())
+            .config
+            .verification_retry()
+            .run("Verifying record", || async {
+                Ok(self
+                    .network
+                    .get_record_from_network(
+                        key.clone(),
+                        None,
+                        self.config.verification_quorum(),
+                        false,
+                        Default::default(),
+                    )
+                    .await?)
+            })
             .await?;
         let header = RecordHeader::from_record(&record)?;
         if let RecordKind::Chunk = header.kind {
@@ -444,8 +554,20 @@ impl Client {
         info!("Verifying register: {address:?}");
         let key = NetworkAddress::from_register_address(address).to_record_key();
         let record = self
-            .network
-            .get_record_from_network(key, None, GetQuorum::All, false, Default::default())
+            .config
+            .verification_retry()
+            .run("Verifying record", || async {
+                Ok(self
+                    .network
+                    .get_record_from_network(
+                        key.clone(),
+                        None,
+                        self.config.verification_quorum(),
+                        false,
+                        Default::default(),
+                    )
+                    .await?)
+            })
             .await?;
 
         let header = RecordHeader::from_record(&record)?;
@@ -457,6 +579,36 @@ impl Client {
         }
     }
 
+    /// Ask each member of the close group of `address` what it knows about the record stored there.
+    ///
+    /// One result is returned per peer queried. Peers that failed to answer yield an error.
+    pub async fn get_record_info(
+        &self,
+        address: NetworkAddress,
+    ) -> Result<Vec<Result<RecordInfo>>> {
+        info!("Getting record info for {address:?}");
+        let close_group = self.network.get_closest_peers(&address, true).await?;
+        let request = Request::Query(Query::GetRecordInfo(address.clone()));
+
+        let responses = self
+            .network
+            .send_and_get_responses(close_group, &request, true)
+            .await;
+
+        let infos = responses
+            .into_iter()
+            .map(|response| match response? {
+                Response::Query(QueryResponse::GetRecordInfo(result)) => Ok(result?),
+                other => {
+                    warn!("Unexpected response to GetRecordInfo for {address:?}: {other:?}");
+                    Err(ProtocolError::RecordInfoNotAvailable(Box::new(address.clone())).into())
+                }
+            })
+            .collect();
+
+        Ok(infos)
+    }
+
     /// Send a `SpendCashNote` request to the network
     pub(crate) async fn network_store_spend(
         &self,
@@ -489,10 +641,8 @@ impl Client {
             (None, Default::default())
         };
 
-        Ok(self
-            .network
-            .put_record(record, record_to_verify, expected_holders)
-            .await?)
+        self.put_record(record, record_to_verify, expected_holders)
+            .await
     }
 
     /// Get a cash_note spend from network
@@ -0,0 +1,277 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::Result;
+use bytes::Bytes;
+use sn_protocol::storage::{Chunk, ChunkAddress};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use xor_name::XorName;
+
+/// On-disk cache of chunks fetched from the network, keyed by their `ChunkAddress`.
+///
+/// The cache is capped at `max_size` bytes and evicts the least recently used chunks first.
+/// As chunks are content addressed, every entry is checked against its xorname when read,
+/// and a corrupted entry is dropped instead of being returned.
+///
+/// Cloning is cheap and all the clones share the same cache.
+#[derive(Clone, Debug)]
+pub struct ChunkCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+#[derive(Debug)]
+struct CacheInner {
+    dir: PathBuf,
+    max_size: u64,
+    total_size: u64,
+    // Recency counter, bumped on every access.
+    clock: u64,
+    // Last access and size of each entry.
+    entries: HashMap<XorName, (u64, u64)>,
+    // Entries ordered by last access, oldest first.
+    lru: BTreeMap<u64, XorName>,
+}
+
+impl ChunkCache {
+    /// Open the cache in `dir`, picking up any chunks left there by previous runs.
+    pub fn open(dir: &Path, max_size: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        // Previously cached chunks are ranked by their modification time.
+        let mut existing = vec![];
+        for entry in fs::read_dir(dir)?.flatten() {
+            let Some(name) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| hex::decode(name).ok())
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .map(XorName)
+            else {
+                continue;
+            };
+            if let Ok(metadata) = entry.metadata() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                existing.push((modified, name, metadata.len()));
+            }
+        }
+        existing.sort();
+
+        let mut inner = CacheInner {
+            dir: dir.to_path_buf(),
+            max_size,
+            total_size: 0,
+            clock: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+        };
+        for (_, name, size) in existing {
+            inner.touch(name, size);
+        }
+        remove_files(inner.evict());
+        debug!(
+            "Opened chunk cache at {dir:?} holding {} chunks, {} bytes",
+            inner.entries.len(),
+            inner.total_size
+        );
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// Get a chunk from the cache, if present and intact.
+    pub fn get(&self, address: &ChunkAddress) -> Option<Chunk> {
+        let name = *address.xorname();
+        // The lock is only held to look up the entry, not while reading it from disk.
+        let (path, size) = {
+            let inner = self.inner.lock().ok()?;
+            let (_, size) = *inner.entries.get(&name)?;
+            (inner.path(&name), size)
+        };
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => Bytes::from(bytes),
+            Err(error) => {
+                warn!("Failed to read cached chunk {address:?}: {error}");
+                self.forget(&name);
+                return None;
+            }
+        };
+        if XorName::from_content(&bytes) != name {
+            warn!("Cached chunk {address:?} is corrupted, dropping it");
+            self.forget(&name);
+            return None;
+        }
+
+        if let Ok(mut inner) = self.inner.lock() {
+            // The entry may have been evicted while it was being read.
+            if inner.entries.contains_key(&name) {
+                inner.touch(name, size);
+            }
+        }
+        trace!("Chunk {address:?} served from the cache");
+        Some(Chunk::new(bytes))
+    }
+
+    /// Add a chunk to the cache, evicting the least recently used ones if needed.
+    pub fn put(&self, chunk: &Chunk) {
+        let name = *chunk.name();
+        let size = chunk.value().len() as u64;
+        let path = {
+            let Ok(inner) = self.inner.lock() else {
+                return;
+            };
+            if size > inner.max_size {
+                return;
+            }
+            (!inner.entries.contains_key(&name)).then(|| inner.path(&name))
+        };
+
+        if let Some(path) = path {
+            if let Err(error) = fs::write(path, chunk.value()) {
+                warn!("Failed to cache chunk {name:?}: {error}");
+                return;
+            }
+        }
+        let evicted = match self.inner.lock() {
+            Ok(mut inner) => {
+                inner.touch(name, size);
+                inner.evict()
+            }
+            Err(_) => return,
+        };
+        remove_files(evicted);
+    }
+
+    /// Number of chunks held in the cache.
+    pub fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .map(|inner| inner.entries.len())
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the cache holds no chunks.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Drop the entry of `name`, removing its file once the lock is released.
+    fn forget(&self, name: &XorName) {
+        let removed = match self.inner.lock() {
+            Ok(mut inner) => inner.remove(name),
+            Err(_) => return,
+        };
+        remove_files([removed]);
+    }
+}
+
+impl CacheInner {
+    fn path(&self, name: &XorName) -> PathBuf {
+        self.dir.join(hex::encode(name))
+    }
+
+    // Record an access to the entry, inserting it if it is new.
+    fn touch(&mut self, name: XorName, size: u64) {
+        self.clock += 1;
+        match self.entries.insert(name, (self.clock, size)) {
+            Some((last_access, _)) => {
+                let _ = self.lru.remove(&last_access);
+            }
+            None => self.total_size += size,
+        }
+        let _ = self.lru.insert(self.clock, name);
+    }
+
+    // Drop the entry, returning the file to remove.
+    // Files are removed by the callers, so that no disk I/O happens while holding the lock.
+    fn remove(&mut self, name: &XorName) -> PathBuf {
+        if let Some((last_access, size)) = self.entries.remove(name) {
+            let _ = self.lru.remove(&last_access);
+            self.total_size -= size;
+        }
+        self.path(name)
+    }
+
+    // Drop the least recently used entries until the cache fits, returning the files to remove.
+    fn evict(&mut self) -> Vec<PathBuf> {
+        let mut evicted = vec![];
+        while self.total_size > self.max_size {
+            let Some((_, oldest)) = self.lru.first_key_value().map(|(k, v)| (*k, *v)) else {
+                break;
+            };
+            evicted.push(self.remove(&oldest));
+        }
+        evicted
+    }
+}
+
+fn remove_files(paths: impl IntoIterator<Item = PathBuf>) {
+    for path in paths {
+        if let Err(error) = fs::remove_file(&path) {
+            trace!("Failed to remove cached chunk {path:?}: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_chunk(size: usize) -> Chunk {
+        let bytes: Vec<u8> = (0..size).map(|_| rand::random::<u8>()).collect();
+        Chunk::new(Bytes::from(bytes))
+    }
+
+    #[test]
+    fn least_recently_used_chunks_are_evicted() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ChunkCache::open(dir.path(), 300)?;
+
+        let first = random_chunk(100);
+        let second = random_chunk(100);
+        let third = random_chunk(100);
+        cache.put(&first);
+        cache.put(&second);
+        cache.put(&third);
+
+        // Reading the first makes the second the least recently used.
+        assert!(cache.get(first.address()).is_some());
+        cache.put(&random_chunk(100));
+
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get(first.address()).is_some());
+        assert!(cache.get(second.address()).is_none());
+        assert!(cache.get(third.address()).is_some());
+
+        // Entries survive re-opening the cache.
+        let reopened = ChunkCache::open(dir.path(), 300)?;
+        assert_eq!(reopened.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_entries_are_dropped() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ChunkCache::open(dir.path(), 1024)?;
+
+        let chunk = random_chunk(100);
+        cache.put(&chunk);
+        fs::write(dir.path().join(hex::encode(chunk.name())), b"tampered")?;
+
+        assert!(cache.get(chunk.address()).is_none());
+        assert!(cache.is_empty());
+        Ok(())
+    }
+}
@@ -47,11 +47,31 @@ pub(crate) fn encrypt_from_path(path: &Path, output_dir: &Path) -> Result<(XorNa
     Ok((address, encrypted_chunks))
 }
 
-#[allow(unused_assignments)]
 pub(crate) fn encrypt_large(
     file_path: &Path,
     output_dir: &Path,
 ) -> Result<(XorName, Vec<(XorName, PathBuf)>)> {
+    let (data_map, mut encrypted_chunks) = encrypt_large_private(file_path, output_dir)?;
+
+    // Pack the datamap into chunks that under the same output folder as well.
+    let (address, additional_chunks) = pack_data_map(data_map)?;
+    for chunk in additional_chunks.iter() {
+        let file_path = output_dir.join(&hex::encode(chunk.name()));
+        encrypted_chunks.push((*chunk.name(), file_path.to_path_buf()));
+        let mut output_file = File::create(file_path)?;
+        output_file.write_all(&chunk.value)?;
+    }
+
+    Ok((address, encrypted_chunks))
+}
+
+/// Self-encrypts the file into chunks written to `output_dir`, but does not pack the `DataMap`
+/// into a chunk. Without the returned `DataMap` the chunks can not be located or decrypted.
+#[allow(unused_assignments)]
+pub(crate) fn encrypt_large_private(
+    file_path: &Path,
+    output_dir: &Path,
+) -> Result<(DataMap, Vec<(XorName, PathBuf)>)> {
     let mut encryptor = StreamSelfEncryptor::encrypt_from_file(
         Box::new(file_path.to_path_buf()),
         Some(Box::new(output_dir.to_path_buf())),
@@ -69,7 +89,7 @@ pub(crate) fn encrypt_large(
         }
     }
     let data_map = data_map.unwrap();
-    let mut encrypted_chunks: Vec<_> = data_map
+    let encrypted_chunks: Vec<_> = data_map
         .infos()
         .iter()
         .map(|chunk_info| {
@@ -78,16 +98,7 @@ pub(crate) fn encrypt_large(
         })
         .collect();
 
-    // Pack the datamap into chunks that under the same output folder as well.
-    let (address, additional_chunks) = pack_data_map(data_map)?;
-    for chunk in additional_chunks.iter() {
-        let file_path = output_dir.join(&hex::encode(chunk.name()));
-        encrypted_chunks.push((*chunk.name(), file_path.to_path_buf()));
-        let mut output_file = File::create(file_path)?;
-        output_file.write_all(&chunk.value)?;
-    }
-
-    Ok((address, encrypted_chunks))
+    Ok((data_map, encrypted_chunks))
 }
 
 pub(crate) fn to_chunk(chunk_content: Bytes) -> Chunk {
@@ -0,0 +1,316 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, ChunkCache};
+use sn_networking::{GetQuorum, CLOSE_GROUP_SIZE};
+use std::{future::Future, time::Duration};
+
+/// Default number of chunks paid for, uploaded, verified or fetched concurrently.
+pub const DEFAULT_BATCH_SIZE: usize = 20;
+
+/// How many times an operation is attempted, and how long to wait in between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. Zero is treated as one.
+    pub max_attempts: usize,
+    /// Wait before the first retry, doubled on each following one.
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy making a single attempt.
+    pub const fn no_retries() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    /// A policy making up to `max_attempts` attempts, with exponential backoff in between.
+    pub const fn exponential(
+        max_attempts: usize,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Wait before the given retry, the first retry being `1`.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1) as u32)
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Run `operation` until it succeeds or all the attempts are used up,
+    /// returning the last error in the latter case.
+    pub(crate) async fn run<T, F, Fut>(&self, name: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(result) => return Ok(result),
+                Err(error) if attempt < max_attempts => {
+                    let backoff = self.backoff(attempt);
+                    debug!("{name} failed on attempt {attempt}/{max_attempts}, retrying in {backoff:?}: {error}");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Tunables of a `Client`, built with `ClientConfigBuilder`.
+///
+/// The defaults match the behaviour of the client before these became configurable.
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    connection_timeout: Option<Duration>,
+    inactivity_timeout: Duration,
+    request_timeout: Option<Duration>,
+    min_peers: usize,
+    get_retry: RetryPolicy,
+    put_retry: RetryPolicy,
+    verification_retry: RetryPolicy,
+    verify_store: bool,
+    get_quorum: GetQuorum,
+    verification_quorum: GetQuorum,
+    batch_size: usize,
+    chunk_cache: Option<ChunkCache>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connection_timeout: None,
+            inactivity_timeout: Duration::from_secs(30),
+            request_timeout: None,
+            min_peers: CLOSE_GROUP_SIZE,
+            get_retry: RetryPolicy::no_retries(),
+            // Puts failing to be verified are redone, as sn_networking used to do itself.
+            put_retry: RetryPolicy::exponential(3, Duration::from_secs(3), Duration::from_secs(3)),
+            verification_retry: RetryPolicy::no_retries(),
+            verify_store: true,
+            get_quorum: GetQuorum::One,
+            verification_quorum: GetQuorum::All,
+            batch_size: DEFAULT_BATCH_SIZE,
+            chunk_cache: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Start building a config from the defaults.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    /// How long `Client::new` waits to get connected before giving up, if at all.
+    pub fn connection_timeout(&self) -> Option<Duration> {
+        self.connection_timeout
+    }
+
+    /// How long without any network event before `ClientEvent::InactiveClient` is emitted.
+    pub fn inactivity_timeout(&self) -> Duration {
+        self.inactivity_timeout
+    }
+
+    /// Timeout of a request to a peer, the networking default being used if not set.
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout
+    }
+
+    /// Number of peers to know of before the client counts as connected.
+    pub fn min_peers(&self) -> usize {
+        self.min_peers
+    }
+
+    /// Retry policy when fetching data.
+    pub fn get_retry(&self) -> RetryPolicy {
+        self.get_retry
+    }
+
+    /// Retry policy when storing data, each attempt being verified if verification is requested.
+    pub fn put_retry(&self) -> RetryPolicy {
+        self.put_retry
+    }
+
+    /// Retry policy when verifying data was stored.
+    pub fn verification_retry(&self) -> RetryPolicy {
+        self.verification_retry
+    }
+
+    /// Whether stored data is verified by default.
+    pub fn verify_store(&self) -> bool {
+        self.verify_store
+    }
+
+    /// Quorum used when fetching chunks.
+    pub fn get_quorum(&self) -> GetQuorum {
+        self.get_quorum
+    }
+
+    /// Quorum used when verifying data was stored.
+    pub fn verification_quorum(&self) -> GetQuorum {
+        self.verification_quorum
+    }
+
+    /// Number of chunks paid for, uploaded, verified or fetched concurrently.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Cache of the chunks fetched from the network, if any.
+    pub fn chunk_cache(&self) -> Option<&ChunkCache> {
+        self.chunk_cache.as_ref()
+    }
+}
+
+/// Builder of `ClientConfig`, to be passed to `Client::new`.
+#[derive(Debug, Default)]
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+impl ClientConfigBuilder {
+    /// Give up connecting to the network after `timeout`.
+    pub fn connection_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Emit `ClientEvent::InactiveClient` after `timeout` without any network event.
+    pub fn inactivity_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.inactivity_timeout = timeout;
+        self
+    }
+
+    /// Timeout of a request to a peer.
+    pub fn request_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Number of peers to know of before the client counts as connected.
+    pub fn min_peers(&mut self, min_peers: usize) -> &mut Self {
+        self.config.min_peers = min_peers.max(1);
+        self
+    }
+
+    /// Retry policy when fetching data.
+    pub fn get_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.config.get_retry = policy;
+        self
+    }
+
+    /// Retry policy when storing data.
+    pub fn put_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.config.put_retry = policy;
+        self
+    }
+
+    /// Retry policy when verifying data was stored.
+    pub fn verification_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.config.verification_retry = policy;
+        self
+    }
+
+    /// Whether stored data is verified by default.
+    pub fn verify_store(&mut self, verify_store: bool) -> &mut Self {
+        self.config.verify_store = verify_store;
+        self
+    }
+
+    /// Quorum used when fetching chunks.
+    pub fn get_quorum(&mut self, quorum: GetQuorum) -> &mut Self {
+        self.config.get_quorum = quorum;
+        self
+    }
+
+    /// Quorum used when verifying data was stored.
+    pub fn verification_quorum(&mut self, quorum: GetQuorum) -> &mut Self {
+        self.config.verification_quorum = quorum;
+        self
+    }
+
+    /// Number of chunks paid for, uploaded, verified or fetched concurrently.
+    pub fn batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.config.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Cache the chunks fetched from the network.
+    pub fn chunk_cache(&mut self, chunk_cache: ChunkCache) -> &mut Self {
+        self.config.chunk_cache = Some(chunk_cache);
+        self
+    }
+
+    /// Finish building the config.
+    pub fn build(&self) -> ClientConfig {
+        self.config.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy =
+            RetryPolicy::exponential(5, Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(64), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn builder_setters_chain() {
+        let config = ClientConfig::builder()
+            .batch_size(0)
+            .min_peers(3)
+            .verify_store(false)
+            .request_timeout(Duration::from_secs(5))
+            .build();
+        assert_eq!(config.batch_size(), 1);
+        assert_eq!(config.min_peers(), 3);
+        assert!(!config.verify_store());
+        assert_eq!(config.request_timeout(), Some(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn run_gives_up_after_max_attempts() {
+        let policy = RetryPolicy::exponential(3, Duration::ZERO, Duration::ZERO);
+        let mut attempts = 0;
+        let result: Result<()> = policy
+            .run("test", || {
+                attempts += 1;
+                async { Err(crate::Error::AmountIsZero) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+}
@@ -9,6 +9,7 @@
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 use super::ClientEvent;
+use sn_protocol::{messages::RegisterCmd, storage::RegisterAddress};
 use sn_registers::{Entry, EntryHash};
 use std::collections::BTreeSet;
 use thiserror::Error;
@@ -60,9 +61,75 @@ pub enum Error {
     )]
     ContentBranchDetected(BTreeSet<(EntryHash, Entry)>),
 
+    #[error(
+        "The latest entries of the Register are not the expected ones, \
+        they have been updated to: {0:?}"
+    )]
+    RegisterWriteConflict(BTreeSet<EntryHash>),
+
+    #[error("Could not connect to the network within {0:?}")]
+    ConnectionTimeout(std::time::Duration),
+
     #[error("The provided amount contains zero nanos")]
     AmountIsZero,
     /// CashNote add would overflow
     #[error("Total price exceed possible token amount")]
     TotalPriceTooHigh,
+
+    #[error("The data is not a valid folder manifest")]
+    FolderManifestParsingFailed,
+
+    #[error("Path {0:?} can not be represented in a folder manifest")]
+    InvalidFolderPath(std::path::PathBuf),
+
+    #[error("The data is not a valid private data map")]
+    PrivateDataMapParsingFailed,
+
+    #[error("Failed to decrypt the private data map")]
+    PrivateDataMapDecryptionFailed,
+
+    #[error("The upload journal could not be parsed")]
+    UploadJournalParsingFailed,
+
+    #[error("Register entry {0:?} is not a valid file version")]
+    FileVersionParsingFailed(EntryHash),
+
+    #[error("Register entry {0:?} is not a valid register log entry")]
+    RegisterLogEntryParsingFailed(EntryHash),
+
+    #[error("The name '{0}' is not registered")]
+    NameNotRegistered(String),
+
+    #[error("The name '{0}' is already registered")]
+    NameAlreadyRegistered(String),
+
+    #[error("The target of the name could not be parsed")]
+    NameTargetParsingFailed,
+
+    #[error("{0:?} is not a valid name URL")]
+    NameUrlParsingFailed(String),
+
+    #[error("Register entry {0:?} is not a valid private register entry")]
+    PrivateRegisterEntryParsingFailed(EntryHash),
+
+    #[error("A key of private register {0:?} was not shared with this client")]
+    PrivateRegisterKeyNotShared(RegisterAddress),
+
+    #[error("Failed to encrypt the private register entry")]
+    PrivateRegisterEncryptionFailed,
+
+    #[error("Failed to decrypt private register entry {0:?}")]
+    PrivateRegisterDecryptionFailed(EntryHash),
+
+    #[error("No key epoch is left for private register {0:?}")]
+    PrivateRegisterEpochsExhausted(RegisterAddress),
+
+    #[error("Register entry {0:?} is not a valid register map operation")]
+    RegisterMapOpParsingFailed(EntryHash),
+
+    #[error("Register cmds were rejected by the network and dropped: {0:?}")]
+    RegisterCmdsRejected(Vec<(RegisterCmd, sn_registers::Error)>),
+
+    #[error("Failed to encode a register entry: {0}")]
+    EntryEncodingFailed(String),
 }
@@ -9,6 +9,9 @@
 use super::error::Result;
 
 use serde::Serialize;
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_registers::RegisterAddress;
+use sn_transfers::NanoTokens;
 use tokio::sync::broadcast;
 
 // Channel where events will be broadcasted by the client.
@@ -51,6 +54,45 @@ pub enum ClientEvent {
         #[debug(skip)]
         msg: Vec<u8>,
     },
+    /// Storage payments have been made for the given records
+    StoragePaid {
+        /// Addresses of the records paid for
+        addresses: Vec<NetworkAddress>,
+        /// Storage cost paid to the nodes
+        storage_cost: NanoTokens,
+        /// Network royalties paid on top of the storage cost
+        royalties_fees: NanoTokens,
+    },
+    /// A batch of chunks has been sent to their close groups.
+    ///
+    /// Chunk events are emitted per batch rather than per chunk, so that large uploads do not
+    /// overflow the channel and make other subscribers lag behind.
+    ChunksStored(Vec<ChunkAddress>),
+    /// A batch of stored chunks has been checked by fetching them back from the network
+    ChunksVerified {
+        /// Chunks which could be fetched back
+        verified: Vec<ChunkAddress>,
+        /// Chunks which could not be fetched back
+        failed: Vec<ChunkAddress>,
+    },
+    /// Chunks which failed verification are about to be paid for and uploaded again
+    Repayment {
+        /// Addresses of the chunks being repaid
+        chunks: Vec<ChunkAddress>,
+    },
+    /// A Register command has been accepted by the Register's close group
+    RegisterStored(RegisterAddress),
+    /// Progress of a streaming download, emitted once per batch of chunks fetched
+    DownloadProgress {
+        /// Head address of the file being downloaded
+        address: ChunkAddress,
+        /// Number of chunks fetched and written out so far
+        chunks_fetched: usize,
+        /// Total number of chunks of the file
+        total_chunks: usize,
+        /// Number of bytes written out so far
+        bytes_written: u64,
+    },
 }
 
 /// Receiver Channel where users of the public API can listen to events broadcasted by the client.
@@ -64,3 +106,38 @@ impl ClientEventsReceiver {
         Ok(event)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_BATCH_SIZE;
+    use tokio::sync::broadcast::error::RecvError;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn events_of_a_large_upload_do_not_lag() -> Result<()> {
+        let channel = ClientEventsChannel::default();
+        let mut receiver = channel.subscribe();
+
+        // Even if the subscriber is not reading yet, the upload of a thousand chunks only
+        // takes a fraction of the channel, where one event per chunk would overflow it.
+        let chunks: Vec<_> = (0..1_000)
+            .map(|_| ChunkAddress::new(XorName::random(&mut rand::thread_rng())))
+            .collect();
+        for batch in chunks.chunks(DEFAULT_BATCH_SIZE) {
+            channel.broadcast(ClientEvent::ChunksStored(batch.to_vec()))?;
+        }
+
+        let mut stored = 0;
+        while stored < chunks.len() {
+            match receiver.0.recv().await {
+                Ok(ClientEvent::ChunksStored(batch)) => stored += batch.len(),
+                Ok(other) => panic!("Unexpected event {other:?}"),
+                Err(RecvError::Lagged(missed)) => panic!("Missed {missed} events"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+        assert_eq!(stored, chunks.len());
+        Ok(())
+    }
+}
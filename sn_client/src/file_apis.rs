@@ -9,7 +9,7 @@
 use super::{
     chunks::{to_chunk, DataMapLevel, Error as ChunksError, SmallFile},
     error::{Error, Result},
-    Client, WalletClient,
+    Client, ClientEvent, WalletClient,
 };
 use bincode::deserialize;
 use bytes::Bytes;
@@ -17,20 +17,26 @@ use futures::{future::join_all, stream::FuturesOrdered, StreamExt};
 use itertools::Itertools;
 use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk, MIN_ENCRYPTABLE_BYTES};
 use self_encryption::{decrypt_full_set, StreamSelfDecryptor};
+use sn_networking::close_group_majority;
 use sn_protocol::{
+    messages::RecordInfo,
     storage::{Chunk, ChunkAddress},
     NetworkAddress, PrettyPrintRecordKey,
 };
-use sn_transfers::{LocalWallet, NanoTokens};
+use sn_transfers::{LocalWallet, NanoTokens, NETWORK_ROYALTIES_AMOUNT_PER_ADDR};
 
 use std::{
     fs::{self, create_dir_all, File},
+    future::Future,
     io::{Read, Write},
     path::{Path, PathBuf},
     time::Instant,
 };
 use tempfile::tempdir;
-use tokio::task;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    task,
+};
 use tracing::trace;
 use xor_name::XorName;
 
@@ -39,17 +45,44 @@ use xor_name::XorName;
 pub struct Files {
     client: Client,
     wallet_dir: PathBuf,
+    wallet_passphrase: Option<String>,
 }
 
 type ChunkFileResult = Result<(XorName, u64, Vec<(XorName, PathBuf)>)>;
 
-// Defines the size of batch for the parallel downloading of chunks.
-const BATCH_SIZE: usize = 20;
+/// A file whose head chunk was fetched, as returned by `Files::open_file`.
+#[derive(Clone, Debug)]
+pub enum OpenedFile {
+    /// A file small enough to be stored in its head chunk, along with its content.
+    Small(Bytes),
+    /// A self-encrypted file, along with its data map.
+    Large(DataMap),
+}
+
+impl OpenedFile {
+    /// Size in bytes of the content of the file.
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Small(bytes) => bytes.len() as u64,
+            Self::Large(data_map) => data_map.file_size() as u64,
+        }
+    }
+}
 
 impl Files {
     /// Create file apis instance.
     pub fn new(client: Client, wallet_dir: PathBuf) -> Self {
-        Self { client, wallet_dir }
+        Self {
+            client,
+            wallet_dir,
+            wallet_passphrase: None,
+        }
+    }
+
+    /// Set the passphrase the main key of the wallet is decrypted with, if it is encrypted.
+    pub fn with_wallet_passphrase(mut self, passphrase: Option<String>) -> Self {
+        self.wallet_passphrase = passphrase;
+        self
     }
 
     /// Return the client instance
@@ -60,7 +93,7 @@ impl Files {
     /// Create a new WalletClient for a given root directory.
     pub fn wallet(&self) -> Result<WalletClient> {
         let path = self.wallet_dir.as_path();
-        let wallet = LocalWallet::load_from(path)?;
+        let wallet = LocalWallet::load_from(path, self.wallet_passphrase.as_deref())?;
 
         Ok(WalletClient::new(self.client.clone(), wallet))
     }
@@ -89,6 +122,72 @@ impl Files {
         }
     }
 
+    /// Streams a file from the network into `writer`, returning the number of bytes written.
+    ///
+    /// Chunks are fetched in order with at most `ClientConfig::batch_size` requests in flight,
+    /// and each one is decrypted and written out as soon as it arrives, so the whole file is
+    /// never held in memory.
+    /// Progress is broadcast as `ClientEvent::DownloadProgress`.
+    pub async fn read_to_writer<W: AsyncWrite + Unpin>(
+        &self,
+        address: ChunkAddress,
+        writer: &mut W,
+        show_holders: bool,
+    ) -> Result<u64> {
+        let chunk = self.client.get_chunk(address, show_holders).await?;
+
+        // first try to deserialize a LargeFile, if it works, we go and stream it
+        let bytes_written = if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
+            self.stream_data_map(address, data_map, writer, show_holders)
+                .await?
+        } else {
+            // if an error occurs, we assume it's a SmallFile
+            writer.write_all(chunk.value()).await?;
+            self.broadcast_download_progress(address, 1, 1, chunk.value().len() as u64);
+            chunk.value().len() as u64
+        };
+        writer.flush().await?;
+
+        Ok(bytes_written)
+    }
+
+    /// Stream the file stored at `address` into a file at `path`, as `read_to_writer` does.
+    ///
+    /// The content is written to a temp file next to `path`, which is only renamed to `path`
+    /// once the whole file was written, so a failed download never leaves a truncated file.
+    pub async fn download_to_path(
+        &self,
+        address: ChunkAddress,
+        path: &Path,
+        show_holders: bool,
+    ) -> Result<u64> {
+        let file_name = path.file_name().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{path:?} does not name a file"),
+            )
+        })?;
+        let mut tmp_name = file_name.to_os_string();
+        tmp_name.push(".part");
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        let result = self.read_to_writer(address, &mut file, show_holders).await;
+        drop(file);
+        match result {
+            Ok(bytes_written) => {
+                tokio::fs::rename(&tmp_path, path).await?;
+                Ok(bytes_written)
+            }
+            Err(error) => {
+                if let Err(remove_error) = tokio::fs::remove_file(&tmp_path).await {
+                    warn!("Failed to remove the partial download {tmp_path:?}: {remove_error}");
+                }
+                Err(error)
+            }
+        }
+    }
+
     /// Read bytes from the network. The contents are spread across
     /// multiple chunks in the network. This function invokes the self-encryptor and returns
     /// the data that was initially stored.
@@ -104,23 +203,52 @@ impl Files {
         length: usize,
     ) -> Result<Bytes> {
         trace!("Reading {length} bytes at: {address:?}, starting from position: {position}");
+        let file = self.open_file(address).await?;
+        self.read_opened_file(&file, position, length).await
+    }
+
+    /// Fetch the head chunk of the file at `address`, resolving its data map if it has one.
+    ///
+    /// The returned file can then be read from any number of times, with `read_opened_file`,
+    /// without fetching its data map again.
+    pub async fn open_file(&self, address: ChunkAddress) -> Result<OpenedFile> {
         let chunk = self.client.get_chunk(address, false).await?;
 
         // First try to deserialize a LargeFile, if it works, we go and seek it.
         // If an error occurs, we consider it to be a SmallFile.
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            return self.seek(data_map, position, length).await;
-        }
-
-        // The error above is ignored to avoid leaking the storage format detail of SmallFiles and LargeFiles.
+        // The error is ignored to avoid leaking the storage format detail of SmallFiles and LargeFiles.
         // The basic idea is that we're trying to deserialize as one, and then the other.
         // The cost of it is that some errors will not be seen without a refactor.
-        let mut bytes = chunk.value().clone();
+        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
+            Ok(OpenedFile::Large(data_map))
+        } else {
+            Ok(OpenedFile::Small(chunk.value().clone()))
+        }
+    }
 
-        let _ = bytes.split_to(position);
-        bytes.truncate(length);
+    /// Read up to `length` bytes of an opened file, starting at `position`.
+    pub async fn read_opened_file(
+        &self,
+        file: &OpenedFile,
+        position: usize,
+        length: usize,
+    ) -> Result<Bytes> {
+        match file {
+            OpenedFile::Large(data_map) => self.seek(data_map.clone(), position, length).await,
+            OpenedFile::Small(bytes) => {
+                let mut bytes = bytes.clone();
+                let _ = bytes.split_to(position.min(bytes.len()));
+                bytes.truncate(length);
+                Ok(bytes)
+            }
+        }
+    }
 
-        Ok(bytes)
+    /// Size in bytes of the file stored at `address`.
+    ///
+    /// Only the chunks holding the data map are fetched, not the content of the file.
+    pub async fn file_size(&self, address: ChunkAddress) -> Result<u64> {
+        Ok(self.open_file(address).await?.size())
     }
 
     /// Tries to chunk the file, returning `(head_address, file_size, chunk_names)`
@@ -209,6 +337,75 @@ impl Files {
         Ok((cost, new_balance))
     }
 
+    /// Split the given chunks into those which still have to be paid for and uploaded, and those
+    /// already stored by a majority of their close group, which can be skipped altogether.
+    ///
+    /// Each skipped chunk comes along with what storing it would have cost, as quoted by its
+    /// close group while being checked, royalties included.
+    /// The close groups of `ClientConfig::batch_size` chunks are queried at a time.
+    /// A chunk whose holders could not be queried is considered missing.
+    pub async fn split_existing_chunks(
+        &self,
+        chunks: Vec<(XorName, PathBuf)>,
+    ) -> Result<(Vec<(XorName, PathBuf)>, Vec<(XorName, NanoTokens)>)> {
+        split_chunks_by_existence(chunks, self.client.config().batch_size(), |name| {
+            self.client
+                .get_record_info(NetworkAddress::from_chunk_address(ChunkAddress::new(name)))
+        })
+        .await
+    }
+
+    /// Pay for and upload the given chunks from their paths on disk.
+    ///
+    /// Chunks which already exist on the network are neither paid for nor uploaded.
+    /// If `verify_store` is set, the chunks are fetched back and any that are missing
+    /// are repaid and uploaded again until all of them are verified.
+    /// Returns the total cost paid.
+    pub async fn pay_and_upload_chunks(
+        &self,
+        chunks: Vec<(XorName, PathBuf)>,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        let (chunks, existing_chunks) = self.split_existing_chunks(chunks).await?;
+        if !existing_chunks.is_empty() {
+            info!(
+                "Skipping {} chunks which already exist on the network",
+                existing_chunks.len()
+            );
+        }
+
+        let (mut total_cost, _new_balance) = self
+            .pay_for_chunks(chunks.iter().map(|(name, _)| *name).collect())
+            .await?;
+        for result in self.upload_chunks_from_paths(&chunks, false).await {
+            result?;
+        }
+
+        if verify_store {
+            let mut failed_chunks = self
+                .verify_uploaded_chunks(chunks, self.client.config().batch_size())
+                .await?;
+            while !failed_chunks.is_empty() {
+                info!("Repaying for {} chunks", failed_chunks.len());
+                self.broadcast_repayment(&failed_chunks);
+                let (cost, _new_balance) = self
+                    .pay_for_chunks(failed_chunks.iter().map(|(name, _)| *name).collect())
+                    .await?;
+                total_cost = total_cost
+                    .checked_add(cost)
+                    .ok_or(Error::TotalPriceTooHigh)?;
+                for result in self.upload_chunks_from_paths(&failed_chunks, false).await {
+                    result?;
+                }
+                failed_chunks = self
+                    .verify_uploaded_chunks(failed_chunks, self.client.config().batch_size())
+                    .await?;
+            }
+        }
+
+        Ok(total_cost)
+    }
+
     /// Verify that chunks were uploaded
     ///
     /// Returns a vec of any chunks that could not be verified
@@ -240,13 +437,22 @@ impl Files {
             let verify_results = join_all(verify_handles).await;
 
             // Check for any errors during fetch
+            let mut verified = vec![];
+            let mut failed = vec![];
             for result in verify_results {
-                if let ((chunk_addr, path), true) = result?? {
+                let ((chunk_addr, path), verification_failed) = result??;
+                let address = ChunkAddress::new(chunk_addr);
+                if verification_failed {
                     warn!("Failed to fetch a chunk {chunk_addr:?}");
+                    failed.push(address);
                     // This needs to be NetAddr to allow for repayment
                     failed_chunks.push((chunk_addr, path));
+                } else {
+                    verified.push(address);
                 }
             }
+            self.client
+                .broadcast_progress(ClientEvent::ChunksVerified { verified, failed });
         }
 
         Ok(failed_chunks)
@@ -305,11 +511,14 @@ impl Files {
                 .await?;
         }
 
-        let mut failed_chunks = self.verify_uploaded_chunks(chunks, BATCH_SIZE).await?;
+        let mut failed_chunks = self
+            .verify_uploaded_chunks(chunks, self.client.config().batch_size())
+            .await?;
         warn!("Failed chunks: {:?}", failed_chunks.len());
 
         while !failed_chunks.is_empty() {
             info!("Repaying for {:?} chunks, so far paid {storage_cost} (royalties fees: {royalties_fees})", failed_chunks.len());
+            self.broadcast_repayment(&failed_chunks);
 
             // Now we pay again or top up, depending on the new current store cost is
             let (new_storage_cost, new_royalties_fees) = self
@@ -342,7 +551,7 @@ impl Files {
             trace!("Chunks uploaded again....");
 
             failed_chunks = self
-                .verify_uploaded_chunks(failed_chunks, BATCH_SIZE)
+                .verify_uploaded_chunks(failed_chunks, self.client.config().batch_size())
                 .await?;
         }
 
@@ -353,11 +562,39 @@ impl Files {
         ))
     }
 
+    /// Upload the given paid for chunks from their paths on disk, concurrently.
+    ///
+    /// Returns the outcome of each upload, in the order of `chunks`.
+    pub(crate) async fn upload_chunks_from_paths(
+        &self,
+        chunks: &[(XorName, PathBuf)],
+        show_holders: bool,
+    ) -> Vec<Result<()>> {
+        let results = join_all(chunks.iter().map(|(_name, path)| async move {
+            let chunk = Chunk::new(Bytes::from(fs::read(path)?));
+            self.get_local_payment_and_upload_chunk(chunk, false, show_holders)
+                .await
+        }))
+        .await;
+
+        let stored = chunks
+            .iter()
+            .zip(results.iter())
+            .filter(|(_, result)| result.is_ok())
+            .map(|((name, _), _)| ChunkAddress::new(*name))
+            .collect::<Vec<_>>();
+        if !stored.is_empty() {
+            self.client
+                .broadcast_progress(ClientEvent::ChunksStored(stored));
+        }
+        results
+    }
+
     // Gets and decrypts chunks from the network using nothing else but the data map.
     // If a downloaded path is given, the decrypted file will be written to the given path,
     // by the decryptor directly.
     // Otherwise, will assume the fetched content is a small one and return as bytes.
-    async fn read_all(
+    pub(crate) async fn read_all(
         &self,
         data_map: DataMap,
         decrypted_file_path: Option<PathBuf>,
@@ -373,6 +610,7 @@ impl Files {
         };
 
         let expected_count = data_map.infos().len();
+        let batch_size = self.client.config().batch_size();
         // let mut missing_chunks = Vec::new();
         let mut ordered_read_futures = FuturesOrdered::new();
         let now = Instant::now();
@@ -392,7 +630,7 @@ impl Files {
                 )
             });
 
-            if ordered_read_futures.len() >= BATCH_SIZE || index + BATCH_SIZE > expected_count {
+            if ordered_read_futures.len() >= batch_size || index + batch_size > expected_count {
                 while let Some((dst_hash, result)) = ordered_read_futures.next().await {
                     let chunk = result.map_err(|error| {
                         error!("Chunk missing {dst_hash:?} with {error:?}");
@@ -417,6 +655,100 @@ impl Files {
         Ok(None)
     }
 
+    // Fetches the chunks of the data map in order, with bounded concurrency,
+    // decrypting and writing out each one as soon as it is its turn.
+    async fn stream_data_map<W: AsyncWrite + Unpin>(
+        &self,
+        address: ChunkAddress,
+        data_map: DataMap,
+        writer: &mut W,
+        show_holders: bool,
+    ) -> Result<u64> {
+        let total_chunks = data_map.infos().len();
+        let mut ordered_read_futures = FuturesOrdered::new();
+        let mut chunks_fetched = 0;
+        let mut bytes_written = 0;
+
+        let mut infos = data_map.infos().into_iter();
+        loop {
+            // Keep the pipeline full, up to the concurrency limit.
+            while ordered_read_futures.len() < self.client.config().batch_size() {
+                let Some(chunk_info) = infos.next() else {
+                    break;
+                };
+                ordered_read_futures.push_back(async move {
+                    let result = self
+                        .client
+                        .get_chunk(ChunkAddress::new(chunk_info.dst_hash), show_holders)
+                        .await;
+                    (chunk_info, result)
+                });
+            }
+
+            let Some((chunk_info, result)) = ordered_read_futures.next().await else {
+                break;
+            };
+            let chunk = result.map_err(|error| {
+                error!("Chunk missing {:?} with {error:?}", chunk_info.dst_hash);
+                ChunksError::ChunkMissing(chunk_info.dst_hash)
+            })?;
+            let encrypted_chunk = EncryptedChunk {
+                index: chunk_info.index,
+                content: chunk.value().clone(),
+            };
+            let bytes = self_encryption::decrypt_range(
+                &data_map,
+                &[encrypted_chunk],
+                0,
+                chunk_info.src_size,
+            )
+            .map_err(ChunksError::SelfEncryption)?;
+            writer.write_all(&bytes).await?;
+
+            chunks_fetched += 1;
+            bytes_written += bytes.len() as u64;
+            trace!("Client (stream) download progress {chunks_fetched:?}/{total_chunks:?}");
+            // Progress is reported once per batch, not to flood the events channel.
+            if chunks_fetched % self.client.config().batch_size() == 0
+                || chunks_fetched == total_chunks
+            {
+                self.broadcast_download_progress(
+                    address,
+                    chunks_fetched,
+                    total_chunks,
+                    bytes_written,
+                );
+            }
+        }
+
+        Ok(bytes_written)
+    }
+
+    fn broadcast_download_progress(
+        &self,
+        address: ChunkAddress,
+        chunks_fetched: usize,
+        total_chunks: usize,
+        bytes_written: u64,
+    ) {
+        self.client
+            .broadcast_progress(ClientEvent::DownloadProgress {
+                address,
+                chunks_fetched,
+                total_chunks,
+                bytes_written,
+            });
+    }
+
+    pub(crate) fn broadcast_repayment(&self, chunks: &[(XorName, PathBuf)]) {
+        self.client.broadcast_progress(ClientEvent::Repayment {
+            chunks: chunks
+                .iter()
+                .map(|(name, _)| ChunkAddress::new(*name))
+                .collect(),
+        });
+    }
+
     /// Extracts a file DataMapLevel from a chunk.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
@@ -535,3 +867,126 @@ fn package_small(file: SmallFile) -> Result<Chunk> {
     }
     Ok(chunk)
 }
+
+/// Split `chunks` by whether a majority of their close group holds them, looking up
+/// `batch_size` chunks at a time with `get_infos`.
+async fn split_chunks_by_existence<F, Fut>(
+    chunks: Vec<(XorName, PathBuf)>,
+    batch_size: usize,
+    get_infos: F,
+) -> Result<(Vec<(XorName, PathBuf)>, Vec<(XorName, NanoTokens)>)>
+where
+    F: Fn(XorName) -> Fut,
+    Fut: Future<Output = Result<Vec<Result<RecordInfo>>>>,
+{
+    let mut missing_chunks = vec![];
+    let mut existing_chunks = vec![];
+
+    for chunks_batch in chunks.chunks(batch_size.max(1)) {
+        let infos = join_all(chunks_batch.iter().map(|(name, _)| get_infos(*name))).await;
+
+        for ((name, path), infos) in chunks_batch.iter().cloned().zip(infos) {
+            let infos = match infos {
+                Ok(infos) => infos,
+                Err(error) => {
+                    debug!("Could not check whether chunk {name:?} exists: {error}");
+                    missing_chunks.push((name, path));
+                    continue;
+                }
+            };
+            let holders = infos.iter().flatten().filter(|info| info.is_held).count();
+            if holders < close_group_majority() {
+                missing_chunks.push((name, path));
+                continue;
+            }
+
+            debug!("Chunk {name:?} is already held by {holders} peers");
+            // Holders refuse to quote for a record they already have, hence the cost the close
+            // group would have asked for is taken from the quotes sent along with the infos.
+            let mut quoted_cost = NETWORK_ROYALTIES_AMOUNT_PER_ADDR;
+            for info in infos.iter().flatten() {
+                quoted_cost = quoted_cost
+                    .checked_add(info.store_cost)
+                    .ok_or(Error::TotalPriceTooHigh)?;
+            }
+            existing_chunks.push((name, quoted_cost));
+        }
+    }
+
+    Ok((missing_chunks, existing_chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::{error::Error as ProtocolError, storage::RecordKind};
+    use std::collections::BTreeMap;
+
+    fn not_available(name: XorName) -> Error {
+        ProtocolError::RecordInfoNotAvailable(Box::new(NetworkAddress::from_chunk_address(
+            ChunkAddress::new(name),
+        )))
+        .into()
+    }
+
+    // Infos of the close group of `name`, as answered by each peer: whether it holds the chunk,
+    // or `None` if it failed to answer.
+    fn record_infos(name: XorName, answers: &[Option<bool>]) -> Vec<Result<RecordInfo>> {
+        answers
+            .iter()
+            .map(|answer| match answer {
+                Some(is_held) => Ok(RecordInfo {
+                    holder: NetworkAddress::from_peer(libp2p::PeerId::random()),
+                    key: NetworkAddress::from_chunk_address(ChunkAddress::new(name)),
+                    is_held: *is_held,
+                    kind: is_held.then_some(RecordKind::Chunk),
+                    size: 0,
+                    stored_at: None,
+                    within_distance_range: true,
+                    store_cost: NanoTokens::from(10),
+                }),
+                None => Err(not_available(name)),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn chunks_held_by_a_majority_are_split_out_with_their_quotes() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let held = XorName::random(&mut rng);
+        let held_by_few = XorName::random(&mut rng);
+        let unanswered = XorName::random(&mut rng);
+        let unreachable = XorName::random(&mut rng);
+
+        let majority = close_group_majority();
+        let mut answers = BTreeMap::new();
+        let mut held_answers = vec![Some(true); majority];
+        held_answers.push(Some(false));
+        held_answers.push(None);
+        let _ = answers.insert(held, held_answers);
+        let _ = answers.insert(held_by_few, vec![Some(true), Some(false), Some(false)]);
+        let _ = answers.insert(unanswered, vec![None; majority]);
+
+        let chunks = [held, held_by_few, unanswered, unreachable]
+            .iter()
+            .map(|name| (*name, PathBuf::from(hex::encode(name))))
+            .collect::<Vec<_>>();
+        let (missing, existing) = split_chunks_by_existence(chunks, 3, |name| {
+            let result = match answers.get(&name) {
+                Some(answers) => Ok(record_infos(name, answers)),
+                None => Err(not_available(name)),
+            };
+            async move { result }
+        })
+        .await?;
+
+        assert_eq!(
+            missing.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec![held_by_few, unanswered, unreachable]
+        );
+        // Only the peers which answered quoted a cost.
+        let quoted_cost = NETWORK_ROYALTIES_AMOUNT_PER_ADDR.as_nano() + 10 * (majority as u64 + 1);
+        assert_eq!(existing, vec![(held, NanoTokens::from(quoted_cost))]);
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Files, UploadJournal, UploadSummary,
+};
+use bytes::Bytes;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::ChunkAddress;
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::{DirEntry, WalkDir};
+use xor_name::XorName;
+
+/// Prefix of a serialised `FolderManifest`, so that arbitrary file contents
+/// are not mistaken for a manifest.
+const FOLDER_MANIFEST_MAGIC: &[u8; 8] = b"SNFOLDR1";
+
+/// Metadata of a single file within a `FolderManifest`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderEntry {
+    /// Head address of the file, as returned by `Files::chunk_file`.
+    pub address: ChunkAddress,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+/// Maps the relative paths of a directory tree to the network addresses of its files.
+///
+/// The manifest is itself self-encrypted and stored as chunks, so a whole tree
+/// can be shared and downloaded using the single address of the manifest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderManifest {
+    /// Relative paths use `/` as separator, regardless of the uploading platform.
+    entries: BTreeMap<String, FolderEntry>,
+}
+
+impl FolderManifest {
+    /// Create an empty manifest.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file at `relative_path` to the manifest, replacing any previous entry at that path.
+    pub fn insert(&mut self, relative_path: &Path, entry: FolderEntry) -> Result<()> {
+        let key = path_to_manifest_key(relative_path)?;
+        let _ = self.entries.insert(key, entry);
+        Ok(())
+    }
+
+    /// Get the entry for the given relative path.
+    pub fn get(&self, relative_path: &str) -> Option<&FolderEntry> {
+        self.entries.get(relative_path)
+    }
+
+    /// Iterate over all `(relative_path, entry)` pairs, ordered by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &FolderEntry)> {
+        self.entries.iter()
+    }
+
+    /// Number of files in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the manifest holds no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size in bytes of all the files in the manifest.
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Serialise the manifest, prefixed with its magic bytes.
+    pub fn to_bytes(&self) -> Result<Bytes> {
+        let mut bytes = FOLDER_MANIFEST_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(self).map_err(|_| Error::FolderManifestParsingFailed)?);
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Deserialise a manifest produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let payload = bytes
+            .strip_prefix(FOLDER_MANIFEST_MAGIC.as_slice())
+            .ok_or(Error::FolderManifestParsingFailed)?;
+        bincode::deserialize(payload).map_err(|_| Error::FolderManifestParsingFailed)
+    }
+}
+
+impl Files {
+    /// Self-encrypt a `FolderManifest`, returning `(root_address, chunk_names)`
+    /// and writing the encrypted chunks into `chunk_dir`.
+    pub fn chunk_folder_manifest(
+        &self,
+        manifest: &FolderManifest,
+        chunk_dir: &Path,
+    ) -> Result<(XorName, Vec<(XorName, PathBuf)>)> {
+        let manifest_path = chunk_dir.join("folder_manifest");
+        let mut file = File::create(&manifest_path)?;
+        file.write_all(&manifest.to_bytes()?)?;
+
+        let manifest_chunks_dir = chunk_dir.join("folder_manifest_chunks");
+        create_dir_all(&manifest_chunks_dir)?;
+        let (root_address, _size, chunks) =
+            self.chunk_file(&manifest_path, &manifest_chunks_dir)?;
+        Ok((root_address, chunks))
+    }
+
+    /// Self-encrypt every file under `folder_path` into `chunk_dir`, along with a
+    /// `FolderManifest` describing the tree.
+    ///
+    /// Returns the root address of the manifest, the manifest itself and the chunks of all the
+    /// files and of the manifest. Fails if any entry of the tree can not be read.
+    pub fn chunk_folder(
+        &self,
+        folder_path: &Path,
+        chunk_dir: &Path,
+    ) -> Result<(XorName, FolderManifest, Vec<(XorName, PathBuf)>)> {
+        let files = walk_files(folder_path)?;
+        let chunked_files = files
+            .par_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                // Each file using individual dir for temp SE chunks.
+                let file_chunks_dir = chunk_dir.join(index.to_string());
+                create_dir_all(&file_chunks_dir)?;
+                let (head_address, size, chunks) =
+                    self.chunk_file(entry.path(), &file_chunks_dir)?;
+                Ok((entry.path(), head_address, size, chunks))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut manifest = FolderManifest::new();
+        let mut all_chunks = BTreeMap::new();
+        for (path, head_address, size, chunks) in chunked_files {
+            let relative_path = path
+                .strip_prefix(folder_path)
+                .map_err(|_| Error::InvalidFolderPath(path.to_path_buf()))?;
+            manifest.insert(
+                relative_path,
+                FolderEntry {
+                    address: ChunkAddress::new(head_address),
+                    size,
+                },
+            )?;
+            all_chunks.extend(chunks);
+        }
+
+        let (root_address, manifest_chunks) = self.chunk_folder_manifest(&manifest, chunk_dir)?;
+        all_chunks.extend(manifest_chunks);
+        Ok((root_address, manifest, all_chunks.into_iter().collect()))
+    }
+
+    /// Upload every file under `folder_path` along with a `FolderManifest` describing the tree,
+    /// through the upload session of `journal`.
+    ///
+    /// The chunks are written into the dir of the session, so an interrupted upload can be
+    /// resumed by calling this again with the journal loaded back from disk.
+    /// Returns the address of the manifest, from which the whole tree can be downloaded,
+    /// the manifest itself and what the upload did.
+    pub async fn upload_folder(
+        &self,
+        folder_path: &Path,
+        journal: &mut UploadJournal,
+        batch_size: usize,
+        verify_store: bool,
+        show_holders: bool,
+    ) -> Result<(ChunkAddress, FolderManifest, UploadSummary)> {
+        let (root_address, manifest, chunks) =
+            self.chunk_folder(folder_path, &journal.chunks_dir())?;
+        info!(
+            "Uploading folder {folder_path:?} with {} files in {} chunks",
+            manifest.len(),
+            chunks.len()
+        );
+        journal.add_chunks(chunks)?;
+
+        let summary = self
+            .upload_journaled_chunks(journal, batch_size, verify_store, show_holders)
+            .await?;
+        Ok((ChunkAddress::new(root_address), manifest, summary))
+    }
+
+    /// Fetch and deserialise the `FolderManifest` stored at `address`.
+    pub async fn get_folder_manifest(&self, address: ChunkAddress) -> Result<FolderManifest> {
+        let bytes = self
+            .read_bytes(address, None, false)
+            .await?
+            .ok_or(Error::FolderManifestParsingFailed)?;
+        FolderManifest::from_bytes(&bytes)
+    }
+
+    /// Download the whole tree described by the `FolderManifest` at `address` into `target_dir`.
+    ///
+    /// Returns the manifest that was followed.
+    pub async fn download_folder(
+        &self,
+        address: ChunkAddress,
+        target_dir: &Path,
+        show_holders: bool,
+    ) -> Result<FolderManifest> {
+        let manifest = self.get_folder_manifest(address).await?;
+        info!(
+            "Downloading folder {address:?} with {} files into {target_dir:?}",
+            manifest.len()
+        );
+
+        for (relative_path, entry) in manifest.iter() {
+            let file_path = manifest_key_to_path(target_dir, relative_path)?;
+            if let Some(parent) = file_path.parent() {
+                create_dir_all(parent)?;
+            }
+            debug!("Downloading {relative_path} from {:?}", entry.address);
+            let _ = self
+                .download_to_path(entry.address, &file_path, show_holders)
+                .await?;
+        }
+
+        Ok(manifest)
+    }
+}
+
+/// List the files under `path`, which may itself be a file.
+/// Unlike skipping them, failing on unreadable entries makes sure nothing is silently left out.
+pub(crate) fn walk_files(path: &Path) -> Result<Vec<DirEntry>> {
+    let mut files = vec![];
+    for entry in WalkDir::new(path) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if entry.file_type().is_file() {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+/// Convert a relative path into its platform independent manifest key.
+fn path_to_manifest_key(relative_path: &Path) -> Result<String> {
+    let mut parts = vec![];
+    for component in relative_path.components() {
+        match component {
+            Component::Normal(part) => parts.push(
+                part.to_str()
+                    .ok_or_else(|| Error::InvalidFolderPath(relative_path.to_path_buf()))?,
+            ),
+            Component::CurDir => {}
+            _ => return Err(Error::InvalidFolderPath(relative_path.to_path_buf())),
+        }
+    }
+    if parts.is_empty() {
+        return Err(Error::InvalidFolderPath(relative_path.to_path_buf()));
+    }
+    Ok(parts.join("/"))
+}
+
+/// Resolve a manifest key under `target_dir`, refusing anything that would escape it.
+fn manifest_key_to_path(target_dir: &Path, key: &str) -> Result<PathBuf> {
+    let mut path = target_dir.to_path_buf();
+    for part in key.split('/') {
+        if part.is_empty() || part == "." || part == ".." || part.contains('\\') {
+            return Err(Error::InvalidFolderPath(PathBuf::from(key)));
+        }
+        path.push(part);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_manifest_round_trip() -> Result<()> {
+        let mut manifest = FolderManifest::new();
+        manifest.insert(
+            Path::new("docs/readme.md"),
+            FolderEntry {
+                address: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+                size: 42,
+            },
+        )?;
+        manifest.insert(
+            Path::new("image.png"),
+            FolderEntry {
+                address: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+                size: 1024,
+            },
+        )?;
+
+        let bytes = manifest.to_bytes()?;
+        let deserialised = FolderManifest::from_bytes(&bytes)?;
+        assert_eq!(manifest, deserialised);
+        assert_eq!(deserialised.total_size(), 1066);
+        assert!(deserialised.get("docs/readme.md").is_some());
+
+        assert!(FolderManifest::from_bytes(b"not a manifest").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_keys_cannot_escape_target_dir() {
+        let target = Path::new("/tmp/target");
+        assert!(manifest_key_to_path(target, "a/b.txt").is_ok());
+        assert!(manifest_key_to_path(target, "../b.txt").is_err());
+        assert!(manifest_key_to_path(target, "a//b.txt").is_err());
+        assert!(path_to_manifest_key(Path::new("../b.txt")).is_err());
+    }
+
+    #[test]
+    fn walking_a_missing_path_fails() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("file"), b"content")?;
+        assert_eq!(walk_files(dir.path())?.len(), 1);
+        assert!(walk_files(&dir.path().join("missing")).is_err());
+        Ok(())
+    }
+}
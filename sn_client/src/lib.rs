@@ -10,24 +10,49 @@
 extern crate tracing;
 
 mod api;
+mod chunk_cache;
 mod chunks;
+mod config;
 mod error;
 mod event;
 mod faucet;
 mod file_apis;
+mod folders;
+#[cfg(feature = "open-metrics")]
+mod metrics;
+mod names;
+mod private_data;
+mod private_register;
 mod register;
+mod register_log;
+mod register_map;
+mod upload_cost;
+mod upload_journal;
+mod versioned_file;
 mod wallet;
 
 pub(crate) use error::Result;
 
 pub use self::{
+    chunk_cache::ChunkCache,
+    config::{ClientConfig, ClientConfigBuilder, RetryPolicy, DEFAULT_BATCH_SIZE},
     error::Error,
     event::{ClientEvent, ClientEventsReceiver},
     faucet::{get_tokens_from_faucet, load_faucet_wallet_from_genesis_wallet},
-    file_apis::Files,
+    file_apis::{Files, OpenedFile},
+    folders::{FolderEntry, FolderManifest},
+    names::{name_register_address, name_url, parse_name_url, NameTarget},
+    private_data::{EncryptedPrivateDataMap, PrivateDataMap},
+    private_register::PrivateRegister,
     register::ClientRegister,
+    register_log::{RegisterLog, MAX_LOG_ENTRY_SIZE},
+    register_map::RegisterMap,
+    upload_cost::UploadCostEstimate,
+    upload_journal::{ChunkUploadState, UploadJournal, UploadSummary},
+    versioned_file::{FileVersion, VersionedFile},
     wallet::{send, WalletClient},
 };
+pub use sn_networking::GetQuorum;
 
 use self::event::ClientEventsChannel;
 use indicatif::ProgressBar;
@@ -41,4 +66,7 @@ pub struct Client {
     signer: bls::SecretKey,
     peers_added: usize,
     progress: Option<ProgressBar>,
+    config: ClientConfig,
+    #[cfg(feature = "open-metrics")]
+    metrics: metrics::ClientMetrics,
 }
@@ -0,0 +1,61 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use prometheus_client::{
+    metrics::histogram::{exponential_buckets, Histogram},
+    registry::Registry,
+};
+
+#[derive(Clone)]
+pub(crate) struct ClientMetrics {
+    /// register
+    pub(crate) register_ops_per_push: Histogram,
+}
+
+impl ClientMetrics {
+    pub(crate) fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("sn_client");
+
+        // A Register holds at most MAX_REG_NUM_ENTRIES = 1024 entries
+        let register_ops_per_push = Histogram::new(exponential_buckets(1.0, 2.0, 11));
+        sub_registry.register(
+            "register_ops_per_push",
+            "Number of Register operations sent to the network with each push",
+            register_ops_per_push.clone(),
+        );
+
+        Self {
+            register_ops_per_push,
+        }
+    }
+
+    /// Record a push of `ops` Register operations to the network.
+    pub(crate) fn observe_register_push(&self, ops: usize) {
+        self.register_ops_per_push.observe(ops as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus_client::encoding::text::encode;
+
+    #[test]
+    fn register_pushes_are_recorded() -> Result<(), std::fmt::Error> {
+        let mut registry = Registry::default();
+        let metrics = ClientMetrics::new(&mut registry);
+        metrics.observe_register_push(1);
+        metrics.observe_register_push(20);
+
+        let mut encoded = String::new();
+        encode(&mut encoded, &registry)?;
+        assert!(encoded.contains("sn_client_register_ops_per_push_count 2"));
+        assert!(encoded.contains("sn_client_register_ops_per_push_sum 21.0"));
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Human readable names pointing to network addresses.
+//!
+//! A name is stored in a Register derived from the name and its owner's key. The network has no
+//! authority arbitrating who gets a name first, and the address of a Register always includes
+//! its owner, so names are scoped to their owner rather than global: two users can register
+//! the same name without clashing, and only the owner can update it.
+//!
+//! A resolver therefore needs the owner along with the name. Both are carried by the
+//! `safe://name/<owner>/<name>` URL returned by `name_url`, which is what gets shared.
+
+use super::{
+    error::{Error, Result},
+    register::{decode_entry, encode_entry},
+    Client, ClientRegister, WalletClient,
+};
+use bls::{PublicKey, PK_SIZE};
+use serde::{Deserialize, Serialize};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    storage::{ChunkAddress, RegisterAddress},
+    NetworkAddress,
+};
+use sn_transfers::NanoTokens;
+use sn_url::{decode_url_of_kind, encode_url, SAFE_URL_SCHEME};
+use std::fmt::{self, Display, Formatter};
+use xor_name::XorName;
+
+/// Prefix hashed along with a name to derive the `meta` of its Register,
+/// so that names do not clash with Registers created from the same string.
+const NAME_META_PREFIX: &[u8] = b"sn_names:";
+
+/// Type of the `safe://name/` URLs of names.
+const NAME_URL_TYPE: &str = "name";
+
+/// What a name points to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameTarget {
+    /// Head address of a file.
+    File(ChunkAddress),
+    /// Root address of a folder manifest.
+    Folder(ChunkAddress),
+    /// Address of a Register.
+    Register(RegisterAddress),
+}
+
+impl Display for NameTarget {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::File(address) => {
+                write!(f, "file {}", NetworkAddress::from_chunk_address(*address))
+            }
+            Self::Folder(address) => {
+                write!(f, "folder {}", NetworkAddress::from_chunk_address(*address))
+            }
+            Self::Register(address) => {
+                write!(
+                    f,
+                    "register {}",
+                    NetworkAddress::from_register_address(*address)
+                )
+            }
+        }
+    }
+}
+
+/// Address of the Register holding the target of `name`, as registered by `owner`.
+pub fn name_register_address(name: &str, owner: PublicKey) -> RegisterAddress {
+    RegisterAddress::new(name_meta(name), owner)
+}
+
+fn name_meta(name: &str) -> XorName {
+    XorName::from_content_parts(&[NAME_META_PREFIX, name.as_bytes()])
+}
+
+/// Render the `safe://name/<owner>/<name>` URL of `name` as registered by `owner`,
+/// which is all a resolver needs to find it.
+pub fn name_url(name: &str, owner: PublicKey) -> String {
+    format!("{}/{name}", encode_url(NAME_URL_TYPE, &owner.to_bytes()))
+}
+
+/// Parse a URL rendered by `name_url` into the name and its owner.
+pub fn parse_name_url(url: &str) -> Result<(String, PublicKey)> {
+    let url = url.trim();
+    let parsing_failed = || Error::NameUrlParsingFailed(url.to_string());
+
+    let path = url
+        .strip_prefix(SAFE_URL_SCHEME)
+        .and_then(|rest| rest.strip_prefix(NAME_URL_TYPE))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .ok_or_else(parsing_failed)?;
+    let (owner, name) = path.split_once('/').ok_or_else(parsing_failed)?;
+    if name.is_empty() {
+        return Err(parsing_failed());
+    }
+
+    let owner_url = format!("{SAFE_URL_SCHEME}{NAME_URL_TYPE}/{owner}");
+    let owner: [u8; PK_SIZE] = decode_url_of_kind(&owner_url, NAME_URL_TYPE)
+        .map_err(|_| parsing_failed())?
+        .try_into()
+        .map_err(|_| parsing_failed())?;
+    let owner = PublicKey::from_bytes(owner).map_err(|_| parsing_failed())?;
+    Ok((name.to_string(), owner))
+}
+
+impl Client {
+    /// Register `name` to point to `target`, paying for the Register backing it.
+    ///
+    /// Only the owner of the Register, i.e. this client, can update the name afterwards.
+    /// Fails if the name was already registered by this client.
+    pub async fn register_name(
+        &self,
+        name: &str,
+        target: NameTarget,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        let (mut register, cost) = self
+            .create_and_pay_for_register(name_meta(name), wallet_client, verify_store)
+            .await?;
+        if !register.read().is_empty() {
+            return Err(Error::NameAlreadyRegistered(name.to_string()));
+        }
+
+        register
+            .write_online(&encode_entry(&target)?, verify_store)
+            .await?;
+        info!("Registered name '{name}' pointing to {target}");
+        Ok(cost)
+    }
+
+    /// Point the already registered `name` to `target`.
+    pub async fn update_name(
+        &self,
+        name: &str,
+        target: NameTarget,
+        verify_store: bool,
+    ) -> Result<()> {
+        let address = name_register_address(name, self.signer_pk());
+        let mut register = self.get_name_register(name, address).await?;
+
+        register
+            .write_merging_branches_online(&encode_entry(&target)?, verify_store)
+            .await?;
+        info!("Updated name '{name}' to point to {target}");
+        Ok(())
+    }
+
+    /// Resolve `name`, as registered by `owner`, to its current target.
+    ///
+    /// If concurrent updates left several latest entries, the one with the
+    /// greatest entry hash is picked, so that every resolver agrees on it.
+    pub async fn resolve_name(&self, name: &str, owner: PublicKey) -> Result<NameTarget> {
+        let address = name_register_address(name, owner);
+        let register = self.get_name_register(name, address).await?;
+
+        let (hash, entry) = register
+            .read()
+            .into_iter()
+            .next_back()
+            .ok_or_else(|| Error::NameNotRegistered(name.to_string()))?;
+        decode_entry(hash, &entry, |_| Error::NameTargetParsingFailed)
+    }
+
+    /// Resolve the name at `url`, as rendered by `name_url`, to its current target.
+    pub async fn resolve_name_url(&self, url: &str) -> Result<NameTarget> {
+        let (name, owner) = parse_name_url(url)?;
+        self.resolve_name(&name, owner).await
+    }
+
+    async fn get_name_register(
+        &self,
+        name: &str,
+        address: RegisterAddress,
+    ) -> Result<ClientRegister> {
+        self.get_register(address)
+            .await
+            .map_err(|error| name_not_registered(name, error))
+    }
+}
+
+/// Report a missing name Register as the name not being registered, passing other errors on.
+fn name_not_registered(name: &str, error: Error) -> Error {
+    match error {
+        Error::Protocol(ProtocolError::RegisterNotFound(_)) => {
+            Error::NameNotRegistered(name.to_string())
+        }
+        error => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use sn_registers::EntryHash;
+
+    #[test]
+    fn name_addresses_do_not_clash_with_plain_registers() {
+        let owner = SecretKey::random().public_key();
+        let address = name_register_address("my-site", owner);
+
+        assert_eq!(address, name_register_address("my-site", owner));
+        assert_ne!(address.meta(), XorName::from_content(b"my-site"));
+        assert_ne!(address, name_register_address("my-other-site", owner));
+    }
+
+    #[test]
+    fn name_urls_carry_the_owner() -> Result<()> {
+        let owner = SecretKey::random().public_key();
+        let url = name_url("my-site", owner);
+        assert!(url.starts_with("safe://name/"));
+        assert_eq!(parse_name_url(&url)?, ("my-site".to_string(), owner));
+
+        let other_owner = SecretKey::random().public_key();
+        assert_ne!(url, name_url("my-site", other_owner));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_name_urls_are_rejected() {
+        let owner = SecretKey::random().public_key();
+        let url = name_url("my-site", owner);
+        let register_url =
+            NetworkAddress::from_register_address(name_register_address("my-site", owner))
+                .to_string();
+
+        for invalid in [
+            "my-site".to_string(),
+            url.trim_end_matches("my-site").to_string(),
+            url.replacen("/name/", "/chunk/", 1),
+            url.replacen("safe://name/", "safe://name/a", 1),
+            register_url,
+        ] {
+            assert!(
+                matches!(
+                    parse_name_url(&invalid),
+                    Err(Error::NameUrlParsingFailed(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_missing_registers_make_a_name_unregistered() {
+        let address = name_register_address("my-site", SecretKey::random().public_key());
+        let not_found = Error::Protocol(ProtocolError::RegisterNotFound(Box::new(address)));
+        assert!(matches!(
+            name_not_registered("my-site", not_found),
+            Error::NameNotRegistered(name) if name == "my-site"
+        ));
+
+        let failed = Error::CouldNotVerifyTransfer("network down".to_string());
+        assert!(matches!(
+            name_not_registered("my-site", failed),
+            Error::CouldNotVerifyTransfer(_)
+        ));
+    }
+
+    #[test]
+    fn name_targets_roundtrip() -> Result<()> {
+        let target =
+            NameTarget::Folder(ChunkAddress::new(XorName::random(&mut rand::thread_rng())));
+        let hash = EntryHash::default();
+        let decoded: NameTarget = decode_entry(hash, &encode_entry(&target)?, |_| {
+            Error::NameTargetParsingFailed
+        })?;
+        assert_eq!(decoded, target);
+        let result: Result<NameTarget> =
+            decode_entry(hash, &[0xff; 3], |_| Error::NameTargetParsingFailed);
+        assert!(matches!(result, Err(Error::NameTargetParsingFailed)));
+        Ok(())
+    }
+}
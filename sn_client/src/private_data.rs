@@ -0,0 +1,184 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    chunks::{encrypt_large_private, Error as ChunksError},
+    error::{Error, Result},
+    Files,
+};
+use bls::{Ciphertext, PublicKey, SecretKey};
+use bytes::Bytes;
+use self_encryption::{DataMap, MIN_ENCRYPTABLE_BYTES};
+use serde::{Deserialize, Serialize};
+use sn_transfers::NanoTokens;
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+use xor_name::XorName;
+
+/// Everything needed to read back a privately uploaded file.
+///
+/// Unlike a regular upload, the data map is never packed into a chunk and stored on the network,
+/// so the content can only be located and decrypted by whoever holds this value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PrivateDataMap {
+    /// Data map of a self-encrypted file, whose chunks are stored on the network.
+    DataMap(DataMap),
+    /// Content of a file too small to be self-encrypted, which is kept locally in full.
+    Inline(Bytes),
+}
+
+impl PrivateDataMap {
+    /// Serialise the data map so it can be stored locally.
+    pub fn to_bytes(&self) -> Result<Bytes> {
+        let bytes = bincode::serialize(self).map_err(|_| Error::PrivateDataMapParsingFailed)?;
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Deserialise a data map produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| Error::PrivateDataMapParsingFailed)
+    }
+
+    /// Encrypt the data map to `pk`, so it can be handed over or stored on untrusted media.
+    pub fn encrypt(&self, pk: &PublicKey) -> Result<EncryptedPrivateDataMap> {
+        Ok(EncryptedPrivateDataMap(pk.encrypt(self.to_bytes()?)))
+    }
+
+    /// Names of the chunks which have to be uploaded to the network for this data map.
+    pub fn chunk_names(&self) -> Vec<XorName> {
+        match self {
+            Self::DataMap(data_map) => data_map.infos().iter().map(|i| i.dst_hash).collect(),
+            Self::Inline(_) => vec![],
+        }
+    }
+}
+
+/// A `PrivateDataMap` encrypted to a `bls::PublicKey`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedPrivateDataMap(Ciphertext);
+
+impl EncryptedPrivateDataMap {
+    /// Decrypt the data map with the secret key matching the public key it was encrypted to.
+    pub fn decrypt(&self, sk: &SecretKey) -> Result<PrivateDataMap> {
+        let bytes = sk
+            .decrypt(&self.0)
+            .ok_or(Error::PrivateDataMapDecryptionFailed)?;
+        PrivateDataMap::from_bytes(&bytes)
+    }
+
+    /// Serialise the encrypted data map.
+    pub fn to_bytes(&self) -> Result<Bytes> {
+        let bytes = bincode::serialize(self).map_err(|_| Error::PrivateDataMapParsingFailed)?;
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Deserialise an encrypted data map produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| Error::PrivateDataMapParsingFailed)
+    }
+}
+
+impl Files {
+    /// Self-encrypt a file without packing its data map into a chunk,
+    /// returning `(private_data_map, chunk_names)` and writing the encrypted chunks into `chunk_dir`.
+    ///
+    /// Files too small to be self-encrypted produce no chunks at all; their content is kept
+    /// inline in the returned data map.
+    pub fn chunk_file_private(
+        &self,
+        file_path: &Path,
+        chunk_dir: &Path,
+    ) -> Result<(PrivateDataMap, Vec<(XorName, PathBuf)>)> {
+        let mut file = File::open(file_path)?;
+        let file_size = file.metadata()?.len();
+
+        if file_size < MIN_ENCRYPTABLE_BYTES as u64 {
+            let mut bytes = Vec::new();
+            let _ = file.read_to_end(&mut bytes)?;
+            if bytes.is_empty() {
+                return Err(ChunksError::EmptyFileProvided.into());
+            }
+            Ok((PrivateDataMap::Inline(bytes.into()), vec![]))
+        } else {
+            let (data_map, chunks) = encrypt_large_private(file_path, chunk_dir)?;
+            Ok((PrivateDataMap::DataMap(data_map), chunks))
+        }
+    }
+
+    /// Upload the chunks of a file while keeping its data map off the network.
+    ///
+    /// Returns the `PrivateDataMap` needed to read the file back and the total cost paid.
+    pub async fn upload_private(
+        &self,
+        file_path: &Path,
+        verify_store: bool,
+    ) -> Result<(PrivateDataMap, NanoTokens)> {
+        // Temp folder to hold SE chunks, which is cleaned up automatically once out of scope.
+        let temp_dir = tempdir()?;
+        let (data_map, chunks) = self.chunk_file_private(file_path, temp_dir.path())?;
+
+        let total_cost = if chunks.is_empty() {
+            NanoTokens::zero()
+        } else {
+            self.pay_and_upload_chunks(chunks, verify_store).await?
+        };
+
+        Ok((data_map, total_cost))
+    }
+
+    /// Read a privately uploaded file using its `PrivateDataMap`.
+    ///
+    /// If a downloaded path is given, the content is written to it and `None` is returned,
+    /// otherwise the content is returned as bytes.
+    pub async fn read_private(
+        &self,
+        data_map: PrivateDataMap,
+        downloaded_file_path: Option<PathBuf>,
+        show_holders: bool,
+    ) -> Result<Option<Bytes>> {
+        match data_map {
+            PrivateDataMap::DataMap(data_map) => {
+                self.read_all(data_map, downloaded_file_path, show_holders)
+                    .await
+            }
+            PrivateDataMap::Inline(bytes) => {
+                if let Some(path) = downloaded_file_path {
+                    fs::write(path, bytes)?;
+                    Ok(None)
+                } else {
+                    Ok(Some(bytes))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_private_data_map_round_trip() -> Result<()> {
+        let sk = SecretKey::random();
+        let data_map = PrivateDataMap::Inline(Bytes::from_static(b"private content"));
+
+        let encrypted = data_map.encrypt(&sk.public_key())?;
+        let encrypted = EncryptedPrivateDataMap::from_bytes(&encrypted.to_bytes()?)?;
+
+        match encrypted.decrypt(&sk)? {
+            PrivateDataMap::Inline(bytes) => assert_eq!(bytes, b"private content".as_slice()),
+            PrivateDataMap::DataMap(_) => panic!("expected an inline data map"),
+        }
+        assert!(encrypted.decrypt(&SecretKey::random()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,625 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    register::{decode_entry, encode_entry},
+    Client, ClientRegister, WalletClient,
+};
+use bls::{Ciphertext, PublicKey, SecretKey, Signature};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::RegisterAddress;
+use sn_registers::{Entry, EntryHash, Register};
+use sn_transfers::NanoTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::XorName;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+type SymmetricKey = [u8; KEY_LEN];
+
+/// An entry of the Register backing a `PrivateRegister`, as seen by the network.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PrivateEntry {
+    /// The symmetric key of an epoch, encrypted to one of its readers.
+    ///
+    /// Signed by the owner, as writers can write entries too.
+    KeyShare {
+        epoch: u64,
+        reader: PublicKey,
+        key: Ciphertext,
+        signature: Signature,
+    },
+    /// An entry encrypted with the symmetric key of an epoch.
+    Data {
+        epoch: u64,
+        nonce: [u8; NONCE_LEN],
+        ciphertext: Vec<u8>,
+    },
+}
+
+/// A Register whose entries can only be read by its owner and the writers it authorised.
+///
+/// Entries are encrypted client side with a symmetric key, which is itself shared with each
+/// reader by writing it to the Register encrypted to their `bls::PublicKey`. Nodes only ever
+/// store ciphertext. Removing a writer starts a new epoch with a fresh key, shared with the
+/// remaining readers only, so the removed writer can not read anything written afterwards.
+///
+/// Keys are rotated by the owner, who is expected to do so from a single client at a time.
+/// Key shares are signed by the owner, any other share is ignored.
+pub struct PrivateRegister {
+    register: ClientRegister,
+    signer: SecretKey,
+}
+
+impl PrivateRegister {
+    /// Create the Register of a new private register on the network, paying for it.
+    ///
+    /// The owner, i.e. this client, is the only reader until writers are added.
+    pub async fn create(
+        client: &Client,
+        meta: XorName,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens)> {
+        let (register, cost) = client
+            .create_and_pay_for_register(meta, wallet_client, verify_store)
+            .await?;
+        let mut private = Self {
+            register,
+            signer: client.signer().clone(),
+        };
+
+        if key_shares(private.register.register())?
+            .current_epoch()
+            .is_none()
+        {
+            let owner = private.register.owner();
+            let entries = rotate_key(private.address(), &private.signer, 0, [owner])?;
+            private.write_locally(&entries)?;
+            private.register.push(verify_store).await?;
+        }
+        Ok((private, cost))
+    }
+
+    /// Retrieve the private register backed by the Register at `address`.
+    pub async fn retrieve(client: &Client, address: RegisterAddress) -> Result<Self> {
+        let register = client.get_register(address).await?;
+        Ok(Self {
+            register,
+            signer: client.signer().clone(),
+        })
+    }
+
+    /// Return the address of the backing Register.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Return the backing Register, whose entries are all encrypted.
+    pub fn register(&self) -> &ClientRegister {
+        &self.register
+    }
+
+    /// Return the users the current key is shared with, i.e. the owner and the current writers.
+    pub fn readers(&self) -> Result<BTreeSet<PublicKey>> {
+        Ok(key_shares(self.register.register())?.readers())
+    }
+
+    /// Allow `user` to write to the Register, sharing the current key with them,
+    /// and push the change to the network.
+    pub async fn add_writer(&mut self, user: PublicKey, verify_store: bool) -> Result<()> {
+        let entry = add_writer(self.register.register(), &self.signer, user)?;
+        self.register.grant_write(user)?;
+        self.write_locally(&[entry])?;
+        self.register.push(verify_store).await?;
+        info!(
+            "Added writer {user:?} to private register {:?}",
+            self.address()
+        );
+        Ok(())
+    }
+
+    /// Stop allowing `user` to write to the Register, and rotate the key so they can not
+    /// read the entries written from now on. The change is pushed to the network.
+    pub async fn remove_writer(&mut self, user: PublicKey, verify_store: bool) -> Result<()> {
+        let (next_epoch, entries) = remove_writer(self.register.register(), &self.signer, user)?;
+        self.register.revoke_write(user)?;
+        self.write_locally(&entries)?;
+        self.register.push(verify_store).await?;
+        info!(
+            "Removed writer {user:?} from private register {:?}, now at key epoch {next_epoch}",
+            self.address(),
+        );
+        Ok(())
+    }
+
+    /// Encrypt `entry` with the current key and write it atop all the latest entries.
+    pub async fn write(&mut self, entry: &[u8], verify_store: bool) -> Result<()> {
+        let bytes = write(self.register.register(), &self.signer, entry)?;
+        self.register
+            .write_merging_branches_online(&bytes, verify_store)
+            .await
+    }
+
+    /// Return all the decrypted entries this client has the key of, oldest first.
+    ///
+    /// Entries of epochs whose key was not shared with this client, such as the ones written
+    /// after it was removed as a writer, are skipped, as are entries which can not be
+    /// decoded or decrypted.
+    pub fn entries(&self) -> Result<Vec<(EntryHash, Entry)>> {
+        entries(self.register.register(), &self.signer)
+    }
+
+    /// Return the latest decrypted entry, if any was written.
+    pub fn latest(&self) -> Result<Option<Entry>> {
+        Ok(self.entries()?.pop().map(|(_, entry)| entry))
+    }
+
+    /// Write `entries` one atop the other, without pushing.
+    fn write_locally(&mut self, entries: &[Entry]) -> Result<()> {
+        for entry in entries {
+            self.register.write_merging_branches(entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key shares of `register` signed by its owner.
+fn key_shares(register: &Register) -> Result<KeyShares> {
+    KeyShares::parse(register.address(), register.owner(), &register.history())
+}
+
+/// The key of the current epoch of `register`, if it was shared with `signer`.
+fn current_key(register: &Register, signer: &SecretKey) -> Result<(u64, SymmetricKey)> {
+    let shares = key_shares(register)?;
+    let epoch = shares
+        .current_epoch()
+        .ok_or(Error::PrivateRegisterKeyNotShared(*register.address()))?;
+    let key = shares
+        .keys(signer)?
+        .remove(&epoch)
+        .ok_or(Error::PrivateRegisterKeyNotShared(*register.address()))?;
+    Ok((epoch, key))
+}
+
+/// The entries sharing a fresh key for `epoch` with each of the `readers`,
+/// signed by the `owner`.
+fn rotate_key(
+    address: &RegisterAddress,
+    owner: &SecretKey,
+    epoch: u64,
+    readers: impl IntoIterator<Item = PublicKey>,
+) -> Result<Vec<Entry>> {
+    let mut key = [0; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    readers
+        .into_iter()
+        .map(|reader| key_share_entry(address, owner, epoch, &key, reader))
+        .collect()
+}
+
+/// The entry sharing the current key of `register` with the new writer `user`,
+/// signed by the `owner`.
+fn add_writer(register: &Register, owner: &SecretKey, user: PublicKey) -> Result<Entry> {
+    let (epoch, key) = current_key(register, owner)?;
+    key_share_entry(register.address(), owner, epoch, &key, user)
+}
+
+/// The entries starting a new epoch, whose key is shared with the current readers of
+/// `register` but `user`, along with that epoch.
+fn remove_writer(
+    register: &Register,
+    owner: &SecretKey,
+    user: PublicKey,
+) -> Result<(u64, Vec<Entry>)> {
+    let (epoch, _) = current_key(register, owner)?;
+    let next_epoch = next_epoch(register.address(), epoch)?;
+    let mut readers = key_shares(register)?.readers();
+    let _ = readers.remove(&user);
+    let entries = rotate_key(register.address(), owner, next_epoch, readers)?;
+    Ok((next_epoch, entries))
+}
+
+/// The entry holding `plaintext` encrypted with the current key of `register`.
+fn write(register: &Register, signer: &SecretKey, plaintext: &[u8]) -> Result<Entry> {
+    let (epoch, key) = current_key(register, signer)?;
+    data_entry(register.address(), epoch, &key, plaintext)
+}
+
+/// The decrypted entries of `register` whose key was shared with `signer`, oldest first.
+fn entries(register: &Register, signer: &SecretKey) -> Result<Vec<(EntryHash, Entry)>> {
+    let history = register.history();
+    let keys = KeyShares::parse(register.address(), register.owner(), &history)?.keys(signer)?;
+    Ok(decrypt_entries(register.address(), &keys, &history))
+}
+
+/// The key shares of a private register signed by its owner, by epoch and reader.
+struct KeyShares {
+    shares: BTreeMap<u64, BTreeMap<PublicKey, (EntryHash, Ciphertext)>>,
+}
+
+impl KeyShares {
+    fn parse(
+        address: &RegisterAddress,
+        owner: PublicKey,
+        history: &[(EntryHash, Entry)],
+    ) -> Result<Self> {
+        let mut shares = BTreeMap::<u64, BTreeMap<_, _>>::new();
+        for (hash, bytes) in history {
+            let entry = match decode_entry(*hash, bytes, Error::PrivateRegisterEntryParsingFailed) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    warn!("Skipping entry of private register {address:?}: {error}");
+                    continue;
+                }
+            };
+            if let PrivateEntry::KeyShare {
+                epoch,
+                reader,
+                key,
+                signature,
+            } = entry
+            {
+                let signed_bytes = key_share_signed_bytes(address, epoch, &reader, &key)?;
+                if !owner.verify(&signature, signed_bytes) {
+                    warn!("Ignoring key share {hash:?} of {address:?}, not signed by the owner");
+                    continue;
+                }
+                let _ = shares
+                    .entry(epoch)
+                    .or_default()
+                    .insert(reader, (*hash, key));
+            }
+        }
+        Ok(Self { shares })
+    }
+
+    /// The latest epoch a key was shared for, whoever with.
+    fn current_epoch(&self) -> Option<u64> {
+        self.shares.keys().next_back().copied()
+    }
+
+    /// The users the key of the current epoch was shared with.
+    fn readers(&self) -> BTreeSet<PublicKey> {
+        self.shares
+            .values()
+            .next_back()
+            .map(|readers| readers.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// The keys of all the epochs shared with `signer`.
+    fn keys(&self, signer: &SecretKey) -> Result<BTreeMap<u64, SymmetricKey>> {
+        let pk = signer.public_key();
+        let mut keys = BTreeMap::new();
+        for (epoch, readers) in &self.shares {
+            if let Some((hash, share)) = readers.get(&pk) {
+                let key = open_key_share(signer, share)
+                    .ok_or(Error::PrivateRegisterDecryptionFailed(*hash))?;
+                let _ = keys.insert(*epoch, key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Decrypt the data entries of `history` whose epoch is in `keys`, oldest first.
+///
+/// Entries which can not be decoded or decrypted are skipped, as any writer may have
+/// written them.
+fn decrypt_entries(
+    address: &RegisterAddress,
+    keys: &BTreeMap<u64, SymmetricKey>,
+    history: &[(EntryHash, Entry)],
+) -> Vec<(EntryHash, Entry)> {
+    let mut entries = vec![];
+    for (hash, bytes) in history {
+        let entry = match decode_entry(*hash, bytes, Error::PrivateRegisterEntryParsingFailed) {
+            Ok(entry) => entry,
+            Err(error) => {
+                warn!("Skipping entry of private register {address:?}: {error}");
+                continue;
+            }
+        };
+        if let PrivateEntry::Data {
+            epoch,
+            nonce,
+            ciphertext,
+        } = entry
+        {
+            let Some(key) = keys.get(&epoch) else {
+                debug!("Skipping entry {hash:?} of {address:?}, the key of epoch {epoch} was not shared");
+                continue;
+            };
+            match decrypt(key, &nonce, &aad(address, epoch), &ciphertext) {
+                Some(entry) => entries.push((*hash, entry)),
+                None => warn!(
+                    "Skipping entry of private register {address:?}: {}",
+                    Error::PrivateRegisterDecryptionFailed(*hash)
+                ),
+            }
+        }
+    }
+    entries
+}
+
+/// Encode `plaintext` encrypted with the `key` of `epoch`.
+fn data_entry(
+    address: &RegisterAddress,
+    epoch: u64,
+    key: &SymmetricKey,
+    plaintext: &[u8],
+) -> Result<Entry> {
+    let mut nonce = [0; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = encrypt(key, &nonce, &aad(address, epoch), plaintext)?;
+    encode_entry(&PrivateEntry::Data {
+        epoch,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Encode the `key` of `epoch` encrypted to `reader`, signed by the `owner`.
+fn key_share_entry(
+    address: &RegisterAddress,
+    owner: &SecretKey,
+    epoch: u64,
+    key: &SymmetricKey,
+    reader: PublicKey,
+) -> Result<Entry> {
+    let key = reader.encrypt(key);
+    let signature = owner.sign(key_share_signed_bytes(address, epoch, &reader, &key)?);
+    encode_entry(&PrivateEntry::KeyShare {
+        epoch,
+        reader,
+        key,
+        signature,
+    })
+}
+
+/// Bytes of a key share signed by the owner, binding it to its Register, epoch and reader.
+fn key_share_signed_bytes(
+    address: &RegisterAddress,
+    epoch: u64,
+    reader: &PublicKey,
+    key: &Ciphertext,
+) -> Result<Vec<u8>> {
+    encode_entry(&(address, epoch, reader, key))
+}
+
+fn next_epoch(address: &RegisterAddress, epoch: u64) -> Result<u64> {
+    epoch
+        .checked_add(1)
+        .ok_or(Error::PrivateRegisterEpochsExhausted(*address))
+}
+
+/// Data authenticated along with each entry, binding it to its Register and epoch.
+fn aad(address: &RegisterAddress, epoch: u64) -> Vec<u8> {
+    let mut aad = address.xorname().0.to_vec();
+    aad.extend_from_slice(&epoch.to_be_bytes());
+    aad
+}
+
+fn open_key_share(sk: &SecretKey, share: &Ciphertext) -> Option<SymmetricKey> {
+    sk.decrypt(share)?.try_into().ok()
+}
+
+fn encrypt(
+    key: &SymmetricKey,
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::PrivateRegisterEncryptionFailed)
+}
+
+fn decrypt(
+    key: &SymmetricKey,
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Option<Vec<u8>> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_registers::{Permissions, MAX_REG_ENTRY_SIZE};
+
+    #[test]
+    fn entries_only_decrypt_with_their_key_and_aad() -> Result<()> {
+        let key = [7; KEY_LEN];
+        let nonce = [1; NONCE_LEN];
+        let ciphertext = encrypt(&key, &nonce, b"register-a", b"secret")?;
+        assert_ne!(ciphertext, b"secret");
+
+        assert_eq!(
+            decrypt(&key, &nonce, b"register-a", &ciphertext),
+            Some(b"secret".to_vec())
+        );
+        assert_eq!(decrypt(&key, &nonce, b"register-b", &ciphertext), None);
+        assert_eq!(
+            decrypt(&[8; KEY_LEN], &nonce, b"register-a", &ciphertext),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn key_shares_only_open_for_their_reader() -> Result<()> {
+        let owner = SecretKey::random();
+        let reader = SecretKey::random();
+        let address =
+            RegisterAddress::new(XorName::random(&mut rand::thread_rng()), owner.public_key());
+        let key = [9; KEY_LEN];
+
+        let bytes = key_share_entry(&address, &owner, 3, &key, reader.public_key())?;
+        assert!(bytes.len() <= MAX_REG_ENTRY_SIZE);
+        let hash = EntryHash::default();
+        match decode_entry(hash, &bytes, Error::PrivateRegisterEntryParsingFailed)? {
+            PrivateEntry::KeyShare { epoch, key: ct, .. } => {
+                assert_eq!(epoch, 3);
+                assert_eq!(open_key_share(&reader, &ct), Some(key));
+                assert_eq!(open_key_share(&SecretKey::random(), &ct), None);
+            }
+            other => panic!("Unexpected entry {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn epochs_can_not_overflow() {
+        let address = RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            SecretKey::random().public_key(),
+        );
+        assert!(matches!(next_epoch(&address, 1), Ok(2)));
+        assert!(matches!(
+            next_epoch(&address, u64::MAX),
+            Err(Error::PrivateRegisterEpochsExhausted(_))
+        ));
+    }
+
+    /// A Register backing a private register, owned by `owner`, with its first key shared.
+    fn new_register(owner: &SecretKey) -> Result<Register> {
+        let mut register = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+        );
+        let entries = rotate_key(register.address(), owner, 0, [owner.public_key()])?;
+        write_all(&mut register, owner, entries)?;
+        Ok(register)
+    }
+
+    /// Write `entries` one atop the other, as `PrivateRegister` does through its Register.
+    fn write_all(
+        register: &mut Register,
+        signer: &SecretKey,
+        entries: impl IntoIterator<Item = Entry>,
+    ) -> Result<()> {
+        for entry in entries {
+            let children = register.read().into_iter().map(|(hash, _)| hash).collect();
+            let _ = register.write(entry, children, signer)?;
+        }
+        Ok(())
+    }
+
+    fn plaintexts(register: &Register, reader: &SecretKey) -> Result<Vec<Entry>> {
+        Ok(entries(register, reader)?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    #[test]
+    fn writers_added_after_a_removal_read_the_current_epoch() -> Result<()> {
+        let owner = SecretKey::random();
+        let writer_a = SecretKey::random();
+        let writer_b = SecretKey::random();
+        let mut register = new_register(&owner)?;
+
+        let share = add_writer(&register, &owner, writer_a.public_key())?;
+        write_all(&mut register, &owner, [share])?;
+        let before = write(&register, &owner, b"before")?;
+        write_all(&mut register, &owner, [before])?;
+        let (epoch, rotation) = remove_writer(&register, &owner, writer_a.public_key())?;
+        assert_eq!(epoch, 1);
+        write_all(&mut register, &owner, rotation)?;
+        let share = add_writer(&register, &owner, writer_b.public_key())?;
+        write_all(&mut register, &owner, [share])?;
+        let after = write(&register, &writer_b, b"after")?;
+        write_all(&mut register, &owner, [after])?;
+
+        let shares = key_shares(&register)?;
+        assert_eq!(shares.current_epoch(), Some(1));
+        assert_eq!(
+            shares.readers(),
+            BTreeSet::from([owner.public_key(), writer_b.public_key()])
+        );
+
+        assert_eq!(
+            plaintexts(&register, &owner)?,
+            vec![b"before".to_vec(), b"after".to_vec()]
+        );
+        assert_eq!(plaintexts(&register, &writer_b)?, vec![b"after".to_vec()]);
+        // the removed writer still reads what it was shared, and skips the rest
+        assert_eq!(plaintexts(&register, &writer_a)?, vec![b"before".to_vec()]);
+        assert!(matches!(
+            write(&register, &writer_a, b"locked out"),
+            Err(Error::PrivateRegisterKeyNotShared(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn key_shares_not_signed_by_the_owner_are_ignored() -> Result<()> {
+        let owner = SecretKey::random();
+        let writer = SecretKey::random();
+        let mut register = new_register(&owner)?;
+        let share = add_writer(&register, &owner, writer.public_key())?;
+        write_all(&mut register, &owner, [share])?;
+
+        // a writer starting an epoch of its own, to lock the owner out of new entries
+        let forged = rotate_key(register.address(), &writer, 5, [writer.public_key()])?;
+        write_all(&mut register, &owner, forged)?;
+
+        let shares = key_shares(&register)?;
+        assert_eq!(shares.current_epoch(), Some(0));
+        assert!(shares.keys(&writer)?.get(&5).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn undecodable_and_undecryptable_entries_are_skipped() -> Result<()> {
+        let owner = SecretKey::random();
+        let mut register = new_register(&owner)?;
+        let first = write(&register, &owner, b"first")?;
+        write_all(&mut register, &owner, [first])?;
+
+        // garbage, and an entry of the current epoch encrypted with another key
+        let address = *register.address();
+        let forged = data_entry(&address, 0, &[3; KEY_LEN], b"forged")?;
+        write_all(&mut register, &owner, [b"not an entry".to_vec(), forged])?;
+
+        let second = write(&register, &owner, b"second")?;
+        write_all(&mut register, &owner, [second])?;
+
+        assert_eq!(key_shares(&register)?.current_epoch(), Some(0));
+        assert_eq!(
+            plaintexts(&register, &owner)?,
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+        Ok(())
+    }
+}
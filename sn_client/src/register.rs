@@ -6,20 +6,26 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{Client, Error, Result, WalletClient};
+use crate::{Client, ClientEvent, ClientEventsReceiver, Error, Result, WalletClient};
 
 use bls::PublicKey;
+use futures::{stream, Stream};
 use libp2p::kad::Record;
+use serde::{de::DeserializeOwned, Serialize};
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::RegisterCmd,
+    messages::{register_notif_topic, RegisterCmd, RegisterNotification},
     storage::{try_serialize_record, RecordKind},
     NetworkAddress,
 };
-use sn_registers::{Entry, EntryHash, Permissions, Register, RegisterAddress, SignedRegister};
+use sn_registers::{
+    Entry, EntryHash, Error as RegisterError, PermissionChange, Permissions, Register,
+    RegisterAddress, SignedRegister, MAX_REG_NUM_ENTRIES,
+};
 use sn_transfers::{NanoTokens, Transfer};
 
-use std::collections::{BTreeSet, HashSet, LinkedList};
+use std::collections::{BTreeSet, HashSet, LinkedList, VecDeque};
+use tokio::sync::broadcast::error::RecvError;
 use xor_name::XorName;
 
 /// Ops made to an offline Register instance are applied locally only,
@@ -91,12 +97,17 @@ impl ClientRegister {
         self.register.address()
     }
 
+    /// Return the local replica of the Register, including the ops not pushed yet.
+    pub(crate) fn register(&self) -> &Register {
+        &self.register
+    }
+
     /// Return the Owner of the Register.
     pub fn owner(&self) -> PublicKey {
         self.register.owner()
     }
 
-    /// Return the Permissions of the Register.
+    /// Return the Permissions in effect on the Register.
     pub fn permissions(&self) -> &Permissions {
         self.register.permissions()
     }
@@ -117,6 +128,29 @@ impl ClientRegister {
         self.register.read()
     }
 
+    /// Return the hashes of the entries the provided 'hash' was written atop of, if present.
+    pub fn children(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let children = self.register.children(hash)?;
+        Ok(children)
+    }
+
+    /// Return the hashes of the entries written atop of the provided 'hash', if present.
+    pub fn parents(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let parents = self.register.parents(hash)?;
+        Ok(parents)
+    }
+
+    /// Return all the entries of the register in causal order, oldest first.
+    pub fn history(&self) -> Vec<(EntryHash, Entry)> {
+        self.register.history()
+    }
+
+    /// Return the latest entries which both 'a' and 'b' were written atop of, directly or not.
+    pub fn common_ancestors(&self, a: EntryHash, b: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let ancestors = self.register.common_ancestors(a, b)?;
+        Ok(ancestors)
+    }
+
     /// Write a new value onto the Register atop latest value.
     /// It returns an error if it finds branches in the content/entries; if it is
     /// required to merge/resolve the branches, invoke the `write_merging_branches` API.
@@ -164,6 +198,33 @@ impl ClientRegister {
         Ok(())
     }
 
+    /// Allow the given user to write to the Register.
+    /// Only the owner of the Register can change its permissions.
+    pub fn grant_write(&mut self, user: PublicKey) -> Result<()> {
+        self.update_permissions(PermissionChange::GrantWrite(user))
+    }
+
+    /// Stop allowing the given user to write to the Register.
+    /// Entries the user wrote before are kept, as long as they were synced to this replica.
+    /// Only the owner of the Register can change its permissions.
+    pub fn revoke_write(&mut self, user: PublicKey) -> Result<()> {
+        self.update_permissions(PermissionChange::RevokeWrite(user))
+    }
+
+    /// Allow, or stop allowing, anyone to write to the Register.
+    /// Only the owner of the Register can change its permissions.
+    pub fn set_anyone_can_write(&mut self, anyone_can_write: bool) -> Result<()> {
+        self.update_permissions(PermissionChange::SetAnyoneCanWrite(anyone_can_write))
+    }
+
+    fn update_permissions(&mut self, change: PermissionChange) -> Result<()> {
+        let op = self
+            .register
+            .update_permissions(change, self.client.signer())?;
+        self.ops.push_front(RegisterCmd::EditPermissions(op));
+        Ok(())
+    }
+
     // ********* Online methods  *********
 
     /// Sync this Register with the replicas on the network.
@@ -243,31 +304,44 @@ impl ClientRegister {
 
     /// Push all operations made locally to the replicas of this Register on the network.
     /// This optionally verifies that the stored Register is the same as our local register
+    ///
+    /// All the cached operations are merged into the Register fetched from the network,
+    /// which is then stored with a single PUT, so queuing many operations offline before
+    /// pushing them costs a single round trip.
+    ///
+    /// Operations rejected by the network, e.g. as the writer lost its permission, are dropped
+    /// and returned in a `RegisterCmdsRejected` error, once the others were pushed.
     pub async fn push(&mut self, verify_store: bool) -> Result<()> {
         let ops_len = self.ops.len();
         if ops_len > 0 {
             let address = *self.address();
             debug!("Pushing {ops_len} cached Register cmds at {address}!");
 
-            // TODO: send them all concurrently
-            while let Some(cmd) = self.ops.pop_back() {
-                // We don't need to send the payment proofs here since
-                // these are all Register mutation cmds which don't require payment.
-                let payment = vec![];
-
-                let result = self
-                    .publish_register(cmd.clone(), payment, verify_store)
-                    .await;
-
-                if let Err(err) = result {
-                    warn!("Did not push Register cmd on all nodes in the close group!: {err}");
-                    // We keep the cmd for next sync to retry
-                    self.ops.push_back(cmd);
+            // Cached cmds are pushed to the front, so the oldest one is at the back.
+            let cmds = self.ops.iter().rev().cloned().collect();
+            let (stored, rejected) = match self.publish_register_edits(cmds, verify_store).await {
+                Ok(published) => published,
+                Err(err) => {
+                    warn!("Did not push Register cmds on all nodes in the close group!: {err}");
+                    // We keep the cmds for next sync to retry
                     return Err(err);
                 }
+            };
+            // The cmds which were rejected would be rejected by every later push as well,
+            // so they are dropped along with the pushed ones.
+            self.ops.clear();
+            let pushed = ops_len - rejected.len();
+
+            #[cfg(feature = "open-metrics")]
+            self.client.metrics.observe_register_push(pushed);
+
+            debug!("Successfully pushed {pushed} of {ops_len} Register cmds at {address}!");
+            if !rejected.is_empty() {
+                // The local replica is replaced by the one which was stored,
+                // so that it no longer holds the rejected edits.
+                self.register = stored.register()?;
+                return Err(Error::RegisterCmdsRejected(rejected));
             }
-
-            debug!("Successfully pushed {ops_len} Register cmds at {address}!");
         }
 
         Ok(())
@@ -309,6 +383,97 @@ impl ClientRegister {
         self.push(verify_store).await
     }
 
+    /// Write several values onto the Register, each one atop the previous one, the first one
+    /// merging all the current branches. They are all pushed to the network with a single PUT.
+    pub async fn write_batch_online<I, E>(&mut self, entries: I, verify_store: bool) -> Result<()>
+    where
+        I: IntoIterator<Item = E>,
+        E: AsRef<[u8]>,
+    {
+        for entry in entries {
+            self.write_merging_branches(entry.as_ref())?;
+        }
+        self.push(verify_store).await
+    }
+
+    /// Write a new value onto the Register atop the `expected` entries, only if they are
+    /// still the latest entries once this replica has caught up with the network.
+    ///
+    /// Returns `Error::RegisterWriteConflict` with the current latest entries otherwise,
+    /// so the caller can build the value again from them and retry. Writes racing with
+    /// this one which have not reached the network yet can not be detected, and may
+    /// still leave branches to merge.
+    pub async fn write_if_latest_online(
+        &mut self,
+        entry: &[u8],
+        expected: BTreeSet<EntryHash>,
+        verify_store: bool,
+    ) -> Result<()> {
+        let remote_replica = Self::get_register_from_network(&self.client, *self.address()).await?;
+        merge_expecting_latest(&mut self.register, remote_replica, &expected)?;
+
+        self.write_atop(entry, expected)?;
+        self.push(verify_store).await
+    }
+
+    /// Allow the given user to write to the Register, and push the change to the network.
+    pub async fn grant_write_online(&mut self, user: PublicKey, verify_store: bool) -> Result<()> {
+        self.grant_write(user)?;
+        self.push(verify_store).await
+    }
+
+    /// Stop allowing the given user to write to the Register, and push the change to the network.
+    pub async fn revoke_write_online(&mut self, user: PublicKey, verify_store: bool) -> Result<()> {
+        self.revoke_write(user)?;
+        self.push(verify_store).await
+    }
+
+    /// Watch the Register for new entries, as notified over gossipsub by the nodes storing it.
+    ///
+    /// Returns a stream of the entries unknown to this replica when the watch started, in
+    /// causal order. Notified ops are checked against the signatures and permissions of the
+    /// Register before being yielded, but are not applied to this `ClientRegister`;
+    /// call `sync` to get them in here. The stream ends when the client is shut down, and
+    /// unsubscribes from the notifications of the Register when dropped.
+    pub fn watch(&self) -> Result<impl Stream<Item = (EntryHash, Entry)>> {
+        let topic = register_notif_topic(self.address());
+        self.client.subscribe_to_topic(topic.clone())?;
+        debug!("Watching Register at {:?} on topic {topic}", self.address());
+
+        let watch = RegisterWatch {
+            client: self.client.clone(),
+            register: self.register.clone(),
+            events: self.client.events_channel(),
+            topic,
+            pending: VecDeque::new(),
+        };
+        Ok(stream::unfold(watch, |mut watch| async move {
+            loop {
+                if let Some(entry) = watch.pending.pop_front() {
+                    return Some((entry, watch));
+                }
+
+                match watch.events.recv().await {
+                    Ok(ClientEvent::GossipsubMsg { topic, msg }) if topic == watch.topic => {
+                        let entries = apply_notification(&mut watch.register, &msg);
+                        watch.pending.extend(entries);
+                    }
+                    Ok(_) => {}
+                    Err(Error::EventsReceiver(RecvError::Lagged(skipped))) => {
+                        warn!("Register watch missed {skipped} client events");
+                    }
+                    Err(err) => {
+                        debug!(
+                            "Stopped watching Register at {:?}: {err:?}",
+                            watch.register.address()
+                        );
+                        return None;
+                    }
+                }
+            }
+        }))
+    }
+
     // ********* Private helpers  *********
 
     /// Publish a `Register` command on the network.
@@ -339,13 +504,103 @@ impl ClientRegister {
                 }
                 SignedRegister::new(register, signature)
             }
-            RegisterCmd::Edit(op) => {
+            edit => {
                 let mut reg = network_reg?;
-                reg.add_op(op)?;
+                Self::apply_edit(&mut reg, edit)?;
                 reg
             }
         };
 
+        self.store_signed_register(register, payment, verify_store)
+            .await
+    }
+
+    /// Publish `Register` edit commands on the network, merging them all into the Register
+    /// stored with a single PUT, and return it along with the cmds which were rejected.
+    /// If `verify_store` is true, it will verify the Register was stored on the network.
+    async fn publish_register_edits(
+        &self,
+        cmds: Vec<RegisterCmd>,
+        verify_store: bool,
+    ) -> Result<(SignedRegister, Vec<(RegisterCmd, RegisterError)>)> {
+        let address = *self.address();
+        debug!(
+            "Querying existing Register for {} cmds: {address:?}",
+            cmds.len()
+        );
+        let mut register = self
+            .client
+            .get_signed_register_from_network(address)
+            .await?;
+        let cmds_len = cmds.len();
+        let rejected = Self::apply_edits(&mut register, cmds);
+        let applied = cmds_len - rejected.len();
+        if applied == 0 {
+            debug!("No Register cmd left to publish: {address:?}");
+            return Ok((register, rejected));
+        }
+
+        debug!("Publishing {applied} merged Register cmds: {address:?}");
+        // We don't need to send the payment proofs here since
+        // these are all Register mutation cmds which don't require payment.
+        self.store_signed_register(register.clone(), vec![], verify_store)
+            .await?;
+        Ok((register, rejected))
+    }
+
+    /// Check and apply edit commands to a `SignedRegister`, returning the ones which were
+    /// rejected along with the reason.
+    ///
+    /// A command which can not be applied, e.g. as its writer is no longer allowed to write,
+    /// is dropped with a warning, so that it does not hold back the other ones.
+    fn apply_edits(
+        register: &mut SignedRegister,
+        cmds: Vec<RegisterCmd>,
+    ) -> Vec<(RegisterCmd, RegisterError)> {
+        let mut rejected = vec![];
+        for cmd in cmds {
+            if let Err(err) = Self::apply_edit(register, &cmd) {
+                warn!("Dropping Register cmd rejected by {:?}: {err:?}", cmd.dst());
+                rejected.push((cmd, err));
+            }
+        }
+        rejected
+    }
+
+    /// Check and apply an edit command to a `SignedRegister`.
+    fn apply_edit(
+        register: &mut SignedRegister,
+        cmd: &RegisterCmd,
+    ) -> std::result::Result<(), RegisterError> {
+        match cmd {
+            RegisterCmd::Edit(op) => {
+                // A full Register is reported, rather than stored with more entries than it
+                // can be read back with.
+                let size = register.ops().len();
+                if size >= MAX_REG_NUM_ENTRIES.into() && !register.ops().contains(op) {
+                    return Err(RegisterError::TooManyEntries(size));
+                }
+                register.add_op(op.clone())?
+            }
+            RegisterCmd::EditPermissions(op) => register.add_permission_op(op.clone())?,
+            RegisterCmd::Create { .. } => {
+                warn!(
+                    "Ignoring Register creation cmd sent as an edit of {:?}",
+                    register.address()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Store a `SignedRegister` on the network.
+    /// If `verify_store` is true, it will verify the Register was stored on the network.
+    async fn store_signed_register(
+        &self,
+        register: SignedRegister,
+        payment: Vec<Transfer>,
+        verify_store: bool,
+    ) -> Result<()> {
         let network_address = NetworkAddress::from_register_address(*register.address());
         let key = network_address.to_record_key();
         let record = Record {
@@ -378,11 +633,12 @@ impl ClientRegister {
         };
 
         // Register edits might exist so we cannot be sure that just because we get a record back that this should fail
-        Ok(self
-            .client
-            .network
+        self.client
             .put_record(record, record_to_verify, expected_holders)
-            .await?)
+            .await?;
+        self.client
+            .broadcast_progress(ClientEvent::RegisterStored(*register.address()));
+        Ok(())
     }
 
     // Retrieve a `Register` from the Network.
@@ -396,3 +652,252 @@ impl ClientRegister {
         Ok(reg.register()?)
     }
 }
+
+/// Merge `remote_replica` into `register`, checking that its latest entries are `expected`.
+fn merge_expecting_latest(
+    register: &mut Register,
+    remote_replica: Register,
+    expected: &BTreeSet<EntryHash>,
+) -> Result<()> {
+    register.merge(remote_replica);
+
+    let latest: BTreeSet<EntryHash> = register.read().into_iter().map(|(hash, _)| hash).collect();
+    if &latest != expected {
+        debug!(
+            "Not writing to Register at {:?}, its latest entries are {latest:?}",
+            register.address()
+        );
+        return Err(Error::RegisterWriteConflict(latest));
+    }
+    Ok(())
+}
+
+/// Serialise `value` into the content of a Register entry.
+pub(crate) fn encode_entry<T: Serialize>(value: &T) -> Result<Entry> {
+    bincode::serialize(value).map_err(|err| Error::EntryEncodingFailed(err.to_string()))
+}
+
+/// Deserialise the content of the Register entry `hash`, failing with `parsing_failed`
+/// if it does not hold a `T`.
+pub(crate) fn decode_entry<T: DeserializeOwned>(
+    hash: EntryHash,
+    entry: &[u8],
+    parsing_failed: fn(EntryHash) -> Error,
+) -> Result<T> {
+    bincode::deserialize(entry).map_err(|_| parsing_failed(hash))
+}
+
+/// State of a stream returned by `ClientRegister::watch`.
+struct RegisterWatch {
+    client: Client,
+    register: Register,
+    events: ClientEventsReceiver,
+    topic: String,
+    pending: VecDeque<(EntryHash, Entry)>,
+}
+
+impl Drop for RegisterWatch {
+    fn drop(&mut self) {
+        if let Err(err) = self.client.unsubscribe_from_topic(self.topic.clone()) {
+            warn!(
+                "Failed to unsubscribe from notifications of Register at {:?}: {err:?}",
+                self.register.address()
+            );
+        }
+    }
+}
+
+/// Apply the ops of a notification to `register`, returning the entries they made known.
+fn apply_notification(register: &mut Register, msg: &[u8]) -> Vec<(EntryHash, Entry)> {
+    let address = *register.address();
+    let notification: RegisterNotification = match bincode::deserialize(msg) {
+        Ok(notification) => notification,
+        Err(err) => {
+            warn!("Ignoring invalid notification for Register at {address:?}: {err:?}");
+            return vec![];
+        }
+    };
+
+    let known: BTreeSet<_> = register
+        .history()
+        .into_iter()
+        .map(|(hash, _)| hash)
+        .collect();
+    for op in notification.permission_ops {
+        if let Err(err) = register.apply_permission_op(op) {
+            warn!("Ignoring notified permission change of Register at {address:?}: {err:?}");
+        }
+    }
+    for op in notification.ops {
+        let hash = op.hash();
+        if op.address() != address || known.contains(&hash) {
+            continue;
+        }
+        // Notifications are relayed by any peer, and applying an op only checks its
+        // signature if the Register is not writable by anyone, so it is checked here.
+        if let Err(err) = op.verify_signature(&op.source()) {
+            warn!("Ignoring notified op {hash:?} of Register at {address:?}: {err:?}");
+            continue;
+        }
+        if let Err(err) = register.apply_op(op) {
+            warn!("Ignoring notified op {hash:?} of Register at {address:?}: {err:?}");
+        }
+    }
+
+    // Ops may be notified before the ones they were written atop of,
+    // so only the entries now part of the history are yielded.
+    register
+        .history()
+        .into_iter()
+        .filter(|(hash, _)| !known.contains(hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use sn_registers::RegisterOp;
+
+    fn notify(register: &mut Register, ops: Vec<RegisterOp>) -> Vec<Entry> {
+        let notification = RegisterNotification {
+            ops,
+            permission_ops: vec![],
+        };
+        let msg = bincode::serialize(&notification).expect("notification to serialize");
+        apply_notification(register, &msg)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    // An op carrying the signature of another op of the same source.
+    fn with_signature_of(op: &RegisterOp, other: &RegisterOp) -> Result<RegisterOp> {
+        let sig_len = bincode::serialize(&SecretKey::random().sign(b"sig"))
+            .map_err(|err| Error::EntryEncodingFailed(err.to_string()))?
+            .len();
+        let mut bytes =
+            bincode::serialize(op).map_err(|err| Error::EntryEncodingFailed(err.to_string()))?;
+        let other =
+            bincode::serialize(other).map_err(|err| Error::EntryEncodingFailed(err.to_string()))?;
+        bytes.truncate(bytes.len() - sig_len);
+        bytes.extend_from_slice(&other[other.len() - sig_len..]);
+        bincode::deserialize(&bytes).map_err(|err| Error::EntryEncodingFailed(err.to_string()))
+    }
+
+    #[test]
+    fn writes_expecting_stale_entries_conflict() -> Result<()> {
+        let owner = SecretKey::random();
+        let mut local = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+        );
+        let (first, _) = local.write(b"first".to_vec(), BTreeSet::new(), &owner)?;
+        let expected = BTreeSet::from([first]);
+
+        // the network only holds what this replica has seen
+        merge_expecting_latest(&mut local.clone(), local.clone(), &expected)?;
+
+        // another client wrote atop the entry this replica expects to be the latest
+        let mut remote = local.clone();
+        let (second, _) = remote.write(b"second".to_vec(), expected.clone(), &owner)?;
+        match merge_expecting_latest(&mut local, remote, &expected) {
+            Err(Error::RegisterWriteConflict(latest)) => {
+                assert_eq!(latest, BTreeSet::from([second]))
+            }
+            other => panic!("Expected a write conflict, got {other:?}"),
+        }
+        // the replica caught up, so the caller can retry atop the returned entries
+        assert_eq!(local.read().len(), 1);
+        assert!(local.get(second).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn queued_edits_are_merged_in_one_register_dropping_rejected_ones() -> Result<()> {
+        let owner = SecretKey::random();
+        let stranger = SecretKey::random();
+        let base = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+        );
+        let mut network_copy = base.clone().into_signed(&owner)?;
+
+        let mut replica = base.clone();
+        let mut cmds = vec![];
+        for i in 0..10_u8 {
+            let children = replica.read().into_iter().map(|(hash, _)| hash).collect();
+            let (_, op) = replica.write(vec![i], children, &owner)?;
+            cmds.push(RegisterCmd::Edit(op));
+        }
+        let (_, rejected) =
+            base.clone()
+                .write(b"not allowed".to_vec(), BTreeSet::new(), &stranger)?;
+        cmds.insert(3, RegisterCmd::Edit(rejected.clone()));
+        let grant = replica
+            .update_permissions(PermissionChange::GrantWrite(stranger.public_key()), &owner)?;
+        cmds.push(RegisterCmd::EditPermissions(grant));
+
+        let rejected_cmds = ClientRegister::apply_edits(&mut network_copy, cmds);
+        assert!(matches!(
+            rejected_cmds.as_slice(),
+            [(RegisterCmd::Edit(op), RegisterError::AccessDenied(_))] if *op == rejected
+        ));
+        assert_eq!(network_copy.ops().len(), 10);
+        assert_eq!(network_copy.permission_ops().len(), 1);
+        network_copy.verify()?;
+        assert_eq!(network_copy.register()?.size(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn notified_ops_are_applied_in_causal_order() -> Result<()> {
+        let owner = SecretKey::random();
+        let base = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+        );
+        let mut writer = base.clone();
+        let (first, first_op) = writer.write(b"first".to_vec(), BTreeSet::new(), &owner)?;
+        let (_, second_op) = writer.write(b"second".to_vec(), BTreeSet::from([first]), &owner)?;
+
+        let mut watched = base;
+        // an op arriving before the one it was written atop of is held back
+        assert!(notify(&mut watched, vec![second_op.clone()]).is_empty());
+        assert_eq!(
+            notify(&mut watched, vec![first_op.clone(), second_op]),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+        // already known ops yield nothing
+        assert!(notify(&mut watched, vec![first_op]).is_empty());
+
+        assert!(apply_notification(&mut watched, b"not a notification").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn notified_ops_with_bad_signatures_are_ignored() -> Result<()> {
+        let owner = SecretKey::random();
+        let user = SecretKey::random();
+        let base = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_anyone_can_write(),
+        );
+        let mut writer = base.clone();
+        let (_, op) = writer.write(b"genuine".to_vec(), BTreeSet::new(), &user)?;
+        let (_, other_op) = base
+            .clone()
+            .write(b"other".to_vec(), BTreeSet::new(), &user)?;
+        let forged = with_signature_of(&op, &other_op)?;
+        assert!(forged.verify_signature(&user.public_key()).is_err());
+
+        let mut watched = base;
+        assert!(notify(&mut watched, vec![forged]).is_empty());
+        assert_eq!(notify(&mut watched, vec![op]), vec![b"genuine".to_vec()]);
+        Ok(())
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    register::{decode_entry, encode_entry},
+    Client, ClientRegister, WalletClient,
+};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::RegisterAddress;
+use sn_registers::{
+    Entry, EntryHash, Error as RegisterError, MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES,
+};
+use sn_transfers::NanoTokens;
+use xor_name::XorName;
+
+/// Prefix hashed along with the meta of the first segment and the index of a segment,
+/// to derive the meta of the following segments.
+const LOG_SEGMENT_PREFIX: &[u8] = b"sn_register_log:";
+
+/// Number of entries left free at the end of a segment, so that the link to the next one can
+/// still be written when other writers, whose view of the segment may be out of date, append
+/// to it concurrently.
+const SEGMENT_HEADROOM: u64 = 64;
+
+/// Number of entries a segment holds before a new segment is started.
+const SEGMENT_CAPACITY: u64 = MAX_REG_NUM_ENTRIES as u64 - SEGMENT_HEADROOM;
+
+/// Bytes added to an entry when it is encoded as a `LogEntry::Data`:
+/// the 4 byte variant index and the 8 byte length of the entry.
+const DATA_ENTRY_OVERHEAD: usize = 12;
+
+/// Maximum size of an entry appended to a log, so that it still fits in a Register entry once
+/// encoded.
+pub const MAX_LOG_ENTRY_SIZE: usize = MAX_REG_ENTRY_SIZE - DATA_ENTRY_OVERHEAD;
+
+/// An entry of a segment of a `RegisterLog`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum LogEntry {
+    /// An entry appended to the log.
+    Data(Entry),
+    /// Link to the next segment, written once this one is full.
+    Next(RegisterAddress),
+}
+
+/// An append-only log spanning as many Registers as needed.
+///
+/// Each Register, or segment, holds up to `SEGMENT_CAPACITY` entries. Once full, it is
+/// linked to a new segment, so the whole log can be followed from the address of the first one.
+pub struct RegisterLog {
+    client: Client,
+    /// Addresses of all the segments, the first one being the address of the log.
+    segments: Vec<RegisterAddress>,
+    /// The last segment, which entries are appended to.
+    tail: ClientRegister,
+}
+
+impl RegisterLog {
+    /// Create a new log on the network, paying for its first segment.
+    pub async fn create(
+        client: &Client,
+        meta: XorName,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens)> {
+        let (tail, cost) = client
+            .create_and_pay_for_register(meta, wallet_client, verify_store)
+            .await?;
+        let log = Self {
+            client: client.clone(),
+            segments: vec![*tail.address()],
+            tail,
+        };
+        Ok((log, cost))
+    }
+
+    /// Retrieve the log starting at `address`, following the links to its last segment.
+    pub async fn retrieve(client: &Client, address: RegisterAddress) -> Result<Self> {
+        let mut segments = vec![address];
+        let tail = follow_segments(client, &mut segments).await?;
+        Ok(Self {
+            client: client.clone(),
+            segments,
+            tail,
+        })
+    }
+
+    /// Return the address of the log, i.e. of its first segment.
+    pub fn address(&self) -> &RegisterAddress {
+        &self.segments[0]
+    }
+
+    /// Return the addresses of all the segments of the log, oldest first.
+    pub fn segments(&self) -> &[RegisterAddress] {
+        &self.segments
+    }
+
+    /// Append an entry to the log, starting a new segment if the last one is full.
+    ///
+    /// Returns the cost paid for the new segment, if one was needed.
+    /// Fails if the entry is larger than `MAX_LOG_ENTRY_SIZE`.
+    pub async fn append(
+        &mut self,
+        entry: &[u8],
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        if entry.len() > MAX_LOG_ENTRY_SIZE {
+            return Err(RegisterError::EntryTooBig {
+                size: entry.len(),
+                max: MAX_LOG_ENTRY_SIZE,
+            }
+            .into());
+        }
+
+        let mut cost = NanoTokens::zero();
+        if self.tail.size() >= SEGMENT_CAPACITY {
+            cost = self.start_segment(wallet_client, verify_store).await?;
+        }
+
+        let bytes = encode_entry(&LogEntry::Data(entry.to_vec()))?;
+        match self
+            .tail
+            .write_merging_branches_online(&bytes, verify_store)
+            .await
+        {
+            Err(err) if is_segment_full(&err) => {
+                // Other writers filled up the segment in the meantime, and may have linked it.
+                self.refresh_tail().await?;
+                if self.tail.size() >= SEGMENT_CAPACITY {
+                    cost = cost
+                        .checked_add(self.start_segment(wallet_client, verify_store).await?)
+                        .ok_or(Error::TotalPriceTooHigh)?;
+                }
+                self.tail
+                    .write_merging_branches_online(&bytes, verify_store)
+                    .await?;
+            }
+            result => result?,
+        }
+        Ok(cost)
+    }
+
+    /// Return all the entries of the log, oldest first.
+    ///
+    /// The last segment is fetched again, so that entries appended by other writers since the
+    /// log was retrieved are included.
+    pub async fn entries(&mut self) -> Result<Vec<Entry>> {
+        self.refresh_tail().await?;
+        let mut entries = vec![];
+        for address in &self.segments[..self.segments.len() - 1] {
+            let segment = self.client.get_register(*address).await?;
+            entries.extend(segment_entries(&segment));
+        }
+        entries.extend(segment_entries(&self.tail));
+        Ok(entries)
+    }
+
+    /// Return the last `count` entries of the log, oldest first,
+    /// only fetching the segments holding them.
+    ///
+    /// As with `entries`, the last segment is fetched again first.
+    pub async fn tail(&mut self, count: usize) -> Result<Vec<Entry>> {
+        self.refresh_tail().await?;
+        let mut entries = segment_entries(&self.tail);
+        for address in self.segments[..self.segments.len() - 1].iter().rev() {
+            if entries.len() >= count {
+                break;
+            }
+            let segment = self.client.get_register(*address).await?;
+            let mut older = segment_entries(&segment);
+            older.extend(entries);
+            entries = older;
+        }
+
+        let skip = entries.len().saturating_sub(count);
+        Ok(entries.split_off(skip))
+    }
+
+    /// Fetch the latest state of the last segment, following any segments linked since.
+    async fn refresh_tail(&mut self) -> Result<()> {
+        self.tail = follow_segments(&self.client, &mut self.segments).await?;
+        Ok(())
+    }
+
+    /// Link the full tail segment to a new one, which becomes the tail.
+    async fn start_segment(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        // Another writer may have linked the segment already.
+        if let Some(next) = next_segment(&self.tail) {
+            self.segments.push(next);
+            self.tail = self.client.get_register(next).await?;
+            return Ok(NanoTokens::zero());
+        }
+
+        let index = self.segments.len() as u64;
+        let meta = segment_meta(self.address().meta(), index);
+        let (next, cost) = self
+            .client
+            .create_and_pay_for_register(meta, wallet_client, verify_store)
+            .await?;
+        info!(
+            "Register log {:?} continues in segment {index} at {:?}",
+            self.address(),
+            next.address()
+        );
+
+        let link = encode_entry(&LogEntry::Next(*next.address()))?;
+        if let Err(err) = self
+            .tail
+            .write_merging_branches_online(&link, verify_store)
+            .await
+        {
+            // The segment may have been filled up, and linked, by another writer meanwhile.
+            self.refresh_tail().await?;
+            if self.segments.len() as u64 > index {
+                warn!(
+                    "Register log {:?} was linked to segment {index} by another writer, \
+                    leaving {:?} unused",
+                    self.address(),
+                    next.address()
+                );
+                return Ok(cost);
+            }
+            return Err(err);
+        }
+        self.segments.push(*next.address());
+        self.tail = next;
+        Ok(cost)
+    }
+}
+
+/// Meta of the segment at `index` of the log whose first segment has `log_meta`.
+fn segment_meta(log_meta: XorName, index: u64) -> XorName {
+    XorName::from_content_parts(&[LOG_SEGMENT_PREFIX, &log_meta.0, &index.to_be_bytes()])
+}
+
+/// Fetch the last of `segments`, following the links to the segments after it, which are
+/// added to `segments`. Returns the segment which is now last.
+async fn follow_segments(
+    client: &Client,
+    segments: &mut Vec<RegisterAddress>,
+) -> Result<ClientRegister> {
+    let mut tail = client.get_register(segments[segments.len() - 1]).await?;
+    while let Some(next) = next_segment(&tail) {
+        debug!(
+            "Following register log {:?} to segment {next:?}",
+            segments[0]
+        );
+        segments.push(next);
+        tail = client.get_register(next).await?;
+    }
+    Ok(tail)
+}
+
+/// Whether a write failed as the segment already holds as many entries as a Register can.
+fn is_segment_full(err: &Error) -> bool {
+    let too_many_entries = |err: &RegisterError| matches!(err, RegisterError::TooManyEntries(_));
+    match err {
+        Error::Register(err) => too_many_entries(err),
+        Error::RegisterCmdsRejected(rejected) => {
+            rejected.iter().any(|(_, err)| too_many_entries(err))
+        }
+        _ => false,
+    }
+}
+
+/// Address of the segment following the given one, if it was linked.
+fn next_segment(segment: &ClientRegister) -> Option<RegisterAddress> {
+    log_entries(segment.address(), segment.history())
+        .into_iter()
+        .find_map(|entry| match entry {
+            LogEntry::Next(address) => Some(address),
+            LogEntry::Data(_) => None,
+        })
+}
+
+/// The data entries of a segment, oldest first.
+fn segment_entries(segment: &ClientRegister) -> Vec<Entry> {
+    log_entries(segment.address(), segment.history())
+        .into_iter()
+        .filter_map(|entry| match entry {
+            LogEntry::Data(entry) => Some(entry),
+            LogEntry::Next(_) => None,
+        })
+        .collect()
+}
+
+/// Decode the history of the segment at `address`, skipping the entries which are not valid
+/// log entries, as anyone allowed to write to the segment may have written them.
+fn log_entries(address: &RegisterAddress, history: Vec<(EntryHash, Entry)>) -> Vec<LogEntry> {
+    let mut entries = vec![];
+    for (hash, bytes) in history {
+        match decode_entry(hash, &bytes, Error::RegisterLogEntryParsingFailed) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => warn!("Skipping entry of register log segment {address:?}: {error}"),
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+
+    #[test]
+    fn segments_have_distinct_metas() {
+        let log_meta = XorName::random(&mut rand::thread_rng());
+        assert_ne!(segment_meta(log_meta, 1), log_meta);
+        assert_ne!(segment_meta(log_meta, 1), segment_meta(log_meta, 2));
+        assert_eq!(segment_meta(log_meta, 1), segment_meta(log_meta, 1));
+    }
+
+    fn roundtrip(entry: &LogEntry) -> Result<LogEntry> {
+        decode_entry(
+            EntryHash::default(),
+            &encode_entry(entry)?,
+            Error::RegisterLogEntryParsingFailed,
+        )
+    }
+
+    #[test]
+    fn largest_log_entries_fit_in_a_register_entry() -> Result<()> {
+        let largest = encode_entry(&LogEntry::Data(vec![7; MAX_LOG_ENTRY_SIZE]))?;
+        assert_eq!(largest.len(), MAX_REG_ENTRY_SIZE);
+
+        let empty = encode_entry(&LogEntry::Data(vec![]))?;
+        assert_eq!(empty.len(), DATA_ENTRY_OVERHEAD);
+        Ok(())
+    }
+
+    #[test]
+    fn undecodable_entries_are_skipped() -> Result<()> {
+        let address = RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            SecretKey::random().public_key(),
+        );
+        let data = LogEntry::Data(vec![1, 2, 3]);
+        let next = LogEntry::Next(address);
+        let history = vec![
+            (EntryHash::default(), encode_entry(&data)?),
+            (EntryHash::default(), b"not a log entry".to_vec()),
+            (EntryHash::default(), encode_entry(&next)?),
+        ];
+        assert_eq!(log_entries(&address, history), vec![data, next]);
+        Ok(())
+    }
+
+    #[test]
+    fn full_segments_are_detected() {
+        assert!(is_segment_full(&Error::Register(
+            RegisterError::TooManyEntries(1024)
+        )));
+        assert!(!is_segment_full(&Error::Register(
+            RegisterError::NoSuchEntry(EntryHash::default())
+        )));
+    }
+
+    #[test]
+    fn log_entries_roundtrip() -> Result<()> {
+        let data = LogEntry::Data(vec![1, 2, 3]);
+        assert_eq!(roundtrip(&data)?, data);
+
+        let address = RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            SecretKey::random().public_key(),
+        );
+        let next = LogEntry::Next(address);
+        assert_eq!(roundtrip(&next)?, next);
+        Ok(())
+    }
+}
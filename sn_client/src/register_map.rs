@@ -0,0 +1,236 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    register::{decode_entry, encode_entry},
+    Client, ClientRegister, WalletClient,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sn_protocol::storage::RegisterAddress;
+use sn_registers::{Entry, EntryHash};
+use sn_transfers::NanoTokens;
+use std::collections::{btree_map, BTreeMap};
+use xor_name::XorName;
+
+/// An operation on a `RegisterMap`, as stored in one entry of its Register.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum MapOp<K, V> {
+    /// Set the value of a key.
+    Set(K, V),
+    /// Remove a key.
+    Remove(K),
+}
+
+/// A replicated key-value map stored in a Register.
+///
+/// Each entry of the Register holds a set or remove operation, written atop all the latest
+/// ones. The map is rebuilt by applying the operations in causal order, so an operation always
+/// overrides the ones it was written atop of. Concurrent operations are applied in the order of
+/// `Register::history`, so every replica resolves them to the same map.
+pub struct RegisterMap<K, V> {
+    register: ClientRegister,
+    map: BTreeMap<K, V>,
+}
+
+impl<K, V> RegisterMap<K, V>
+where
+    K: Ord + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Create the Register of a new map on the network, paying for it.
+    pub async fn create(
+        client: &Client,
+        meta: XorName,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens)> {
+        let (register, cost) = client
+            .create_and_pay_for_register(meta, wallet_client, verify_store)
+            .await?;
+        Ok((Self::from_register(register), cost))
+    }
+
+    /// Retrieve the map backed by the Register at `address`.
+    pub async fn retrieve(client: &Client, address: RegisterAddress) -> Result<Self> {
+        let register = client.get_register(address).await?;
+        Ok(Self::from_register(register))
+    }
+
+    /// Return the address of the backing Register.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Return the backing Register.
+    pub fn register(&self) -> &ClientRegister {
+        &self.register
+    }
+
+    /// Return the value of `key`, if it is set.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Iterate over the keys and values of the map, in key order.
+    pub fn iter(&self) -> btree_map::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Return the number of keys set in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Return true if no key is set in the map.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Set the value of `key`, and push the change to the network.
+    pub async fn set(&mut self, key: K, value: V, verify_store: bool) -> Result<()> {
+        self.apply_online(MapOp::Set(key, value), verify_store)
+            .await
+    }
+
+    /// Remove `key` from the map, and push the change to the network.
+    pub async fn remove(&mut self, key: K, verify_store: bool) -> Result<()> {
+        self.apply_online(MapOp::Remove(key), verify_store).await
+    }
+
+    /// Sync the backing Register with the replicas on the network, and rebuild the map
+    /// with the operations made by other writers.
+    pub async fn sync(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        let cost = self.register.sync(wallet_client, verify_store).await?;
+        self.map = fold(self.register.history());
+        Ok(cost)
+    }
+
+    fn from_register(register: ClientRegister) -> Self {
+        let map = fold(register.history());
+        Self { register, map }
+    }
+
+    async fn apply_online(&mut self, op: MapOp<K, V>, verify_store: bool) -> Result<()> {
+        let entry = encode_entry(&op)?;
+        self.register
+            .write_merging_branches_online(&entry, verify_store)
+            .await?;
+        self.map = fold(self.register.history());
+        Ok(())
+    }
+}
+
+/// Rebuild a map from the entries of its Register, oldest first.
+///
+/// Entries which are not operations on a map of this type are skipped, so that a single bad
+/// entry, which anyone allowed to write can add, does not make the whole map unreadable.
+fn fold<K, V>(history: Vec<(EntryHash, Entry)>) -> BTreeMap<K, V>
+where
+    K: Ord + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    let mut map = BTreeMap::new();
+    for (hash, entry) in history {
+        match decode_entry(hash, &entry, Error::RegisterMapOpParsingFailed) {
+            Ok(MapOp::Set(key, value)) => {
+                let _ = map.insert(key, value);
+            }
+            Ok(MapOp::Remove(key)) => {
+                let _ = map.remove(&key);
+            }
+            Err(error) => warn!("Skipping register map entry: {error}"),
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use sn_registers::{Permissions, Register};
+    use std::collections::BTreeSet;
+
+    fn entry(op: MapOp<String, u32>) -> Result<(EntryHash, Entry)> {
+        Ok((EntryHash::default(), encode_entry(&op)?))
+    }
+
+    #[test]
+    fn later_ops_override_earlier_ones() -> Result<()> {
+        let history = vec![
+            entry(MapOp::Set("a".to_string(), 1))?,
+            entry(MapOp::Set("b".to_string(), 2))?,
+            entry(MapOp::Set("a".to_string(), 3))?,
+            entry(MapOp::Remove("b".to_string()))?,
+            entry(MapOp::Remove("c".to_string()))?,
+        ];
+
+        let map: BTreeMap<String, u32> = fold(history);
+        assert_eq!(map, BTreeMap::from([("a".to_string(), 3)]));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_ops_are_skipped() -> Result<()> {
+        let history = vec![
+            entry(MapOp::Set("a".to_string(), 1))?,
+            (EntryHash::default(), vec![9]),
+            entry(MapOp::Set("b".to_string(), 2))?,
+        ];
+        let map: BTreeMap<String, u32> = fold(history);
+        assert_eq!(
+            map,
+            BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_writes_resolve_the_same_on_every_replica() -> Result<()> {
+        let owner = SecretKey::random();
+        let writer = SecretKey::random();
+        let mut replica_a = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_with([writer.public_key()]),
+        );
+        let (base, _) = replica_a.write(
+            encode_entry(&MapOp::Set("key".to_string(), 0_u32))?,
+            BTreeSet::new(),
+            &owner,
+        )?;
+        let mut replica_b = replica_a.clone();
+
+        // both replicas set the same key atop the same entry, without seeing each other's write
+        let (_, op_a) = replica_a.write(
+            encode_entry(&MapOp::Set("key".to_string(), 1_u32))?,
+            BTreeSet::from([base]),
+            &owner,
+        )?;
+        let (_, op_b) = replica_b.write(
+            encode_entry(&MapOp::Set("key".to_string(), 2_u32))?,
+            BTreeSet::from([base]),
+            &writer,
+        )?;
+        replica_a.apply_op(op_b)?;
+        replica_b.apply_op(op_a)?;
+        assert_eq!(replica_a.read().len(), 2);
+
+        let map_a: BTreeMap<String, u32> = fold(replica_a.history());
+        let map_b: BTreeMap<String, u32> = fold(replica_b.history());
+        assert_eq!(map_a, map_b);
+        assert_eq!(map_a.len(), 1);
+        assert!(matches!(map_a.get("key"), Some(1) | Some(2)));
+        Ok(())
+    }
+}
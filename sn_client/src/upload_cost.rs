@@ -0,0 +1,195 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    folders::walk_files,
+    Files,
+};
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_transfers::{MainPubkey, NanoTokens, NETWORK_ROYALTIES_AMOUNT_PER_ADDR};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::create_dir_all,
+    path::Path,
+};
+use tempfile::tempdir;
+use tokio::task::JoinSet;
+use xor_name::XorName;
+
+/// Estimated cost of uploading some content, as quoted by the network.
+#[derive(Clone, Debug, Default)]
+pub struct UploadCostEstimate {
+    /// Storage cost quoted for each chunk by its close group, royalties excluded.
+    pub chunk_costs: BTreeMap<XorName, NanoTokens>,
+    /// Chunks for which no quote could be obtained, hence not part of the totals.
+    pub failed_chunks: BTreeSet<XorName>,
+    /// Sum of the storage costs of all the quoted chunks.
+    pub storage_cost: NanoTokens,
+    /// Network royalties due on top of the storage cost.
+    pub royalties_fees: NanoTokens,
+}
+
+impl UploadCostEstimate {
+    /// Total of the storage cost and the royalties.
+    pub fn total_cost(&self) -> Result<NanoTokens> {
+        self.storage_cost
+            .checked_add(self.royalties_fees)
+            .ok_or(Error::TotalPriceTooHigh)
+    }
+
+    // Account for the outcome of querying the store costs of the chunk `name`.
+    fn add_store_costs(
+        &mut self,
+        name: XorName,
+        costs: std::result::Result<Vec<(MainPubkey, NanoTokens)>, sn_networking::Error>,
+    ) -> Result<()> {
+        match costs {
+            Ok(costs) => self.add_quotes(name, costs),
+            Err(error) => {
+                warn!("Cannot get store cost for chunk {name:?} with error {error:?}");
+                let _ = self.failed_chunks.insert(name);
+                Ok(())
+            }
+        }
+    }
+
+    // Account for the quotes of the close group of the chunk `name`, along with its royalties.
+    fn add_quotes(&mut self, name: XorName, quotes: Vec<(MainPubkey, NanoTokens)>) -> Result<()> {
+        let mut chunk_cost = NanoTokens::zero();
+        for (_payee, cost) in quotes {
+            chunk_cost = chunk_cost
+                .checked_add(cost)
+                .ok_or(Error::TotalPriceTooHigh)?;
+        }
+        self.storage_cost = self
+            .storage_cost
+            .checked_add(chunk_cost)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        // Royalties are paid per chunk, on top of what its close group asks for.
+        self.royalties_fees = self
+            .royalties_fees
+            .checked_add(NETWORK_ROYALTIES_AMOUNT_PER_ADDR)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        let _ = self.chunk_costs.insert(name, chunk_cost);
+        Ok(())
+    }
+}
+
+impl Files {
+    /// Estimate the cost of uploading the file, or all the files of the directory, at `path`.
+    ///
+    /// The content is chunked and the store cost of every chunk is queried from its close group
+    /// in parallel, `ClientConfig::batch_size` chunks at a time. Nothing is paid for and the wallet is left untouched.
+    pub async fn estimate_upload_cost(&self, path: &Path) -> Result<UploadCostEstimate> {
+        // Temp folder to hold SE chunks, which is cleaned up automatically once out of scope.
+        let temp_dir = tempdir()?;
+        let mut chunk_names = BTreeSet::new();
+
+        // An unreadable entry fails the estimate, rather than leaving its cost out.
+        for (index, entry) in walk_files(path)?.into_iter().enumerate() {
+            // Each file using individual dir for temp SE chunks.
+            let file_chunks_dir = temp_dir.path().join(index.to_string());
+            create_dir_all(&file_chunks_dir)?;
+            let (_head_address, _size, chunks) = self.chunk_file(entry.path(), &file_chunks_dir)?;
+            chunk_names.extend(chunks.into_iter().map(|(name, _)| name));
+        }
+
+        self.estimate_chunks_cost(chunk_names).await
+    }
+
+    /// Query the store cost of each of the given chunks, without paying for them.
+    ///
+    /// At most `ClientConfig::batch_size` queries are in flight at once.
+    pub async fn estimate_chunks_cost(
+        &self,
+        chunk_names: impl IntoIterator<Item = XorName>,
+    ) -> Result<UploadCostEstimate> {
+        let batch_size = self.client().config().batch_size();
+        let mut estimate = UploadCostEstimate::default();
+        let mut tasks = JoinSet::new();
+        for name in chunk_names {
+            if tasks.len() >= batch_size {
+                if let Some(result) = tasks.join_next().await {
+                    let (name, costs) = result?;
+                    estimate.add_store_costs(name, costs)?;
+                }
+            }
+
+            let client = self.client().clone();
+            let _ = tasks.spawn(async move {
+                let address = NetworkAddress::from_chunk_address(ChunkAddress::new(name));
+                let costs = client.network.get_store_costs_from_network(address).await;
+                (name, costs)
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            let (name, costs) = result?;
+            estimate.add_store_costs(name, costs)?;
+        }
+
+        Ok(estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+
+    fn quotes(amounts: &[u64]) -> Vec<(MainPubkey, NanoTokens)> {
+        amounts
+            .iter()
+            .map(|amount| {
+                (
+                    MainPubkey::new(SecretKey::random().public_key()),
+                    NanoTokens::from(*amount),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quotes_are_summed_with_royalties_per_chunk() -> Result<()> {
+        let mut estimate = UploadCostEstimate::default();
+        let first = XorName::random(&mut rand::thread_rng());
+        let second = XorName::random(&mut rand::thread_rng());
+        estimate.add_quotes(first, quotes(&[10, 20]))?;
+        estimate.add_quotes(second, quotes(&[5]))?;
+
+        assert_eq!(
+            estimate.chunk_costs.get(&first),
+            Some(&NanoTokens::from(30))
+        );
+        assert_eq!(
+            estimate.chunk_costs.get(&second),
+            Some(&NanoTokens::from(5))
+        );
+        assert_eq!(estimate.storage_cost, NanoTokens::from(35));
+        assert_eq!(
+            estimate.royalties_fees.as_nano(),
+            2 * NETWORK_ROYALTIES_AMOUNT_PER_ADDR.as_nano()
+        );
+        assert_eq!(
+            estimate.total_cost()?.as_nano(),
+            35 + 2 * NETWORK_ROYALTIES_AMOUNT_PER_ADDR.as_nano()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn overflowing_quotes_are_rejected() {
+        let mut estimate = UploadCostEstimate::default();
+        let name = XorName::random(&mut rand::thread_rng());
+        assert!(matches!(
+            estimate.add_quotes(name, quotes(&[u64::MAX, 1])),
+            Err(Error::TotalPriceTooHigh)
+        ));
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Files,
+};
+use serde::{Deserialize, Serialize};
+use sn_transfers::{LocalWallet, NanoTokens};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use xor_name::XorName;
+
+// Filenames within the dir of an upload session.
+const JOURNAL_FILE_NAME: &str = "journal";
+const CHUNKS_DIR_NAME: &str = "chunks";
+
+/// Progress of a single chunk within an upload session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ChunkUploadState {
+    /// The chunk has been produced by self-encryption, but not paid for yet.
+    Pending,
+    /// A payment for the chunk is held in the local wallet.
+    Paid,
+    /// The chunk has been sent to the network.
+    Uploaded,
+    /// The chunk has been fetched back from the network.
+    Verified,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct JournaledChunk {
+    path: PathBuf,
+    state: ChunkUploadState,
+}
+
+/// On-disk record of the chunks of an upload session and how far each of them got.
+///
+/// Each session lives in its own dir, named after the uploaded path, which also holds the
+/// self-encrypted chunks so that they survive the process dying part way through.
+/// The journal is written to disk on every state change, allowing an interrupted upload to be
+/// resumed without paying for or uploading the completed chunks again.
+#[derive(Clone, Debug)]
+pub struct UploadJournal {
+    session_dir: PathBuf,
+    source: PathBuf,
+    chunks: BTreeMap<XorName, JournaledChunk>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredJournal {
+    source: PathBuf,
+    chunks: BTreeMap<XorName, JournaledChunk>,
+}
+
+impl UploadJournal {
+    /// Start a new session for uploading `source`, discarding any previous session for it.
+    pub fn create(journals_dir: &Path, source: &Path) -> Result<Self> {
+        let session_dir = session_dir(journals_dir, source);
+        if session_dir.exists() {
+            fs::remove_dir_all(&session_dir)?;
+        }
+        fs::create_dir_all(session_dir.join(CHUNKS_DIR_NAME))?;
+
+        let journal = Self {
+            session_dir,
+            source: source.to_path_buf(),
+            chunks: BTreeMap::new(),
+        };
+        journal.store()?;
+        Ok(journal)
+    }
+
+    /// Load the session for uploading `source`, if one was left behind by a previous run.
+    pub fn load(journals_dir: &Path, source: &Path) -> Result<Option<Self>> {
+        let session_dir = session_dir(journals_dir, source);
+        let journal_path = session_dir.join(JOURNAL_FILE_NAME);
+        if !journal_path.is_file() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&journal_path)?;
+        let stored: StoredJournal =
+            bincode::deserialize(&bytes).map_err(|_| Error::UploadJournalParsingFailed)?;
+        Ok(Some(Self {
+            session_dir,
+            source: stored.source,
+            chunks: stored.chunks,
+        }))
+    }
+
+    /// The path being uploaded in this session.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Dir the chunks of this session shall be written to.
+    pub fn chunks_dir(&self) -> PathBuf {
+        self.session_dir.join(CHUNKS_DIR_NAME)
+    }
+
+    /// Record the chunks making up the upload.
+    /// Chunks already known to the journal keep their current state.
+    pub fn add_chunks(
+        &mut self,
+        chunks: impl IntoIterator<Item = (XorName, PathBuf)>,
+    ) -> Result<()> {
+        for (name, path) in chunks {
+            let _ = self.chunks.entry(name).or_insert(JournaledChunk {
+                path,
+                state: ChunkUploadState::Pending,
+            });
+        }
+        self.store()
+    }
+
+    /// Move the given chunks to `state` and persist the journal.
+    pub fn set_state(
+        &mut self,
+        names: impl IntoIterator<Item = XorName>,
+        state: ChunkUploadState,
+    ) -> Result<()> {
+        for name in names {
+            if let Some(chunk) = self.chunks.get_mut(&name) {
+                chunk.state = state;
+            }
+        }
+        self.store()
+    }
+
+    /// Current state of a chunk, if it is part of this session.
+    pub fn state(&self, name: &XorName) -> Option<ChunkUploadState> {
+        self.chunks.get(name).map(|chunk| chunk.state)
+    }
+
+    /// All the chunks currently in `state`.
+    pub fn chunks_in_state(&self, state: ChunkUploadState) -> Vec<(XorName, PathBuf)> {
+        self.chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.state == state)
+            .map(|(name, chunk)| (*name, chunk.path.clone()))
+            .collect()
+    }
+
+    /// Number of chunks in each state.
+    pub fn summary(&self) -> BTreeMap<ChunkUploadState, usize> {
+        let mut summary = BTreeMap::new();
+        for chunk in self.chunks.values() {
+            *summary.entry(chunk.state).or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// Returns `true` once every chunk has been verified.
+    pub fn is_complete(&self) -> bool {
+        self.chunks
+            .values()
+            .all(|chunk| chunk.state == ChunkUploadState::Verified)
+    }
+
+    /// Mark pending chunks as paid if the wallet already holds a payment for them,
+    /// so that an interrupted session does not pay twice.
+    ///
+    /// Returns the number of chunks whose payment was reused.
+    pub fn reuse_wallet_payments(&mut self, wallet: &LocalWallet) -> Result<usize> {
+        let paid: Vec<_> = self
+            .chunks
+            .iter()
+            .filter(|(name, chunk)| {
+                chunk.state == ChunkUploadState::Pending
+                    && !wallet.get_payment_cash_notes(name).is_empty()
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        let reused = paid.len();
+        self.set_state(paid, ChunkUploadState::Paid)?;
+        Ok(reused)
+    }
+
+    /// Remove the session from disk, including its chunks.
+    pub fn remove(self) -> Result<()> {
+        fs::remove_dir_all(&self.session_dir)?;
+        Ok(())
+    }
+
+    fn store(&self) -> Result<()> {
+        let stored = StoredJournal {
+            source: self.source.clone(),
+            chunks: self.chunks.clone(),
+        };
+        let bytes = bincode::serialize(&stored).map_err(|_| Error::UploadJournalParsingFailed)?;
+
+        // Write to a temp file first, so a crash mid-write can not corrupt the journal.
+        let journal_path = self.session_dir.join(JOURNAL_FILE_NAME);
+        let tmp_path = journal_path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, journal_path)?;
+        Ok(())
+    }
+}
+
+/// Sessions are keyed by the hash of the uploaded path.
+fn session_dir(journals_dir: &Path, source: &Path) -> PathBuf {
+    let source = source
+        .canonicalize()
+        .unwrap_or_else(|_| source.to_path_buf());
+    let id = XorName::from_content(source.to_string_lossy().as_bytes());
+    journals_dir.join(hex::encode(id))
+}
+
+/// What `Files::upload_journaled_chunks` did to get the chunks of a journal to the network.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UploadSummary {
+    /// Total paid, storage cost and royalties, repayments included.
+    pub cost: NanoTokens,
+    /// Number of chunks paid for, repayments included.
+    pub paid_chunks: usize,
+    /// Number of chunks uploaded, re-uploads included.
+    pub uploaded_chunks: usize,
+    /// Number of chunks whose payment was already held in the wallet.
+    pub reused_payments: usize,
+    /// Number of chunks skipped as they were already stored on the network.
+    pub existing_chunks: usize,
+    /// What the skipped chunks would have cost, as quoted by their close groups.
+    pub saved_cost: NanoTokens,
+    /// Balance of the wallet once done.
+    pub new_balance: NanoTokens,
+}
+
+impl Files {
+    /// Pay for, upload and optionally verify all the chunks of the journal which are not done yet,
+    /// `batch_size` chunks at a time, updating the journal as each step completes.
+    ///
+    /// Chunks failing to upload are left paid for, so a later call retries them without paying
+    /// again. Chunks failing verification are repaid and uploaded again until all are verified.
+    pub async fn upload_journaled_chunks(
+        &self,
+        journal: &mut UploadJournal,
+        batch_size: usize,
+        verify_store: bool,
+        show_holders: bool,
+    ) -> Result<UploadSummary> {
+        let mut summary = UploadSummary {
+            reused_payments: journal.reuse_wallet_payments(&self.wallet()?.into_wallet())?,
+            ..Default::default()
+        };
+        if summary.reused_payments > 0 {
+            info!(
+                "Reusing existing payments for {} chunks",
+                summary.reused_payments
+            );
+        }
+
+        let (_, existing_chunks) = self
+            .split_existing_chunks(journal.chunks_in_state(ChunkUploadState::Pending))
+            .await?;
+        summary.existing_chunks = existing_chunks.len();
+        for (_, quoted_cost) in existing_chunks.iter() {
+            summary.saved_cost = summary
+                .saved_cost
+                .checked_add(*quoted_cost)
+                .ok_or(Error::TotalPriceTooHigh)?;
+        }
+        journal.set_state(
+            existing_chunks.into_iter().map(|(name, _)| name),
+            ChunkUploadState::Verified,
+        )?;
+
+        loop {
+            for batch in journal
+                .chunks_in_state(ChunkUploadState::Pending)
+                .chunks(batch_size)
+            {
+                let (cost, _new_balance) = self
+                    .pay_for_chunks(batch.iter().map(|(name, _)| *name).collect())
+                    .await?;
+                summary.cost = summary
+                    .cost
+                    .checked_add(cost)
+                    .ok_or(Error::TotalPriceTooHigh)?;
+                summary.paid_chunks += batch.len();
+                journal.set_state(batch.iter().map(|(name, _)| *name), ChunkUploadState::Paid)?;
+            }
+
+            for batch in journal
+                .chunks_in_state(ChunkUploadState::Paid)
+                .chunks(batch_size)
+            {
+                let results = self.upload_chunks_from_paths(batch, show_holders).await;
+                let mut uploaded = vec![];
+                for ((name, _), result) in batch.iter().zip(results) {
+                    match result {
+                        Ok(()) => uploaded.push(*name),
+                        Err(error) => warn!("Failed to upload chunk {name:?}: {error}"),
+                    }
+                }
+                summary.uploaded_chunks += uploaded.len();
+                journal.set_state(uploaded, ChunkUploadState::Uploaded)?;
+            }
+
+            if !verify_store {
+                break;
+            }
+
+            let uploaded = journal.chunks_in_state(ChunkUploadState::Uploaded);
+            let failed = self
+                .verify_uploaded_chunks(uploaded.clone(), batch_size)
+                .await?;
+            let failed_names: Vec<_> = failed.iter().map(|(name, _)| *name).collect();
+            journal.set_state(
+                uploaded
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .filter(|name| !failed_names.contains(name)),
+                ChunkUploadState::Verified,
+            )?;
+
+            if failed_names.is_empty() {
+                break;
+            }
+            info!("Repaying for {} chunks", failed_names.len());
+            self.broadcast_repayment(&failed);
+            journal.set_state(failed_names, ChunkUploadState::Pending)?;
+        }
+
+        summary.new_balance = self.wallet()?.balance();
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_persists_chunk_states() -> Result<()> {
+        let journals_dir = tempfile::tempdir()?;
+        let source = Path::new("/some/uploaded/dir");
+        let mut rng = rand::thread_rng();
+        let first = XorName::random(&mut rng);
+        let second = XorName::random(&mut rng);
+
+        let mut journal = UploadJournal::create(journals_dir.path(), source)?;
+        journal.add_chunks(vec![
+            (first, journal.chunks_dir().join("first")),
+            (second, journal.chunks_dir().join("second")),
+        ])?;
+        journal.set_state([first], ChunkUploadState::Uploaded)?;
+
+        let mut loaded =
+            UploadJournal::load(journals_dir.path(), source)?.expect("journal to have been stored");
+        assert_eq!(loaded.state(&first), Some(ChunkUploadState::Uploaded));
+        assert_eq!(loaded.state(&second), Some(ChunkUploadState::Pending));
+        assert!(!loaded.is_complete());
+
+        // Adding chunks again on resume keeps the progress made so far.
+        loaded.add_chunks(vec![(first, PathBuf::from("first"))])?;
+        assert_eq!(loaded.state(&first), Some(ChunkUploadState::Uploaded));
+
+        loaded.set_state([first, second], ChunkUploadState::Verified)?;
+        assert!(loaded.is_complete());
+        loaded.remove()?;
+        assert!(UploadJournal::load(journals_dir.path(), source)?.is_none());
+        Ok(())
+    }
+}
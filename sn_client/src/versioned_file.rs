@@ -0,0 +1,136 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    register::{decode_entry, encode_entry},
+    Client, ClientRegister, WalletClient,
+};
+use sn_protocol::storage::{ChunkAddress, RegisterAddress};
+use sn_registers::{Entry, EntryHash};
+use sn_transfers::NanoTokens;
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+/// A single version of a `VersionedFile`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileVersion {
+    /// Hash of the register entry holding this version.
+    pub hash: EntryHash,
+    /// Head address of the file content, as returned by `Files::chunk_file`.
+    pub address: ChunkAddress,
+    /// Hashes of the versions this one was published atop of.
+    pub previous: BTreeSet<EntryHash>,
+}
+
+/// A file whose successive versions are published to a single Register.
+///
+/// Each entry of the Register holds the head address of one version, written atop the
+/// versions it supersedes, so the whole history can be followed from the Register address.
+pub struct VersionedFile {
+    register: ClientRegister,
+}
+
+impl VersionedFile {
+    /// Create the Register of a new versioned file on the network, paying for it.
+    pub async fn create(
+        client: &Client,
+        meta: XorName,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens)> {
+        let (register, cost) = client
+            .create_and_pay_for_register(meta, wallet_client, verify_store)
+            .await?;
+        Ok((Self { register }, cost))
+    }
+
+    /// Retrieve the versioned file backed by the Register at `address`.
+    pub async fn retrieve(client: &Client, address: RegisterAddress) -> Result<Self> {
+        let register = client.get_register(address).await?;
+        Ok(Self { register })
+    }
+
+    /// Return the address of the backing Register.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Return the backing Register.
+    pub fn register(&self) -> &ClientRegister {
+        &self.register
+    }
+
+    /// Publish the file at `address` as the new latest version.
+    ///
+    /// The version is written atop all the current latest versions, merging any branches
+    /// left by concurrent publishers.
+    pub async fn publish_version(
+        &mut self,
+        address: ChunkAddress,
+        verify_store: bool,
+    ) -> Result<()> {
+        let entry = encode_entry(&address)?;
+        self.register
+            .write_merging_branches_online(&entry, verify_store)
+            .await?;
+        info!("Published version {address:?} of {:?}", self.address());
+        Ok(())
+    }
+
+    /// Return the latest version, if any was published.
+    ///
+    /// When concurrent publishes left several latest versions, the one with the
+    /// greatest entry hash is picked, so that every reader agrees on it.
+    pub fn latest(&self) -> Result<Option<FileVersion>> {
+        self.register
+            .read()
+            .into_iter()
+            .next_back()
+            .map(|(hash, entry)| self.version(hash, &entry))
+            .transpose()
+    }
+
+    /// Return all the published versions, oldest first.
+    ///
+    /// A version always comes after the versions it was published atop of;
+    /// concurrent versions are ordered by entry hash.
+    pub fn history(&self) -> Result<Vec<FileVersion>> {
+        self.register
+            .history()
+            .into_iter()
+            .map(|(hash, entry)| self.version(hash, &entry))
+            .collect()
+    }
+
+    fn version(&self, hash: EntryHash, entry: &Entry) -> Result<FileVersion> {
+        Ok(FileVersion {
+            hash,
+            address: decode_entry(hash, entry, Error::FileVersionParsingFailed)?,
+            previous: self.register.children(hash)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_entries_roundtrip() -> Result<()> {
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let hash = EntryHash::default();
+        let entry = encode_entry(&address)?;
+        let decoded: ChunkAddress = decode_entry(hash, &entry, Error::FileVersionParsingFailed)?;
+        assert_eq!(decoded, address);
+        let result: Result<ChunkAddress> =
+            decode_entry(hash, &[1, 2], Error::FileVersionParsingFailed);
+        assert!(matches!(result, Err(Error::FileVersionParsingFailed(_))));
+        Ok(())
+    }
+}
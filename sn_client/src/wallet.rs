@@ -8,7 +8,7 @@
 
 use crate::Error;
 
-use super::{error::Result, Client};
+use super::{error::Result, Client, ClientEvent};
 use futures::{future::join_all, TryFutureExt};
 use sn_protocol::NetworkAddress;
 use sn_transfers::{
@@ -140,6 +140,8 @@ impl WalletClient {
     ) -> WalletResult<(NanoTokens, NanoTokens)> {
         let verify_store = true;
         let mut payment_map = BTreeMap::default();
+        // keep track of the full address of each record, as only its xorname is in the payment map
+        let mut paid_addresses = BTreeMap::default();
 
         let mut tasks = JoinSet::new();
         // we can collate all the payments together into one transfer
@@ -166,6 +168,7 @@ impl WalletClient {
                 Ok((content_addr, Ok(costs))) => {
                     if let Some(xorname) = content_addr.as_xorname() {
                         let _ = payment_map.insert(xorname, costs);
+                        let _ = paid_addresses.insert(xorname, content_addr.clone());
                         debug!("Storecosts inserted into payment map for {content_addr:?}");
                     } else {
                         warn!("Cannot get store cost for a content that is not a data type: {content_addr:?}");
@@ -186,7 +189,18 @@ impl WalletClient {
             Ok((NanoTokens::zero(), NanoTokens::zero()))
         } else {
             self.wallet.adjust_payment_map(&mut payment_map);
-            self.pay_for_records(payment_map, verify_store).await
+            let addresses = payment_map
+                .keys()
+                .filter_map(|name| paid_addresses.get(name).cloned())
+                .collect();
+            let (storage_cost, royalties_fees) =
+                self.pay_for_records(payment_map, verify_store).await?;
+            self.client.broadcast_progress(ClientEvent::StoragePaid {
+                addresses,
+                storage_cost,
+                royalties_fees,
+            });
+            Ok((storage_cost, royalties_fees))
         }
     }
 
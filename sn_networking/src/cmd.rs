@@ -17,7 +17,7 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use sn_protocol::{
-    messages::{Request, Response},
+    messages::{RecordInfo, Request, Response},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::NanoTokens;
@@ -105,6 +105,11 @@ pub enum SwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<Option<Record>>,
     },
+    /// Get holder-side metadata about a record from the local RecordStore
+    GetLocalRecordInfo {
+        key: NetworkAddress,
+        sender: oneshot::Sender<RecordInfo>,
+    },
     /// Put record to network
     PutRecord {
         record: Record,
@@ -221,6 +226,9 @@ impl Debug for SwarmCmd {
                     PrettyPrintRecordKey::from(key)
                 )
             }
+            SwarmCmd::GetLocalRecordInfo { key, .. } => {
+                write!(f, "SwarmCmd::GetLocalRecordInfo {{ key: {key:?} }}")
+            }
             SwarmCmd::GetAllLocalRecordAddresses { .. } => {
                 write!(f, "SwarmCmd::GetAllLocalRecordAddresses")
             }
@@ -335,6 +343,22 @@ impl SwarmDriver {
                     .map(|rec| rec.into_owned());
                 let _ = sender.send(record);
             }
+            SwarmCmd::GetLocalRecordInfo { key, sender } => {
+                let record_key = key.to_record_key();
+                let store = self.swarm.behaviour_mut().kademlia.store_mut();
+                let meta = store.record_meta(&record_key);
+                let info = RecordInfo {
+                    holder: NetworkAddress::from_peer(self.self_peer_id),
+                    is_held: meta.is_some(),
+                    kind: meta.as_ref().and_then(|meta| meta.kind.clone()),
+                    size: meta.as_ref().map_or(0, |meta| meta.size),
+                    stored_at: meta.and_then(|meta| meta.stored_at),
+                    within_distance_range: store.is_within_distance_range(&record_key),
+                    store_cost: store.store_cost(),
+                    key,
+                };
+                let _ = sender.send(info);
+            }
             SwarmCmd::PutRecord { record, sender } => {
                 let record_key = PrettyPrintRecordKey::from(&record.key).into_owned();
                 trace!(
@@ -44,7 +44,7 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use sn_protocol::{
-    messages::{Query, QueryResponse, Request, Response},
+    messages::{Query, QueryResponse, RecordInfo, Request, Response},
     storage::{RecordHeader, RecordKind},
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey,
 };
@@ -406,6 +406,19 @@ impl Network {
             .map_err(|_e| Error::InternalMsgChannelDropped)
     }
 
+    /// Get holder-side metadata about the record at `key` from our local RecordStore.
+    pub async fn get_local_record_info(&self, key: &NetworkAddress) -> Result<RecordInfo> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetLocalRecordInfo {
+            key: key.clone(),
+            sender,
+        })?;
+
+        receiver
+            .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)
+    }
+
     /// Put `Record` to network
     /// Optionally verify the record is stored after putting it to network
     /// Retry up to `PUT_RECORD_RETRIES` times if we can't verify the record is stored
@@ -425,7 +438,7 @@ impl Network {
             );
 
             let res = self
-                .put_record_once(
+                .put_and_verify_record(
                     record.clone(),
                     verify_store.clone(),
                     expected_holders.clone(),
@@ -446,27 +459,30 @@ impl Network {
         ))
     }
 
-    async fn put_record_once(
-        &self,
-        record: Record,
-        verify_store: Option<Record>,
-        expected_holders: ExpectedHoldersList,
-    ) -> Result<()> {
-        let record_key = record.key.clone();
-        let pretty_key = PrettyPrintRecordKey::from(&record_key);
+    /// Put `Record` to network once, neither retrying nor verifying it is stored.
+    /// This is for callers applying their own retry and verification policies.
+    pub async fn put_record_once(&self, record: Record) -> Result<()> {
         info!(
             "Putting record of {} - length {:?} to network",
-            pretty_key,
+            PrettyPrintRecordKey::from(&record.key),
             record.value.len()
         );
 
         // Waiting for a response to avoid flushing to network too quick that causing choke
         let (sender, receiver) = oneshot::channel();
-        self.send_swarm_cmd(SwarmCmd::PutRecord {
-            record: record.clone(),
-            sender,
-        })?;
-        let response = receiver.await?;
+        self.send_swarm_cmd(SwarmCmd::PutRecord { record, sender })?;
+        receiver.await?
+    }
+
+    async fn put_and_verify_record(
+        &self,
+        record: Record,
+        verify_store: Option<Record>,
+        expected_holders: ExpectedHoldersList,
+    ) -> Result<()> {
+        let record_key = record.key.clone();
+        let pretty_key = PrettyPrintRecordKey::from(&record_key);
+        let response = self.put_record_once(record).await;
 
         if verify_store.is_some() || !expected_holders.is_empty() {
             // Small wait before we attempt to verify.
@@ -16,13 +16,18 @@ use libp2p::{
 };
 #[cfg(feature = "open-metrics")]
 use prometheus_client::metrics::gauge::Gauge;
-use sn_protocol::{NetworkAddress, PrettyPrintRecordKey};
+use sn_protocol::{
+    storage::{RecordHeader, RecordKind},
+    NetworkAddress, PrettyPrintRecordKey,
+};
 use sn_transfers::NanoTokens;
 use std::{
     borrow::Cow,
     collections::HashSet,
     fs,
+    io::Read,
     path::{Path, PathBuf},
+    time::SystemTime,
     vec,
 };
 use tokio::sync::mpsc;
@@ -48,6 +53,17 @@ pub struct NodeRecordStore {
     record_count_metric: Option<Gauge>,
 }
 
+/// Metadata of a record held in the store, read without loading its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocalRecordMeta {
+    /// The kind from the record header, if it could be parsed.
+    pub(crate) kind: Option<RecordKind>,
+    /// The size in bytes of the record value.
+    pub(crate) size: usize,
+    /// When the record was written to disk.
+    pub(crate) stored_at: Option<SystemTime>,
+}
+
 /// Configuration for a `DiskBackedRecordStore`.
 #[derive(Debug, Clone)]
 pub struct NodeRecordStoreConfig {
@@ -291,6 +307,56 @@ impl NodeRecordStore {
     pub(crate) fn set_distance_range(&mut self, distance_range: Distance) {
         self.distance_range = Some(distance_range);
     }
+
+    /// Returns `true` if the `Key` falls within our distance range.
+    /// A store without a distance range accepts all records.
+    pub(crate) fn is_within_distance_range(&self, key: &Key) -> bool {
+        match self.distance_range {
+            Some(distance_range) => {
+                distance_range >= self.local_key.distance(&KBucketKey::new(key.to_vec()))
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the metadata of the record, if we hold it.
+    /// Only the header is read from disk, the size and time come from the file metadata.
+    pub(crate) fn record_meta(&self, key: &Key) -> Option<LocalRecordMeta> {
+        if !self.records.contains(key) {
+            return None;
+        }
+        let file_path = self.config.storage_dir.join(Self::key_to_hex(key));
+        let file = match fs::File::open(&file_path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("Error while opening record file {file_path:?}: {err:?}");
+                return None;
+            }
+        };
+        let metadata = file.metadata().ok();
+
+        let mut prefix = Vec::with_capacity(RecordHeader::SIZE);
+        let kind = match file
+            .take(RecordHeader::SIZE as u64)
+            .read_to_end(&mut prefix)
+        {
+            Ok(_) => RecordHeader::from_prefix(&prefix)
+                .ok()
+                .map(|header| header.kind),
+            Err(err) => {
+                error!("Error while reading the header of record file {file_path:?}: {err:?}");
+                None
+            }
+        };
+
+        Some(LocalRecordMeta {
+            kind,
+            size: metadata
+                .as_ref()
+                .map_or(0, |metadata| metadata.len() as usize),
+            stored_at: metadata.and_then(|metadata| metadata.modified().ok()),
+        })
+    }
 }
 
 impl RecordStore for NodeRecordStore {
@@ -422,6 +488,14 @@ impl ClientRecordStore {
     }
 
     pub(crate) fn set_distance_range(&mut self, _distance_range: Distance) {}
+
+    pub(crate) fn is_within_distance_range(&self, _key: &Key) -> bool {
+        false
+    }
+
+    pub(crate) fn record_meta(&self, _key: &Key) -> Option<LocalRecordMeta> {
+        None
+    }
 }
 
 impl RecordStore for ClientRecordStore {
@@ -501,6 +575,7 @@ mod tests {
         kad::{KBucketKey, RecordKey},
     };
     use quickcheck::*;
+    use sn_protocol::storage::try_serialize_record;
     use tokio::runtime::Runtime;
 
     const MULITHASH_CODE: u64 = 0x12;
@@ -641,6 +716,38 @@ mod tests {
         assert!(store.get(&r.key).is_none());
     }
 
+    #[tokio::test]
+    async fn record_meta_is_read_from_the_stored_header() -> eyre::Result<()> {
+        let mut store = NodeRecordStore::with_config(PeerId::random(), Default::default(), None);
+        let record_key = NetworkAddress::from_peer(PeerId::random()).to_record_key();
+        assert_eq!(store.record_meta(&record_key), None);
+
+        let value = try_serialize_record(&vec![7u8; 1024], RecordKind::Chunk)?;
+        let record = Record {
+            key: record_key.clone(),
+            value: value.clone(),
+            publisher: None,
+            expires: None,
+        };
+        assert!(store.put_verified(record).is_ok());
+
+        // loop to allow the async disk write to complete.
+        let mut meta = None;
+        for _ in 0..10 {
+            meta = store.record_meta(&record_key);
+            if meta.as_ref().is_some_and(|meta| meta.size == value.len()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let meta = meta.wrap_err("record meta should be available once stored")?;
+        assert_eq!(meta.kind, Some(RecordKind::Chunk));
+        assert_eq!(meta.size, value.len());
+        assert!(meta.stored_at.is_some());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn pruning_on_full() -> Result<()> {
         let max_iterations = 10;
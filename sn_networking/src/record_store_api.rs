@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::record_store::{ClientRecordStore, NodeRecordStore};
+use crate::record_store::{ClientRecordStore, LocalRecordMeta, NodeRecordStore};
 use libp2p::kad::{
     store::{RecordStore, Result},
     KBucketDistance as Distance, ProviderRecord, Record, RecordKey,
@@ -127,4 +127,18 @@ impl UnifiedRecordStore {
             Self::Node(store) => store.set_distance_range(distance_range),
         }
     }
+
+    pub(crate) fn is_within_distance_range(&self, key: &RecordKey) -> bool {
+        match self {
+            Self::Client(store) => store.is_within_distance_range(key),
+            Self::Node(store) => store.is_within_distance_range(key),
+        }
+    }
+
+    pub(crate) fn record_meta(&self, key: &RecordKey) -> Option<LocalRecordMeta> {
+        match self {
+            Self::Client(store) => store.record_meta(key),
+            Self::Node(store) => store.record_meta(key),
+        }
+    }
 }
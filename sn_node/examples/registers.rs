@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_client::{Client, Error, WalletClient};
+use sn_client::{Client, ClientConfig, Error, WalletClient};
 use sn_registers::RegisterAddress;
 use sn_transfers::LocalWallet;
 use xor_name::XorName;
@@ -44,7 +44,7 @@ async fn main() -> Result<()> {
     let signer = SecretKey::random();
 
     println!("Starting SAFE client...");
-    let client = Client::new(signer, None, None).await?;
+    let client = Client::new(signer, None, ClientConfig::default()).await?;
     println!("SAFE client signer public key: {:?}", client.signer_pk());
 
     let root_dir = dirs_next::data_dir()
@@ -54,7 +54,7 @@ async fn main() -> Result<()> {
 
     // Loading a local wallet. It needs to have a non-zero balance for
     // this example to be able to pay for the Register's storage.
-    let wallet = LocalWallet::load_from(&root_dir)
+    let wallet = LocalWallet::load_from(&root_dir, None)
         .wrap_err("Unable to read wallet file in {root_dir:?}")
         .suggestion(
             "If you have an old wallet file, it may no longer be compatible. Try removing it",
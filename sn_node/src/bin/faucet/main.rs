@@ -11,7 +11,9 @@ mod faucet_server;
 use clap::{Parser, Subcommand};
 use eyre::{bail, eyre, Result};
 use faucet_server::run_faucet_server;
-use sn_client::{get_tokens_from_faucet, load_faucet_wallet_from_genesis_wallet, Client};
+use sn_client::{
+    get_tokens_from_faucet, load_faucet_wallet_from_genesis_wallet, Client, ClientConfig,
+};
 use sn_logging::{LogBuilder, LogOutputDest};
 use sn_peers_acquisition::{parse_peers_args, PeersArgs};
 use sn_transfers::{parse_main_pubkey, NanoTokens, Transfer};
@@ -55,7 +57,7 @@ async fn main() -> Result<()> {
     info!("Instantiating a SAFE Test Faucet...");
 
     let secret_key = bls::SecretKey::random();
-    let client = Client::new(secret_key, bootstrap_peers, None).await?;
+    let client = Client::new(secret_key, bootstrap_peers, ClientConfig::default()).await?;
 
     faucet_cmds(opt.cmd, &client).await?;
 
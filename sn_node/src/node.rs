@@ -97,7 +97,7 @@ impl NodeBuilder {
         let reward_key = MainSecretKey::random();
         let reward_address = reward_key.main_pubkey();
 
-        let mut wallet = LocalWallet::load_from_main_key(&self.root_dir, reward_key)?;
+        let mut wallet = LocalWallet::load_from_main_key(&self.root_dir, reward_key, None)?;
         // store in case it's a fresh wallet created if none was found
         wallet.deposit_and_store_to_disk(&vec![])?;
 
@@ -468,6 +468,19 @@ impl Node {
 
                 QueryResponse::GetReplicatedRecord(result)
             }
+            Query::GetRecordInfo(address) => {
+                trace!("Got GetRecordInfo request for {address:?}");
+                let result = self
+                    .network
+                    .get_local_record_info(&address)
+                    .await
+                    .map_err(|err| {
+                        error!("Problem getting record info for {address:?}: {err:?}");
+                        ProtocolError::RecordInfoNotAvailable(Box::new(address))
+                    });
+
+                QueryResponse::GetRecordInfo(result)
+            }
         };
         Response::Query(resp)
     }
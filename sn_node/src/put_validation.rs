@@ -15,7 +15,7 @@ use crate::{
 use libp2p::kad::{Record, RecordKey};
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::CmdOk,
+    messages::{register_notif_topic, CmdOk, RegisterNotification},
     storage::{
         try_deserialize_record, try_serialize_record, Chunk, RecordHeader, RecordKind, SpendAddress,
     },
@@ -98,7 +98,7 @@ impl Node {
                         .await?;
                 }
 
-                self.validate_and_store_register(register).await
+                self.validate_and_store_register(register, true).await
             }
         }
     }
@@ -161,7 +161,7 @@ impl Node {
                     );
                     return Err(ProtocolError::RecordKeyMismatch);
                 }
-                self.validate_and_store_register(register).await
+                self.validate_and_store_register(register, false).await
             }
         }
     }
@@ -240,9 +240,14 @@ impl Node {
     }
 
     /// Validate and store a `Register` to the RecordStore
+    ///
+    /// The newly stored ops are published to the clients watching the Register when
+    /// `publish_notification` is set, i.e. for client PUTs only, so that replicating them
+    /// between nodes doesn't publish them again from every node of the close group.
     pub(crate) async fn validate_and_store_register(
         &self,
         register: SignedRegister,
+        publish_notification: bool,
     ) -> Result<CmdOk, ProtocolError> {
         let reg_addr = register.address();
         debug!("Validating and storing register {reg_addr:?}");
@@ -261,12 +266,13 @@ impl Node {
         let pretty_key = PrettyPrintRecordKey::from(&key);
 
         // check register and merge if needed
-        let updated_register = match self.register_validation(&register, present_locally).await? {
-            Some(reg) => reg,
-            None => {
-                return Ok(CmdOk::DataAlreadyPresent);
-            }
-        };
+        let (updated_register, notification) =
+            match self.register_validation(&register, present_locally).await? {
+                Some(updated) => updated,
+                None => {
+                    return Ok(CmdOk::DataAlreadyPresent);
+                }
+            };
 
         // store in kad
         let record = Record {
@@ -283,6 +289,20 @@ impl Node {
 
         self.record_metrics(Marker::ValidRegisterRecordPutFromNetwork(&pretty_key));
 
+        // publish the newly stored ops over the register's gossipsub topic, for clients watching it.
+        if publish_notification && !notification.is_empty() {
+            match bincode::serialize(&notification) {
+                Ok(msg) => {
+                    trace!("Publishing a notification over gossipsub for register {reg_addr:?}");
+                    let topic = register_notif_topic(reg_addr);
+                    if let Err(err) = self.network.publish_on_topic(topic, msg) {
+                        debug!("Failed to publish a notification over gossipsub for register {reg_addr:?}: {err:?}");
+                    }
+                }
+                Err(err) => debug!("Failed to serialise the notification to publish over gossipsub for register {reg_addr:?}: {err:?}"),
+            }
+        }
+
         Ok(CmdOk::StoredSuccessfully)
     }
 
@@ -463,7 +483,7 @@ impl Node {
         trace!("Validating record payment for {pretty_key}");
 
         // load wallet
-        let mut wallet = LocalWallet::load_from(&self.network.root_dir_path)
+        let mut wallet = LocalWallet::load_from(&self.network.root_dir_path, None)
             .map_err(|err| ProtocolError::FailedToStorePaymentIntoNodeWallet(err.to_string()))?;
 
         // unpack transfer
@@ -532,7 +552,7 @@ impl Node {
         &self,
         register: &SignedRegister,
         present_locally: bool,
-    ) -> Result<Option<SignedRegister>, ProtocolError> {
+    ) -> Result<Option<(SignedRegister, RegisterNotification)>, ProtocolError> {
         // check if register is valid
         let reg_addr = register.address();
         if let Err(e) = register.verify() {
@@ -543,7 +563,8 @@ impl Node {
         // if we don't have it locally return it
         if !present_locally {
             debug!("Register with addr {reg_addr:?} is valid and doesn't exist locally");
-            return Ok(Some(register.to_owned()));
+            let notification = RegisterNotification::new_ops(None, register);
+            return Ok(Some((register.to_owned(), notification)));
         }
         trace!("Register with addr {reg_addr:?} exists locally, comparing with local version");
 
@@ -571,7 +592,9 @@ impl Node {
             Ok(None)
         } else {
             trace!("Register with addr {reg_addr:?} is different from the local version");
-            Ok(Some(merged_register))
+            let notification =
+                RegisterNotification::new_ops(Some(&local_register), &merged_register);
+            Ok(Some((merged_register, notification)))
         }
     }
 
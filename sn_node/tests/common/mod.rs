@@ -15,7 +15,7 @@ pub mod safenode_proto {
 
 use safenode_proto::{safe_node_client::SafeNodeClient, NodeInfoRequest, RestartRequest};
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
-use sn_client::{load_faucet_wallet_from_genesis_wallet, send, Client, Files};
+use sn_client::{load_faucet_wallet_from_genesis_wallet, send, Client, ClientConfig, Files};
 use sn_peers_acquisition::parse_peer_addr;
 use sn_protocol::storage::ChunkAddress;
 use sn_transfers::LocalWallet;
@@ -64,13 +64,13 @@ pub async fn get_client() -> Client {
     };
 
     println!("Client bootstrap with peer {bootstrap_peers:?}");
-    Client::new(secret_key, bootstrap_peers, None)
+    Client::new(secret_key, bootstrap_peers, ClientConfig::default())
         .await
         .expect("Client shall be successfully created.")
 }
 
 pub async fn get_wallet(root_dir: &Path) -> LocalWallet {
-    LocalWallet::load_from(root_dir).expect("Wallet shall be successfully created.")
+    LocalWallet::load_from(root_dir, None).expect("Wallet shall be successfully created.")
 }
 
 pub async fn get_funded_wallet(
@@ -191,7 +191,7 @@ fn current_rewards_balance() -> Result<NanoTokens> {
 
     for entry in std::fs::read_dir(node_dir_path)? {
         let path = entry?.path();
-        let wallet = LocalWallet::try_load_from(&path)?;
+        let wallet = LocalWallet::try_load_from(&path, None)?;
         let balance = wallet.balance();
         total_rewards = total_rewards
             .checked_add(balance)
@@ -106,6 +106,12 @@ pub enum Error {
     // Could not Serialize/Deserialize Record
     #[error("Could not Serialize/Deserialize Record")]
     RecordParsingFailed,
+    // The payload checksum in the RecordHeader did not match the payload
+    #[error("The Record payload does not match the checksum in its header: {0:?}")]
+    RecordChecksumMismatch(PrettyPrintRecordKey<'static>),
+    // The RecordHeader was written with a schema version we do not understand
+    #[error("Unsupported Record schema version: {0}")]
+    UnsupportedRecordVersion(u8),
     // The Record::key must match with the one that is derived from the Record::value
     #[error("The Record::key does not match with the key derived from Record::value")]
     RecordKeyMismatch,
@@ -115,4 +121,11 @@ pub enum Error {
     // The record already exists at this node
     #[error("The record already exists, so do not charge for it: {0:?}")]
     RecordExists(PrettyPrintRecordKey<'static>),
+    // The holder could not look up the record info
+    #[error("Record info could not be retrieved for {0:?}")]
+    RecordInfoNotAvailable(Box<NetworkAddress>),
+
+    // ---------- address errors
+    #[error("Could not parse address {0}")]
+    AddressParsingFailed(String),
 }
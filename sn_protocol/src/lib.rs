@@ -13,6 +13,8 @@ extern crate tracing;
 pub mod error;
 /// Messages types
 pub mod messages;
+/// Canonical `safe://` text form of addresses.
+pub mod safe_url;
 /// Storage types for spends, chunks and registers.
 pub mod storage;
 
@@ -202,23 +204,7 @@ impl Debug for NetworkAddress {
 
 impl Display for NetworkAddress {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            NetworkAddress::PeerId(id) => {
-                write!(f, "NetworkAddress::PeerId({})", hex::encode(id))
-            }
-            NetworkAddress::ChunkAddress(addr) => {
-                write!(f, "NetworkAddress::ChunkAddress({addr:?})")
-            }
-            NetworkAddress::SpendAddress(addr) => {
-                write!(f, "NetworkAddress::SpendAddress({addr:?})")
-            }
-            NetworkAddress::RegisterAddress(addr) => {
-                write!(f, "NetworkAddress::RegisterAddress({addr:?})")
-            }
-            NetworkAddress::RecordKey(key) => {
-                write!(f, "NetworkAddress::RecordKey({})", hex::encode(key))
-            }
-        }
+        write!(f, "{}", self.to_url())
     }
 }
 
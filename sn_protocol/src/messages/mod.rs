@@ -17,8 +17,8 @@ pub use self::{
     cmd::{Cmd, Hash},
     node_id::NodeId,
     query::Query,
-    register::RegisterCmd,
-    response::{CmdOk, CmdResponse, QueryResponse},
+    register::{register_notif_topic, RegisterCmd, RegisterNotification},
+    response::{CmdOk, CmdResponse, QueryResponse, RecordInfo},
 };
 
 use super::NetworkAddress;
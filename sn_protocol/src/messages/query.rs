@@ -32,13 +32,19 @@ pub enum Query {
         /// Key of the record to be fetched
         key: NetworkAddress,
     },
+    /// Retrieve what a peer knows about the record at the given address, without the record itself.
+    ///
+    /// This should eventually lead to a [`GetRecordInfo`] response.
+    ///
+    /// [`GetRecordInfo`]: super::QueryResponse::GetRecordInfo
+    GetRecordInfo(NetworkAddress),
 }
 
 impl Query {
     /// Used to send a query to the close group of the address.
     pub fn dst(&self) -> NetworkAddress {
         match self {
-            Query::GetStoreCost(address) => address.clone(),
+            Query::GetStoreCost(address) | Query::GetRecordInfo(address) => address.clone(),
             // Shall not be called for this, as this is a `one-to-one` message,
             // and the destionation shall be decided by the requester already.
             Query::GetReplicatedRecord { key, .. } => key.clone(),
@@ -55,6 +61,9 @@ impl std::fmt::Display for Query {
             Query::GetReplicatedRecord { key, requester } => {
                 write!(f, "Query::GetStoreCost({requester:?} {key:?})")
             }
+            Query::GetRecordInfo(address) => {
+                write!(f, "Query::GetRecordInfo({address:?})")
+            }
         }
     }
 }
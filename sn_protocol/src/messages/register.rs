@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_registers::{Register, RegisterAddress, RegisterOp};
+use sn_registers::{PermissionOp, Register, RegisterAddress, RegisterOp, SignedRegister};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +23,8 @@ pub enum RegisterCmd {
     },
     /// Edit the register
     Edit(RegisterOp),
+    /// Change the permissions of the register
+    EditPermissions(PermissionOp),
 }
 
 /// Custom debug implementation to avoid printing the whole register
@@ -34,6 +36,9 @@ impl std::fmt::Debug for RegisterCmd {
                 write!(f, "RegisterCmd::Create({:?})", register.address())
             }
             RegisterCmd::Edit(op) => write!(f, "RegisterCmd::Edit({:?})", op.address()),
+            RegisterCmd::EditPermissions(op) => {
+                write!(f, "RegisterCmd::EditPermissions({:?})", op.address())
+            }
         }
     }
 }
@@ -44,6 +49,98 @@ impl RegisterCmd {
         match self {
             Self::Create { register, .. } => *register.address(),
             Self::Edit(op) => op.address(),
+            Self::EditPermissions(op) => op.address(),
         }
     }
 }
+
+/// Prefix of the gossipsub topics nodes publish `RegisterNotification`s on.
+const REGISTER_NOTIF_TOPIC_PREFIX: &str = "REGISTER_NOTIFICATION_";
+
+/// Name of the gossipsub topic notifications for the register at `address` are published on.
+pub fn register_notif_topic(address: &RegisterAddress) -> String {
+    format!("{REGISTER_NOTIF_TOPIC_PREFIX}{}", address.to_hex())
+}
+
+/// Operations newly stored by a node for a register, published over the register's
+/// gossipsub topic so that clients can follow its edits without polling.
+///
+/// The operations carry the signatures of their writers, and are meant to be
+/// checked against the register before being applied.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterNotification {
+    /// Operations written by the owner or the writers of the register.
+    pub ops: Vec<RegisterOp>,
+    /// Permission changes made by the owner of the register.
+    pub permission_ops: Vec<PermissionOp>,
+}
+
+impl RegisterNotification {
+    /// The operations of `updated` which were not in `stored`, if there was a stored register.
+    pub fn new_ops(stored: Option<&SignedRegister>, updated: &SignedRegister) -> Self {
+        match stored {
+            Some(stored) => Self {
+                ops: updated.ops().difference(stored.ops()).cloned().collect(),
+                permission_ops: updated
+                    .permission_ops()
+                    .difference(stored.permission_ops())
+                    .cloned()
+                    .collect(),
+            },
+            None => Self {
+                ops: updated.ops().iter().cloned().collect(),
+                permission_ops: updated.permission_ops().iter().cloned().collect(),
+            },
+        }
+    }
+
+    /// Return true if there is no operation to notify.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.permission_ops.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::{rand::thread_rng, SecretKey};
+    use sn_registers::{PermissionChange, Permissions};
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    #[test]
+    fn notifications_only_hold_the_ops_not_stored_yet() -> Result<(), sn_registers::Error> {
+        let owner = SecretKey::random();
+        let mut replica = Register::new(
+            owner.public_key(),
+            XorName::random(&mut thread_rng()),
+            Permissions::new_owner_only(),
+        );
+        let mut stored = replica.clone().into_signed(&owner)?;
+
+        let (first, first_op) = replica.write(b"first".to_vec(), BTreeSet::new(), &owner)?;
+        stored.add_op(first_op.clone())?;
+
+        let mut updated = stored.clone();
+        let (_, second_op) = replica.write(b"second".to_vec(), BTreeSet::from([first]), &owner)?;
+        updated.add_op(second_op.clone())?;
+        let grant = replica.update_permissions(
+            PermissionChange::GrantWrite(SecretKey::random().public_key()),
+            &owner,
+        )?;
+        updated.add_permission_op(grant.clone())?;
+
+        let notification = RegisterNotification::new_ops(Some(&stored), &updated);
+        assert_eq!(notification.ops, vec![second_op.clone()]);
+        assert_eq!(notification.permission_ops, vec![grant.clone()]);
+
+        // everything is new when nothing was stored
+        let notification = RegisterNotification::new_ops(None, &updated);
+        assert_eq!(notification.ops.len(), 2);
+        assert!(notification.ops.contains(&first_op) && notification.ops.contains(&second_op));
+        assert_eq!(notification.permission_ops, vec![grant]);
+
+        assert!(RegisterNotification::new_ops(Some(&updated), &updated).is_empty());
+        Ok(())
+    }
+}
@@ -6,12 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, NetworkAddress};
+use crate::{error::Result, storage::RecordKind, NetworkAddress};
 
 use core::fmt;
 use serde::{Deserialize, Serialize};
 use sn_transfers::{MainPubkey, NanoTokens};
-use std::fmt::Debug;
+use std::{fmt::Debug, time::SystemTime};
 
 /// The response to a query, containing the query result.
 #[allow(clippy::large_enum_variant)]
@@ -29,6 +29,33 @@ pub enum QueryResponse {
     ///
     /// [`GetReplicatedRecord`]: crate::messages::Query::GetReplicatedRecord
     GetReplicatedRecord(Result<(NetworkAddress, Vec<u8>)>),
+    // ===== RecordInfo =====
+    //
+    /// Response to [`GetRecordInfo`]
+    ///
+    /// [`GetRecordInfo`]: crate::messages::Query::GetRecordInfo
+    GetRecordInfo(Result<RecordInfo>),
+}
+
+/// Holder-side metadata about a record, used to debug where data lives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordInfo {
+    /// The peer that produced this info.
+    pub holder: NetworkAddress,
+    /// The address that was asked about.
+    pub key: NetworkAddress,
+    /// Whether the holder has the record in its local store.
+    pub is_held: bool,
+    /// The kind of the record, if held.
+    pub kind: Option<RecordKind>,
+    /// The size in bytes of the stored record value, zero if not held.
+    pub size: usize,
+    /// When the holder stored the record, if held and known.
+    pub stored_at: Option<SystemTime>,
+    /// Whether the key falls within the distance range the holder is responsible for.
+    pub within_distance_range: bool,
+    /// The store cost the holder currently quotes for a record, whether or not it holds this one.
+    pub store_cost: NanoTokens,
 }
 
 // Debug implementation for QueryResponse, to avoid printing Vec<u8>
@@ -58,6 +85,9 @@ impl Debug for QueryResponse {
                     write!(f, "GetReplicatedRecord(Err({:?}))", err)
                 }
             },
+            QueryResponse::GetRecordInfo(result) => {
+                write!(f, "GetRecordInfo({:?})", result)
+            }
         }
     }
 }
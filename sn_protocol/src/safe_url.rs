@@ -0,0 +1,176 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Canonical `safe://` text form of network addresses.
+//!
+//! The encoding itself lives in the `sn_url` crate, so `RegisterAddress` and `SpendAddress`
+//! can implement `Display` and `FromStr` in their own crates. This adds the same for
+//! `ChunkAddress` and `NetworkAddress`.
+
+use crate::{
+    error::{Error, Result},
+    storage::{ChunkAddress, RegisterAddress, SpendAddress},
+    NetworkAddress,
+};
+use sn_registers::REGISTER_URL_TYPE;
+use sn_transfers::SPEND_URL_TYPE;
+pub use sn_url::SAFE_URL_SCHEME;
+use sn_url::{decode_url, decode_url_of_kind, encode_url};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+const CHUNK_TYPE: &str = "chunk";
+const PEER_TYPE: &str = "peer";
+const RECORD_KEY_TYPE: &str = "key";
+
+impl NetworkAddress {
+    /// Render this address in its canonical `safe://` text form.
+    pub fn to_url(&self) -> String {
+        match self {
+            NetworkAddress::ChunkAddress(addr) => addr.to_string(),
+            NetworkAddress::RegisterAddress(addr) => addr.to_string(),
+            NetworkAddress::SpendAddress(addr) => addr.to_string(),
+            NetworkAddress::PeerId(bytes) => encode_url(PEER_TYPE, bytes),
+            NetworkAddress::RecordKey(bytes) => encode_url(RECORD_KEY_TYPE, bytes),
+        }
+    }
+
+    /// Parse an address from its canonical `safe://` text form.
+    pub fn from_url(url: &str) -> Result<Self> {
+        let (kind, bytes) = decode_url(url).map_err(url_err)?;
+        match kind {
+            CHUNK_TYPE => Ok(NetworkAddress::ChunkAddress(parse_chunk_address(url)?)),
+            SPEND_URL_TYPE => Ok(NetworkAddress::SpendAddress(parse_spend_address(url)?)),
+            REGISTER_URL_TYPE => Ok(NetworkAddress::RegisterAddress(parse_register_address(
+                url,
+            )?)),
+            PEER_TYPE => Ok(NetworkAddress::PeerId(bytes)),
+            RECORD_KEY_TYPE => Ok(NetworkAddress::RecordKey(bytes)),
+            _ => Err(parsing_err(url, "unknown address type")),
+        }
+    }
+}
+
+impl FromStr for NetworkAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_url(s)
+    }
+}
+
+impl Display for ChunkAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", encode_url(CHUNK_TYPE, &self.xorname().0))
+    }
+}
+
+impl FromStr for ChunkAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_chunk_address(s)
+    }
+}
+
+/// Parse a `ChunkAddress` from either its `safe://chunk/` form or a legacy 64 character hex xorname.
+pub fn parse_chunk_address(s: &str) -> Result<ChunkAddress> {
+    let s = s.trim();
+    let bytes = if s.starts_with(SAFE_URL_SCHEME) {
+        decode_url_of_kind(s, CHUNK_TYPE).map_err(url_err)?
+    } else {
+        hex::decode(s).map_err(|_| parsing_err(s, "invalid hex"))?
+    };
+    let bytes: [u8; XOR_NAME_LEN] = bytes
+        .try_into()
+        .map_err(|_| parsing_err(s, "invalid length"))?;
+    Ok(ChunkAddress::new(XorName(bytes)))
+}
+
+/// Parse a `RegisterAddress` from either its `safe://register/` form or the legacy hex encoding
+/// produced by `RegisterAddress::to_hex`.
+pub fn parse_register_address(s: &str) -> Result<RegisterAddress> {
+    s.parse().map_err(url_err)
+}
+
+/// Parse a `SpendAddress` from either its `safe://spend/` form or a legacy 64 character hex xorname.
+pub fn parse_spend_address(s: &str) -> Result<SpendAddress> {
+    s.parse()
+        .map_err(|err: sn_transfers::Error| Error::AddressParsingFailed(err.to_string()))
+}
+
+fn url_err(err: impl Display) -> Error {
+    Error::AddressParsingFailed(err.to_string())
+}
+
+fn parsing_err(input: &str, reason: &str) -> Error {
+    Error::AddressParsingFailed(format!("{input:?}: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::{rand::thread_rng, SecretKey};
+    use libp2p::PeerId;
+
+    #[test]
+    fn network_address_url_round_trip() -> Result<()> {
+        let mut rng = thread_rng();
+        let addresses = vec![
+            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng))),
+            NetworkAddress::from_cash_note_address(SpendAddress::new(XorName::random(&mut rng))),
+            NetworkAddress::from_register_address(RegisterAddress::new(
+                XorName::random(&mut rng),
+                SecretKey::random().public_key(),
+            )),
+            NetworkAddress::from_peer(PeerId::random()),
+            NetworkAddress::RecordKey(XorName::random(&mut rng).0.to_vec()),
+        ];
+
+        for addr in addresses {
+            let url = addr.to_string();
+            assert!(url.starts_with(SAFE_URL_SCHEME));
+            assert_eq!(url.parse::<NetworkAddress>()?, addr);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn url_with_bad_checksum_or_type_is_rejected() {
+        let addr = ChunkAddress::new(XorName::random(&mut thread_rng()));
+        let url = addr.to_string();
+
+        let mut corrupted = url.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'a' { b'b' } else { b'a' };
+        let corrupted = String::from_utf8(corrupted).expect("still utf8");
+        assert!(NetworkAddress::from_url(&corrupted).is_err());
+
+        let retyped = url.replacen(CHUNK_TYPE, SPEND_URL_TYPE, 1);
+        assert!(NetworkAddress::from_url(&retyped).is_err());
+        assert!(parse_spend_address(&url).is_err());
+    }
+
+    #[test]
+    fn legacy_hex_is_still_accepted() -> Result<()> {
+        let mut rng = thread_rng();
+        let xorname = XorName::random(&mut rng);
+        let chunk_addr = parse_chunk_address(&hex::encode(xorname))?;
+        assert_eq!(chunk_addr, ChunkAddress::new(xorname));
+        assert_eq!(parse_chunk_address(&chunk_addr.to_string())?, chunk_addr);
+
+        let reg_addr = RegisterAddress::new(xorname, SecretKey::random().public_key());
+        assert_eq!(parse_register_address(&reg_addr.to_hex())?, reg_addr);
+
+        Ok(())
+    }
+}
@@ -10,11 +10,38 @@ use crate::error::Error;
 use crate::PrettyPrintRecordKey;
 use libp2p::kad::Record;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt::Display;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The schema version of versioned headers.
+///
+/// Version `0` denotes the legacy header, which carried only the `RecordKind`
+/// and no checksum. Bump this whenever the payload format of any `RecordKind` changes.
+pub const RECORD_SCHEMA_VERSION: u8 = 1;
+
+/// Whether `try_serialize_record` writes versioned headers rather than legacy ones.
+///
+/// Nodes released before versioned headers slice a fixed 2 byte header off every record, so
+/// they fail to read records carrying a versioned one. The rollout is therefore done in two
+/// releases:
+/// 1. this one, which reads both headers but keeps writing the legacy one;
+/// 2. once all nodes of the network run it, a release flipping this to `true`.
+pub const WRITE_VERSIONED_RECORD_HEADERS: bool = false;
+
+/// First byte of a versioned header. `0xc1` is never used by MessagePack,
+/// so it can not be confused with the leading byte of a legacy header.
+const VERSIONED_HEADER_MARKER: u8 = 0xc1;
+
+/// Length of the payload checksum carried in a versioned header.
+const CHECKSUM_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RecordHeader {
     pub kind: RecordKind,
+    /// Schema version of the payload following the header.
+    pub version: u8,
+    /// Truncated SHA256 of the payload. `None` for legacy headers.
+    pub checksum: Option<[u8; CHECKSUM_LEN]>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -26,18 +53,35 @@ pub enum RecordKind {
     RegisterWithPayment,
 }
 
+impl RecordKind {
+    fn to_u32(&self) -> u32 {
+        match *self {
+            Self::ChunkWithPayment => 0,
+            Self::Chunk => 1,
+            Self::Spend => 2,
+            Self::Register => 3,
+            Self::RegisterWithPayment => 4,
+        }
+    }
+
+    fn from_u32(num: u32) -> Option<Self> {
+        match num {
+            0 => Some(Self::ChunkWithPayment),
+            1 => Some(Self::Chunk),
+            2 => Some(Self::Spend),
+            3 => Some(Self::Register),
+            4 => Some(Self::RegisterWithPayment),
+            _ => None,
+        }
+    }
+}
+
 impl Serialize for RecordKind {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        match *self {
-            Self::ChunkWithPayment => serializer.serialize_u32(0),
-            Self::Chunk => serializer.serialize_u32(1),
-            Self::Spend => serializer.serialize_u32(2),
-            Self::Register => serializer.serialize_u32(3),
-            Self::RegisterWithPayment => serializer.serialize_u32(4),
-        }
+        serializer.serialize_u32(self.to_u32())
     }
 }
 
@@ -47,16 +91,8 @@ impl<'de> Deserialize<'de> for RecordKind {
         D: serde::Deserializer<'de>,
     {
         let num = u32::deserialize(deserializer)?;
-        match num {
-            0 => Ok(Self::ChunkWithPayment),
-            1 => Ok(Self::Chunk),
-            2 => Ok(Self::Spend),
-            3 => Ok(Self::Register),
-            4 => Ok(Self::RegisterWithPayment),
-            _ => Err(serde::de::Error::custom(
-                "Unexpected integer for RecordKind variant",
-            )),
-        }
+        Self::from_u32(num)
+            .ok_or_else(|| serde::de::Error::custom("Unexpected integer for RecordKind variant"))
     }
 }
 impl Display for RecordKind {
@@ -65,28 +101,117 @@ impl Display for RecordKind {
     }
 }
 
+/// The header written by older nodes and clients: a MessagePack encoded `{ kind }`.
+#[derive(Debug, Serialize, Deserialize)]
+struct LegacyRecordHeader {
+    kind: RecordKind,
+}
+
 impl RecordHeader {
-    pub const SIZE: usize = 2;
+    /// Encoded size of a versioned header: marker, kind, version and checksum.
+    pub const SIZE: usize = 3 + CHECKSUM_LEN;
+    /// Encoded size of a legacy header.
+    pub const LEGACY_SIZE: usize = 2;
+
+    /// Create a header of the current schema version for the given payload.
+    pub fn new(kind: RecordKind, payload: &[u8]) -> Self {
+        Self {
+            kind,
+            version: RECORD_SCHEMA_VERSION,
+            checksum: Some(payload_checksum(payload)),
+        }
+    }
+
+    /// Create a legacy header, readable by nodes which predate versioned headers.
+    pub fn legacy(kind: RecordKind) -> Self {
+        Self {
+            kind,
+            version: 0,
+            checksum: None,
+        }
+    }
+
+    /// The number of bytes this header occupies at the start of `Record::value`.
+    pub fn encoded_len(&self) -> usize {
+        if self.checksum.is_some() {
+            Self::SIZE
+        } else {
+            Self::LEGACY_SIZE
+        }
+    }
 
     pub fn try_serialize(self) -> Result<Vec<u8>, Error> {
-        rmp_serde::to_vec(&self).map_err(|err| {
-            error!("Failed to serialized RecordHeader {self:?} with error: {err:?}");
-            Error::RecordHeaderParsingFailed
-        })
+        let Some(checksum) = self.checksum else {
+            return rmp_serde::to_vec(&LegacyRecordHeader { kind: self.kind }).map_err(|err| {
+                error!("Failed to serialized legacy RecordHeader with error: {err:?}");
+                Error::RecordHeaderParsingFailed
+            });
+        };
+
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.push(VERSIONED_HEADER_MARKER);
+        bytes.push(self.kind.to_u32() as u8);
+        bytes.push(self.version);
+        bytes.extend_from_slice(&checksum);
+        Ok(bytes)
     }
 
     pub fn try_deserialize(bytes: &[u8]) -> Result<Self, Error> {
-        rmp_serde::from_slice(bytes).map_err(|err| {
-            error!("Failed to deserialized RecordHeader with error: {err:?}");
-            Error::RecordHeaderParsingFailed
+        if bytes.first() != Some(&VERSIONED_HEADER_MARKER) {
+            let legacy: LegacyRecordHeader = rmp_serde::from_slice(bytes).map_err(|err| {
+                error!("Failed to deserialized RecordHeader with error: {err:?}");
+                Error::RecordHeaderParsingFailed
+            })?;
+            return Ok(Self {
+                kind: legacy.kind,
+                version: 0,
+                checksum: None,
+            });
+        }
+
+        if bytes.len() < Self::SIZE {
+            return Err(Error::RecordHeaderParsingFailed);
+        }
+        let kind = RecordKind::from_u32(bytes[1] as u32).ok_or(Error::RecordHeaderParsingFailed)?;
+        let version = bytes[2];
+        if version > RECORD_SCHEMA_VERSION {
+            error!("Record schema version {version} is newer than the supported {RECORD_SCHEMA_VERSION}");
+            return Err(Error::UnsupportedRecordVersion(version));
+        }
+        let mut checksum = [0u8; CHECKSUM_LEN];
+        checksum.copy_from_slice(&bytes[3..Self::SIZE]);
+
+        Ok(Self {
+            kind,
+            version,
+            checksum: Some(checksum),
         })
     }
 
     pub fn from_record(record: &Record) -> Result<Self, Error> {
-        if record.value.len() < RecordHeader::SIZE + 1 {
+        let header = Self::from_prefix(&record.value)?;
+        if record.value.len() < header.encoded_len() + 1 {
+            return Err(Error::RecordHeaderParsingFailed);
+        }
+        Ok(header)
+    }
+
+    /// Parse the header from the leading bytes of a record value.
+    ///
+    /// Reading the first `RecordHeader::SIZE` bytes of a value is always enough, so a stored
+    /// record's header can be read without loading the whole value.
+    pub fn from_prefix(value: &[u8]) -> Result<Self, Error> {
+        if value.first() == Some(&VERSIONED_HEADER_MARKER) {
+            if value.len() < Self::SIZE {
+                return Err(Error::RecordHeaderParsingFailed);
+            }
+            return Self::try_deserialize(&value[..Self::SIZE]);
+        }
+
+        if value.len() < RecordHeader::LEGACY_SIZE {
             return Err(Error::RecordHeaderParsingFailed);
         }
-        Self::try_deserialize(&record.value[..RecordHeader::SIZE + 1])
+        Self::try_deserialize(&value[..value.len().min(RecordHeader::LEGACY_SIZE + 1)])
     }
 
     pub fn is_record_of_type_chunk(record: &Record) -> Result<bool, Error> {
@@ -95,14 +220,33 @@ impl RecordHeader {
     }
 }
 
+fn payload_checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(payload);
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    checksum
+}
+
 /// Utility to deserialize a `KAD::Record` into any type.
 /// Use `RecordHeader::from_record` if you want the `RecordHeader` instead.
+///
+/// The payload checksum is verified for versioned records, legacy records are read as is.
 pub fn try_deserialize_record<T: serde::de::DeserializeOwned>(record: &Record) -> Result<T, Error> {
-    let bytes = if record.value.len() > RecordHeader::SIZE {
-        &record.value[RecordHeader::SIZE..]
-    } else {
-        return Err(Error::RecordParsingFailed);
-    };
+    let header = RecordHeader::from_record(record).map_err(|_| Error::RecordParsingFailed)?;
+    let bytes = &record.value[header.encoded_len()..];
+
+    if let Some(checksum) = header.checksum {
+        if payload_checksum(bytes) != checksum {
+            error!(
+                "Checksum mismatch for record {}",
+                PrettyPrintRecordKey::from(&record.key)
+            );
+            return Err(Error::RecordChecksumMismatch(
+                PrettyPrintRecordKey::from(&record.key).into_owned(),
+            ));
+        }
+    }
+
     rmp_serde::from_slice(bytes).map_err(|err| {
         error!(
             "Failed to deserialized record {} with error: {err:?}",
@@ -113,16 +257,31 @@ pub fn try_deserialize_record<T: serde::de::DeserializeOwned>(record: &Record) -
 }
 
 /// Utility to serialize the provided data along with the RecordKind to be stored as Record::value
+///
+/// The header is a legacy one unless `WRITE_VERSIONED_RECORD_HEADERS` is set.
 pub fn try_serialize_record<T: serde::Serialize>(
     data: &T,
     record_kind: RecordKind,
+) -> Result<Vec<u8>, Error> {
+    serialize_record(data, record_kind, WRITE_VERSIONED_RECORD_HEADERS)
+}
+
+fn serialize_record<T: serde::Serialize>(
+    data: &T,
+    record_kind: RecordKind,
+    versioned: bool,
 ) -> Result<Vec<u8>, Error> {
     let payload = rmp_serde::to_vec(data).map_err(|err| {
         error!("Failed to serialized Records with error: {err:?}");
         Error::RecordParsingFailed
     })?;
 
-    let mut record_value = RecordHeader { kind: record_kind }.try_serialize()?;
+    let header = if versioned {
+        RecordHeader::new(record_kind, &payload)
+    } else {
+        RecordHeader::legacy(record_kind)
+    };
+    let mut record_value = header.try_serialize()?;
     record_value.extend(payload);
 
     Ok(record_value)
@@ -130,41 +289,73 @@ pub fn try_serialize_record<T: serde::Serialize>(
 
 #[cfg(test)]
 mod tests {
-    use super::{RecordHeader, RecordKind};
-    use crate::error::Result;
+    use super::{
+        serialize_record, try_deserialize_record, try_serialize_record, RecordHeader, RecordKind,
+        WRITE_VERSIONED_RECORD_HEADERS,
+    };
+    use crate::error::{Error, Result};
+    use libp2p::kad::{Record, RecordKey};
 
     #[test]
     fn verify_record_header_encoded_size() -> Result<()> {
-        let chunk_with_payment = RecordHeader {
-            kind: RecordKind::ChunkWithPayment,
-        }
-        .try_serialize()?;
-        assert_eq!(chunk_with_payment.len(), RecordHeader::SIZE);
+        for kind in [
+            RecordKind::ChunkWithPayment,
+            RecordKind::RegisterWithPayment,
+            RecordKind::Chunk,
+            RecordKind::Spend,
+            RecordKind::Register,
+        ] {
+            let header = RecordHeader::new(kind.clone(), b"payload").try_serialize()?;
+            assert_eq!(header.len(), RecordHeader::SIZE);
 
-        let reg_with_payment = RecordHeader {
-            kind: RecordKind::RegisterWithPayment,
+            let legacy = RecordHeader::legacy(kind).try_serialize()?;
+            assert_eq!(legacy.len(), RecordHeader::LEGACY_SIZE);
         }
-        .try_serialize()?;
-        assert_eq!(reg_with_payment.len(), RecordHeader::SIZE);
 
-        let chunk = RecordHeader {
-            kind: RecordKind::Chunk,
-        }
-        .try_serialize()?;
-        assert_eq!(chunk.len(), RecordHeader::SIZE);
+        Ok(())
+    }
 
-        let spend = RecordHeader {
-            kind: RecordKind::Spend,
-        }
-        .try_serialize()?;
-        assert_eq!(spend.len(), RecordHeader::SIZE);
+    #[test]
+    fn versioned_record_round_trip_and_checksum() -> Result<()> {
+        let data = vec![1u8, 2, 3, 4];
+        let value = serialize_record(&data, RecordKind::Chunk, true)?;
+        let mut record = Record::new(RecordKey::new(b"key"), value);
 
-        let register = RecordHeader {
-            kind: RecordKind::Register,
-        }
-        .try_serialize()?;
-        assert_eq!(register.len(), RecordHeader::SIZE);
+        let header = RecordHeader::from_record(&record)?;
+        assert_eq!(header.kind, RecordKind::Chunk);
+        assert_eq!(header.version, super::RECORD_SCHEMA_VERSION);
+        assert_eq!(try_deserialize_record::<Vec<u8>>(&record)?, data);
 
+        // flip a payload byte, the checksum must catch it
+        let last = record.value.len() - 1;
+        record.value[last] ^= 0xff;
+        assert!(matches!(
+            try_deserialize_record::<Vec<u8>>(&record),
+            Err(Error::RecordChecksumMismatch(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_record_is_still_readable() -> Result<()> {
+        let data = vec![5u8, 6, 7];
+        let value = serialize_record(&data, RecordKind::Spend, false)?;
+        let record = Record::new(RecordKey::new(b"key"), value);
+
+        let header = RecordHeader::from_record(&record)?;
+        assert_eq!(header.kind, RecordKind::Spend);
+        assert_eq!(header.version, 0);
+        assert_eq!(try_deserialize_record::<Vec<u8>>(&record)?, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_header_is_written_until_the_network_upgraded() -> Result<()> {
+        let value = try_serialize_record(&vec![1u8], RecordKind::Chunk)?;
+        let header = RecordHeader::from_record(&Record::new(RecordKey::new(b"key"), value))?;
+        assert_eq!(header.checksum.is_some(), WRITE_VERSIONED_RECORD_HEADERS);
         Ok(())
     }
 }
@@ -10,12 +10,17 @@ use crate::error::{Error, Result};
 
 use bls::{PublicKey, PK_SIZE};
 use serde::{Deserialize, Serialize};
+use sn_url::{decode_url_of_kind, encode_url, SAFE_URL_SCHEME};
 use std::{
     fmt::{Debug, Display},
     hash::Hash,
+    str::FromStr,
 };
 use xor_name::{XorName, XOR_NAME_LEN};
 
+/// The `<type>` of a Register address in its `safe://` url form.
+pub const REGISTER_URL_TYPE: &str = "register";
+
 /// Address of a Register on the SAFE Network
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub struct RegisterAddress {
@@ -27,7 +32,22 @@ pub struct RegisterAddress {
 
 impl Display for RegisterAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}({:?})", self.to_hex(), self.xorname())
+        write!(f, "{}", self.to_url())
+    }
+}
+
+impl FromStr for RegisterAddress {
+    type Err = Error;
+
+    /// Parse either the `safe://register/` url form or the legacy hex encoding.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with(SAFE_URL_SCHEME) {
+            Self::from_bytes(&decode_url_of_kind(s, REGISTER_URL_TYPE)?)
+                .map_err(|_| Error::UrlParsingFailed(format!("{s:?}: invalid register address")))
+        } else {
+            Self::from_hex(s)
+        }
     }
 }
 
@@ -60,15 +80,31 @@ impl RegisterAddress {
 
     /// Serialize this `RegisterAddress` instance to a hex-encoded `String`.
     pub fn to_hex(&self) -> String {
-        let mut bytes = vec![];
-        bytes.extend_from_slice(&self.meta.0);
-        bytes.extend_from_slice(&self.owner.to_bytes());
-        hex::encode(bytes)
+        hex::encode(self.to_bytes())
     }
 
     /// Deserialize a hex-encoded representation of a `RegisterAddress` to a `RegisterAddress` instance.
     pub fn from_hex(hex: &str) -> Result<Self> {
         let bytes = hex::decode(hex).map_err(|_| Error::HexDeserializeFailed)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Render this `RegisterAddress` in its canonical `safe://register/` url form.
+    pub fn to_url(&self) -> String {
+        encode_url(REGISTER_URL_TYPE, &self.to_bytes())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.meta.0);
+        bytes.extend_from_slice(&self.owner.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != XOR_NAME_LEN + PK_SIZE {
+            return Err(Error::HexDeserializeFailed);
+        }
         let meta_bytes: [u8; XOR_NAME_LEN] = bytes[..XOR_NAME_LEN]
             .try_into()
             .map_err(|_| Error::HexDeserializeFailed)?;
@@ -112,4 +148,17 @@ mod tests {
         let err = RegisterAddress::from_hex(&bad_hex);
         assert_eq!(err, Err(Error::HexDeserializeFailed));
     }
+
+    #[test]
+    fn test_register_url_conversion() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let addr =
+            RegisterAddress::new(XorName::random(&mut rng), SecretKey::random().public_key());
+
+        let url = addr.to_string();
+        assert!(url.starts_with(SAFE_URL_SCHEME));
+        assert_eq!(url.parse::<RegisterAddress>()?, addr);
+        assert_eq!(addr.to_hex().parse::<RegisterAddress>()?, addr);
+        Ok(())
+    }
 }
@@ -66,6 +66,17 @@ pub enum Error {
     /// The provided String can't be deserialized as a RegisterAddress
     #[error("Failed to deserialize hex RegisterAddress")]
     HexDeserializeFailed,
+    /// The provided String is not a valid `safe://` url
+    #[error("Could not parse url {0}")]
+    UrlParsingFailed(String),
+}
+
+impl From<sn_url::Error> for Error {
+    fn from(err: sn_url::Error) -> Self {
+        match err {
+            sn_url::Error::UrlParsingFailed(reason) => Self::UrlParsingFailed(reason),
+        }
+    }
 }
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
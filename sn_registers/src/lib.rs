@@ -9,16 +9,18 @@
 mod address;
 pub(crate) mod error;
 mod metadata;
+mod permission_op;
 mod permissions;
 pub(crate) mod reg_crdt;
 pub(crate) mod register;
 mod register_op;
 
 pub use self::{
-    address::RegisterAddress,
+    address::{RegisterAddress, REGISTER_URL_TYPE},
     error::Error,
     metadata::{Entry, EntryHash},
+    permission_op::{PermissionChange, PermissionOp},
     permissions::Permissions,
-    register::{Register, SignedRegister},
+    register::{Register, SignedRegister, MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES},
     register_op::RegisterOp,
 };
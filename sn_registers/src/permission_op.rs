@@ -0,0 +1,100 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, EntryHash, Error, RegisterAddress};
+
+use bls::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A change to the permissions of a Register.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PermissionChange {
+    /// Allow the user to write to the Register.
+    GrantWrite(PublicKey),
+    /// Stop allowing the user to write to the Register.
+    RevokeWrite(PublicKey),
+    /// Allow, or stop allowing, anyone to write to the Register.
+    SetAnyoneCanWrite(bool),
+}
+
+/// Owner-signed operation changing the permissions of a Register.
+///
+/// Permission ops are applied in order of their sequence number, ties being broken by the
+/// rest of the op, so that all replicas end up with the same permissions whatever the order
+/// the ops were received in.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PermissionOp {
+    /// Position of the op among the permission ops of the Register.
+    pub(crate) seq: u64,
+    /// Address of a Register object on the network.
+    pub(crate) address: RegisterAddress,
+    /// The change to apply.
+    pub(crate) change: PermissionChange,
+    /// The latest entries of the Register when the owner made the change.
+    /// Writes by a user whose permission is revoked stay valid if they are among,
+    /// or were written before, these entries.
+    pub(crate) seen: BTreeSet<EntryHash>,
+    /// The signature of the owner on all the above.
+    pub(crate) signature: Signature,
+}
+
+impl PermissionOp {
+    /// Create a new PermissionOp, signed by the owner
+    pub(crate) fn new(
+        seq: u64,
+        address: RegisterAddress,
+        change: PermissionChange,
+        seen: BTreeSet<EntryHash>,
+        owner: &SecretKey,
+    ) -> Result<Self> {
+        let bytes = Self::bytes_for_signing(seq, &address, &change, &seen)?;
+        let signature = owner.sign(bytes);
+        Ok(Self {
+            seq,
+            address,
+            change,
+            seen,
+            signature,
+        })
+    }
+
+    /// address of the register this op is destined for
+    pub fn address(&self) -> RegisterAddress {
+        self.address
+    }
+
+    /// the change this op makes to the permissions
+    pub fn change(&self) -> &PermissionChange {
+        &self.change
+    }
+
+    /// the latest entries of the register when the change was made
+    pub fn seen(&self) -> &BTreeSet<EntryHash> {
+        &self.seen
+    }
+
+    /// Check the op was signed by the owner of the register it is destined for
+    pub fn verify_signature(&self) -> Result<()> {
+        let bytes = Self::bytes_for_signing(self.seq, &self.address, &self.change, &self.seen)?;
+        if !self.address.owner().verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Returns a bytes version of the PermissionOp used for signing
+    fn bytes_for_signing(
+        seq: u64,
+        address: &RegisterAddress,
+        change: &PermissionChange,
+        seen: &BTreeSet<EntryHash>,
+    ) -> Result<Vec<u8>> {
+        bincode::serialize(&(seq, address, change, seen)).map_err(|_| Error::SerialisationFailed)
+    }
+}
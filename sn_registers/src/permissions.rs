@@ -6,6 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::PermissionChange;
+
 use bls::PublicKey;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, hash::Hash};
@@ -54,4 +56,19 @@ impl Permissions {
     pub fn can_write(&self, user: &PublicKey) -> bool {
         self.anyone_can_write() || self.writers.contains(user)
     }
+
+    /// Apply a change made by the owner of the Register
+    pub(crate) fn apply_change(&mut self, change: &PermissionChange) {
+        match change {
+            PermissionChange::GrantWrite(user) => {
+                let _ = self.writers.insert(*user);
+            }
+            PermissionChange::RevokeWrite(user) => {
+                let _ = self.writers.remove(user);
+            }
+            PermissionChange::SetAnyoneCanWrite(anyone_can_write) => {
+                self.anyone_can_write = *anyone_can_write;
+            }
+        }
+    }
 }
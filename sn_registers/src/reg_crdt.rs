@@ -12,7 +12,7 @@ use crdts::merkle_reg::Node as MerkleDagEntry;
 use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     hash::Hash,
 };
@@ -104,6 +104,131 @@ impl RegisterCrdt {
         self.data.node(hash.0).map(|node| &node.value)
     }
 
+    /// Get the hashes of the entries the entry of the provided `hash` was written atop of,
+    /// if the entry exists.
+    pub(crate) fn children(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        self.data.node(hash.0).map(|node| {
+            node.children
+                .iter()
+                .map(|child| EntryHash(*child))
+                .collect()
+        })
+    }
+
+    /// Get the hashes of the entries written atop of the entry of the provided `hash`,
+    /// if the entry exists.
+    pub(crate) fn parents(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        self.data.node(hash.0)?;
+        Some(
+            self.data
+                .parents(hash.0)
+                .hashes()
+                .into_iter()
+                .map(EntryHash)
+                .collect(),
+        )
+    }
+
+    /// Get all the entries reachable from the current ones, oldest first.
+    ///
+    /// Every entry comes after the entries it was written atop of. Of the entries whose children
+    /// all came already, the one with the lowest hash comes next, so all replicas agree on the order.
+    pub(crate) fn history(&self) -> Vec<(EntryHash, Entry)> {
+        let heads: BTreeSet<EntryHash> = self.read().into_iter().map(|(hash, _)| hash).collect();
+        let held: BTreeSet<EntryHash> = self
+            .causal_past(&heads)
+            .into_iter()
+            .filter(|hash| self.data.node(hash.0).is_some())
+            .collect();
+
+        // Count the held children of every entry, and note which entries were written atop of each.
+        let mut pending_children: BTreeMap<EntryHash, usize> = BTreeMap::new();
+        let mut written_atop: BTreeMap<EntryHash, Vec<EntryHash>> = BTreeMap::new();
+        for hash in &held {
+            let children = self
+                .data
+                .node(hash.0)
+                .map(|node| node.children.clone())
+                .unwrap_or_default();
+            let mut count = 0;
+            for child in children.into_iter().map(EntryHash) {
+                if held.contains(&child) {
+                    count += 1;
+                    written_atop.entry(child).or_default().push(*hash);
+                }
+            }
+            let _ = pending_children.insert(*hash, count);
+        }
+
+        let mut ready: BTreeSet<EntryHash> = pending_children
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(hash, _)| *hash)
+            .collect();
+        let mut ordered = Vec::with_capacity(held.len());
+        while let Some(hash) = ready.pop_first() {
+            if let Some(node) = self.data.node(hash.0) {
+                ordered.push((hash, node.value.clone()));
+            }
+            for parent in written_atop.remove(&hash).unwrap_or_default() {
+                if let Some(count) = pending_children.get_mut(&parent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        let _ = ready.insert(parent);
+                    }
+                }
+            }
+        }
+        ordered
+    }
+
+    /// Get the latest entries which both `a` and `b` were written atop of, directly or not,
+    /// an entry counting as written atop of itself. `None` if either entry does not exist.
+    pub(crate) fn common_ancestors(
+        &self,
+        a: EntryHash,
+        b: EntryHash,
+    ) -> Option<BTreeSet<EntryHash>> {
+        let a_ancestors = self.ancestors(a)?;
+        let b_ancestors = self.ancestors(b)?;
+        let common: BTreeSet<EntryHash> = a_ancestors
+            .intersection(&b_ancestors)
+            .filter(|hash| self.data.node(hash.0).is_some())
+            .copied()
+            .collect();
+
+        // Only keep the common ancestors that no other common ancestor was written atop of.
+        let mut superseded = BTreeSet::new();
+        for hash in &common {
+            let mut ancestors = self.ancestors(*hash)?;
+            let _ = ancestors.remove(hash);
+            superseded.extend(ancestors);
+        }
+        Some(common.difference(&superseded).copied().collect())
+    }
+
+    /// The provided entries and all the entries they were written atop of, directly or not,
+    /// including the hashes referenced by known entries but not held by this replica.
+    pub(crate) fn causal_past(&self, entries: &BTreeSet<EntryHash>) -> BTreeSet<EntryHash> {
+        let mut past = BTreeSet::new();
+        let mut to_visit: Vec<EntryHash> = entries.iter().copied().collect();
+        while let Some(hash) = to_visit.pop() {
+            if !past.insert(hash) {
+                continue;
+            }
+            if let Some(node) = self.data.node(hash.0) {
+                to_visit.extend(node.children.iter().map(|child| EntryHash(*child)));
+            }
+        }
+        past
+    }
+
+    /// The entry of the provided `hash` and all the entries it was written atop of, if it exists.
+    fn ancestors(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        self.data.node(hash.0)?;
+        Some(self.causal_past(&BTreeSet::from([hash])))
+    }
+
     /// Read current entries (multiple entries occur on concurrent writes).
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, Error, Permissions, RegisterAddress,
-    RegisterOp,
+    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, Error, PermissionChange, PermissionOp,
+    Permissions, RegisterAddress, RegisterOp,
 };
 
 use bls::{PublicKey, SecretKey, Signature};
@@ -18,13 +18,14 @@ use std::collections::BTreeSet;
 use xor_name::XorName;
 
 /// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
+pub const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
 
 /// Maximum number of entries of a register.
-const MAX_REG_NUM_ENTRIES: u16 = 1024;
+pub const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
 /// A Register on the SAFE Network
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+#[serde(from = "RegisterFields")]
 pub struct Register {
     /// CRDT data of the Register
     crdt: RegisterCrdt,
@@ -32,6 +33,35 @@ pub struct Register {
     /// Depending on the permissions, the owner can allow other users to write to the register
     /// Everyone can always read the Register because all data is public
     permissions: Permissions,
+    /// Changes to the above permissions signed by the owner
+    permission_ops: BTreeSet<PermissionOp>,
+    /// The permissions in effect, i.e. the above permissions updated by all the changes.
+    /// Kept so they are not worked out again on every check, and rebuilt when deserialising.
+    #[serde(skip_serializing)]
+    effective_permissions: Permissions,
+}
+
+/// The serialised fields of a Register, from which the ones derived from them are rebuilt.
+#[derive(Deserialize)]
+struct RegisterFields {
+    crdt: RegisterCrdt,
+    permissions: Permissions,
+    /// Defaults to none for Registers stored before permissions could be changed.
+    #[serde(default)]
+    permission_ops: BTreeSet<PermissionOp>,
+}
+
+impl From<RegisterFields> for Register {
+    fn from(fields: RegisterFields) -> Self {
+        let mut register = Self {
+            crdt: fields.crdt,
+            effective_permissions: fields.permissions.clone(),
+            permissions: fields.permissions,
+            permission_ops: fields.permission_ops,
+        };
+        register.refresh_effective_permissions();
+        register
+    }
 }
 
 /// A Signed Register on the SAFE Network
@@ -45,6 +75,11 @@ pub struct SignedRegister {
     /// operations to apply on this register,
     /// they contain a signature of the writer
     ops: BTreeSet<RegisterOp>,
+    /// permission changes to apply on this register,
+    /// they contain a signature of the owner
+    /// Defaults to none for Registers stored before permissions could be changed.
+    #[serde(default)]
+    permission_ops: BTreeSet<PermissionOp>,
 }
 
 impl SignedRegister {
@@ -54,6 +89,7 @@ impl SignedRegister {
             base_register,
             signature,
             ops: BTreeSet::new(),
+            permission_ops: BTreeSet::new(),
         }
     }
 
@@ -68,8 +104,14 @@ impl SignedRegister {
             return Err(Error::InvalidSignature);
         }
 
+        for op in &self.permission_ops {
+            self.base_register.check_permission_op(op)?;
+        }
+        // Writes by users whose permission was revoked since are not rejected here,
+        // they are left out when applying the operations instead.
+        let register = self.base_with_permission_ops()?;
         for op in &self.ops {
-            self.base_register.check_register_op(op)?;
+            register.check_register_op_source(op)?;
         }
         Ok(())
     }
@@ -85,10 +127,17 @@ impl SignedRegister {
     }
 
     /// Return the Register after applying all the operations
+    /// Writes which are not allowed by the permissions in effect are left out.
     pub fn register(self) -> Result<Register> {
-        let mut register = self.base_register;
+        let all_entries = self.with_all_ops()?;
+        let mut register = self.base_with_permission_ops()?;
         for op in self.ops {
-            register.apply_op(op)?;
+            // The writer may have lost its permission since, in which case the
+            // write is only kept if the owner had seen it when revoking the permission.
+            if all_entries.check_register_op(&op).is_ok() {
+                register.check_entry_and_reg_sizes(&op.crdt_op.value)?;
+                register.crdt.apply_op(op)?;
+            }
         }
         Ok(register)
     }
@@ -99,6 +148,7 @@ impl SignedRegister {
             return Err(Error::DifferentBaseRegister);
         }
         self.ops.extend(other.ops);
+        self.permission_ops.extend(other.permission_ops);
         Ok(())
     }
 
@@ -110,6 +160,7 @@ impl SignedRegister {
         }
         other.verify()?;
         self.ops.extend(other.ops);
+        self.permission_ops.extend(other.permission_ops);
         Ok(())
     }
 
@@ -123,12 +174,52 @@ impl SignedRegister {
         self.base_register.owner()
     }
 
+    /// Return the operations applied on the base register.
+    pub fn ops(&self) -> &BTreeSet<RegisterOp> {
+        &self.ops
+    }
+
+    /// Return the permission changes applied on the base register.
+    pub fn permission_ops(&self) -> &BTreeSet<PermissionOp> {
+        &self.permission_ops
+    }
+
     /// Check and add an Op to the SignedRegister
     pub fn add_op(&mut self, op: RegisterOp) -> Result<()> {
-        self.base_register.check_register_op(&op)?;
+        self.with_all_ops()?.check_register_op(&op)?;
         self.ops.insert(op);
         Ok(())
     }
+
+    /// Check and add a permission Op to the SignedRegister
+    pub fn add_permission_op(&mut self, op: PermissionOp) -> Result<()> {
+        self.base_register.check_permission_op(&op)?;
+        self.permission_ops.insert(op);
+        Ok(())
+    }
+
+    // The base register with the permission changes applied.
+    fn base_with_permission_ops(&self) -> Result<Register> {
+        let mut register = self.base_register.clone();
+        for op in &self.permission_ops {
+            register.check_permission_op(op)?;
+        }
+        register
+            .permission_ops
+            .extend(self.permission_ops.iter().cloned());
+        register.refresh_effective_permissions();
+        Ok(register)
+    }
+
+    // The base register with all the operations applied, whether they are allowed or not,
+    // to tell which writes the owner had seen when changing the permissions.
+    fn with_all_ops(&self) -> Result<Register> {
+        let mut register = self.base_with_permission_ops()?;
+        for op in &self.ops {
+            register.crdt.apply_op(op.clone())?;
+        }
+        Ok(register)
+    }
 }
 
 impl Register {
@@ -138,7 +229,9 @@ impl Register {
         permissions.writers.insert(owner);
         Self {
             crdt: RegisterCrdt::new(address),
+            effective_permissions: permissions.clone(),
             permissions,
+            permission_ops: BTreeSet::new(),
         }
     }
 
@@ -154,8 +247,11 @@ impl Register {
 
     /// Returns a bytes version of the Register used for signing
     /// Use this API when you want to sign a Register withtout providing a secret key to the Register API
+    ///
+    /// The permission changes are left out, as each of them is signed by the owner on its own,
+    /// so the bytes are the same as for Registers signed before permissions could be changed.
     pub fn bytes(&self) -> Result<Vec<u8>> {
-        bincode::serialize(self).map_err(|_| Error::SerialisationFailed)
+        bincode::serialize(&(&self.crdt, &self.permissions)).map_err(|_| Error::SerialisationFailed)
     }
 
     /// Sign a Register into a SignedRegister
@@ -200,9 +296,69 @@ impl Register {
         self.crdt.read()
     }
 
-    /// Return the permission.
+    /// Return the hashes of the entries the provided 'hash' was written atop of, if present.
+    pub fn children(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.children(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
+    /// Return the hashes of the entries written atop of the provided 'hash', if present.
+    pub fn parents(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.parents(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
+    /// Return all the entries of the register in causal order, oldest first.
+    /// Of the entries whose predecessors all came already, the one with the lowest hash comes
+    /// next, so all replicas return the same order.
+    pub fn history(&self) -> Vec<(EntryHash, Entry)> {
+        self.crdt.history()
+    }
+
+    /// Return the latest entries which both 'a' and 'b' were written atop of,
+    /// directly or not. An entry counts as written atop of itself.
+    pub fn common_ancestors(&self, a: EntryHash, b: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.common_ancestors(a, b).ok_or_else(|| {
+            let missing = if self.crdt.get(a).is_none() { a } else { b };
+            Error::NoSuchEntry(missing)
+        })
+    }
+
+    /// Return the permissions in effect, i.e. the ones the Register was created with
+    /// updated by all the permission changes made by the owner since.
     pub fn permissions(&self) -> &Permissions {
-        &self.permissions
+        &self.effective_permissions
+    }
+
+    /// Change the permissions of the Register, returning the generated
+    /// operation so the caller can broadcast it to other replicas.
+    /// Only the owner can change the permissions.
+    pub fn update_permissions(
+        &mut self,
+        change: PermissionChange,
+        owner: &SecretKey,
+    ) -> Result<PermissionOp> {
+        if self.owner() != owner.public_key() {
+            return Err(Error::InvalidSecretKey);
+        }
+        let seq = self
+            .permission_ops
+            .iter()
+            .map(|op| op.seq + 1)
+            .max()
+            .unwrap_or(0);
+        let seen = self.read().into_iter().map(|(hash, _)| hash).collect();
+        let op = PermissionOp::new(seq, *self.address(), change, seen, owner)?;
+        self.permission_ops.insert(op.clone());
+        self.refresh_effective_permissions();
+        Ok(op)
+    }
+
+    /// Apply a permission change signed by the owner.
+    pub fn apply_permission_op(&mut self, op: PermissionOp) -> Result<()> {
+        self.check_permission_op(&op)?;
+        if self.permission_ops.insert(op) {
+            self.refresh_effective_permissions();
+        }
+        Ok(())
     }
 
     /// Write an entry to the Register, returning the generated
@@ -230,31 +386,96 @@ impl Register {
     /// Merge another Register into this one.
     pub fn merge(&mut self, other: Self) {
         self.crdt.merge(other.crdt);
+        self.permission_ops.extend(other.permission_ops);
+        self.refresh_effective_permissions();
     }
 
     /// Check if a register op is valid for our current register
     pub fn check_register_op(&self, op: &RegisterOp) -> Result<()> {
-        self.check_user_permissions(op.source)?;
-        if self.permissions.anyone_can_write() {
+        if self.check_user_permissions(op.source).is_err() {
+            // Writes made before the permission was revoked remain valid,
+            // as long as the owner had seen them when revoking it.
+            let hash = EntryHash(op.crdt_op.hash());
+            let seen_by_owner = self
+                .last_revocation(&op.source)
+                .map(|revocation| self.crdt.causal_past(&revocation.seen).contains(&hash))
+                .unwrap_or(false);
+            if !seen_by_owner {
+                return Err(Error::AccessDenied(op.source));
+            }
+        } else if self.permissions().anyone_can_write() {
             return Ok(()); // anyone can write, so no need to check the signature
         }
 
         op.verify_signature(&op.source)
     }
 
+    /// Check if a permission op is valid for our current register
+    pub fn check_permission_op(&self, op: &PermissionOp) -> Result<()> {
+        if self.address() != &op.address {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(op.address),
+                reg_addr: Box::new(*self.address()),
+            });
+        }
+        op.verify_signature()
+    }
+
     /// Helper to check user write permissions for the given requester's public key.
     ///
     /// Returns:
     /// `Ok(())` if the user can write to this register
     /// `Err::AccessDenied` if the user cannot write to this register
     pub fn check_user_permissions(&self, requester: PublicKey) -> Result<()> {
-        if requester == self.owner() || self.permissions.can_write(&requester) {
+        if requester == self.owner() || self.permissions().can_write(&requester) {
             Ok(())
         } else {
             Err(Error::AccessDenied(requester))
         }
     }
 
+    // Private helper to work out the permissions in effect again, once the changes are updated.
+    fn refresh_effective_permissions(&mut self) {
+        let mut permissions = self.permissions.clone();
+        for op in &self.permission_ops {
+            permissions.apply_change(&op.change);
+        }
+        self.effective_permissions = permissions;
+    }
+
+    // Private helper to find the last permission change which left the user unable to write.
+    fn last_revocation(&self, user: &PublicKey) -> Option<&PermissionOp> {
+        let mut permissions = self.permissions.clone();
+        let mut last_revocation = None;
+        for op in &self.permission_ops {
+            let could_write = permissions.can_write(user);
+            permissions.apply_change(&op.change);
+            if could_write && !permissions.can_write(user) {
+                last_revocation = Some(op);
+            }
+        }
+        last_revocation
+    }
+
+    // Private helper to check the op is validly signed by a user who was allowed
+    // to write at some point, whether or not the permission was revoked since.
+    fn check_register_op_source(&self, op: &RegisterOp) -> Result<()> {
+        let mut permissions = self.permissions.clone();
+        let mut allowed = op.source == self.owner() || permissions.can_write(&op.source);
+        for permission_op in &self.permission_ops {
+            permissions.apply_change(&permission_op.change);
+            allowed |= permissions.can_write(&op.source);
+        }
+        if !allowed {
+            return Err(Error::AccessDenied(op.source));
+        }
+        if self.permissions.anyone_can_write() && permissions.anyone_can_write() {
+            return Ok(()); // anyone could always write, so no need to check the signature
+        }
+
+        op.verify_signature(&op.source)
+    }
+
     // Private helper to check the given Entry's size is within define limit,
     // as well as check the Register hasn't already reached the maximum number of entries.
     fn check_entry_and_reg_sizes(&self, entry: &Entry) -> Result<()> {
@@ -278,13 +499,15 @@ impl Register {
 #[cfg(test)]
 mod tests {
     use super::{
-        EntryHash, Error, Permissions, Register, RegisterAddress, Result, MAX_REG_NUM_ENTRIES,
+        EntryHash, Error, PermissionChange, Permissions, Register, RegisterAddress, RegisterCrdt,
+        RegisterOp, Result, SignedRegister, MAX_REG_NUM_ENTRIES,
     };
 
-    use bls::SecretKey;
+    use bls::{SecretKey, Signature};
     use eyre::Context;
     use proptest::prelude::*;
     use rand::{rngs::OsRng, seq::SliceRandom, thread_rng, Rng};
+    use serde::Serialize;
     use std::{collections::BTreeSet, sync::Arc};
     use xor_name::XorName;
 
@@ -406,6 +629,10 @@ mod tests {
         let third_entry = register.get(entry3_hash)?;
         assert_eq!(third_entry, &entry3);
 
+        let third_children = register.children(entry3_hash)?;
+        assert_eq!(third_children, BTreeSet::from([entry1_hash, entry2_hash]));
+        assert!(register.children(entry1_hash)?.is_empty());
+
         let non_existing_hash = EntryHash::default();
         let entry_not_found = register.get(non_existing_hash);
         assert_eq!(entry_not_found, Err(Error::NoSuchEntry(non_existing_hash)));
@@ -413,6 +640,64 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_history_traversal() -> eyre::Result<()> {
+        let (sk, register) = &mut create_reg_replicas(1)[0];
+
+        // root <- (left, right) <- merge
+        let (root, _) = register.write(random_register_entry(), BTreeSet::new(), sk)?;
+        let (left, _) = register.write(random_register_entry(), [root].into(), sk)?;
+        let (right, _) = register.write(random_register_entry(), [root].into(), sk)?;
+        let (merge, _) = register.write(random_register_entry(), [left, right].into(), sk)?;
+
+        let history: Vec<EntryHash> = register.history().into_iter().map(|(h, _)| h).collect();
+        let (first, second) = if left < right {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        assert_eq!(history, vec![root, first, second, merge]);
+
+        // Concurrent entries are ordered by hash even across branches: with `a` atop root `x`
+        // and `b` atop root `y`, the lowest of the roots comes first whatever `a` and `b` are.
+        let (sk, register) = &mut create_reg_replicas(1)[0];
+        let (x, _) = register.write(random_register_entry(), BTreeSet::new(), sk)?;
+        let (y, _) = register.write(random_register_entry(), BTreeSet::new(), sk)?;
+        let (a, _) = register.write(random_register_entry(), [x].into(), sk)?;
+        let (b, _) = register.write(random_register_entry(), [y].into(), sk)?;
+        let history: Vec<EntryHash> = register.history().into_iter().map(|(h, _)| h).collect();
+        let mut expected = vec![];
+        let mut ready = BTreeSet::from([x, y]);
+        while let Some(next) = ready.pop_first() {
+            expected.push(next);
+            if next == x {
+                let _ = ready.insert(a);
+            } else if next == y {
+                let _ = ready.insert(b);
+            }
+        }
+        assert_eq!(history, expected);
+
+        assert_eq!(register.parents(root)?, BTreeSet::from([left, right]));
+        assert!(register.parents(merge)?.is_empty());
+        assert_eq!(register.children(merge)?, BTreeSet::from([left, right]));
+
+        assert_eq!(
+            register.common_ancestors(left, right)?,
+            BTreeSet::from([root])
+        );
+        assert_eq!(
+            register.common_ancestors(left, merge)?,
+            BTreeSet::from([left])
+        );
+        assert_eq!(
+            register.common_ancestors(root, EntryHash::default()),
+            Err(Error::NoSuchEntry(EntryHash::default()))
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn register_query_public_perms() -> eyre::Result<()> {
         let meta = xor_name::rand::random();
@@ -460,6 +745,140 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_permission_changes() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+        let base = Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+        );
+        let mut signed = base.clone().into_signed(&owner_sk)?;
+
+        // the writer can't write until the owner grants it the permission
+        let mut owner_replica = base;
+        let (_, denied_op) =
+            owner_replica
+                .clone()
+                .write(random_register_entry(), BTreeSet::new(), &writer_sk)?;
+        assert_eq!(
+            owner_replica.check_register_op(&denied_op),
+            Err(Error::AccessDenied(writer))
+        );
+        assert_eq!(
+            owner_replica.update_permissions(PermissionChange::GrantWrite(writer), &writer_sk),
+            Err(Error::InvalidSecretKey)
+        );
+        let grant =
+            owner_replica.update_permissions(PermissionChange::GrantWrite(writer), &owner_sk)?;
+        assert!(owner_replica.permissions().can_write(&writer));
+        signed.add_permission_op(grant.clone())?;
+
+        let mut writer_replica = owner_replica.clone();
+        let (_, seen_op) =
+            writer_replica.write(random_register_entry(), BTreeSet::new(), &writer_sk)?;
+        owner_replica.apply_op(seen_op.clone())?;
+        signed.add_op(seen_op.clone())?;
+
+        // a write the owner has not seen yet when revoking the permission
+        let mut unseen_signed = signed.clone();
+        let (_, unseen_op) =
+            writer_replica.write(random_register_entry(), BTreeSet::new(), &writer_sk)?;
+        unseen_signed.add_op(unseen_op.clone())?;
+
+        let revoke =
+            owner_replica.update_permissions(PermissionChange::RevokeWrite(writer), &owner_sk)?;
+        assert!(!owner_replica.permissions().can_write(&writer));
+        signed.add_permission_op(revoke)?;
+
+        owner_replica.check_register_op(&seen_op)?;
+        assert_eq!(
+            owner_replica.check_register_op(&unseen_op),
+            Err(Error::AccessDenied(writer))
+        );
+        assert_eq!(
+            signed.add_op(unseen_op.clone()),
+            Err(Error::AccessDenied(writer))
+        );
+
+        // merging in the unseen write keeps the register valid, but leaves the write out
+        signed.verified_merge(unseen_signed)?;
+        signed.verify()?;
+        let register = signed.register()?;
+        assert_eq!(register.size(), 1);
+        let entries: Vec<_> = register.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(entries, vec![EntryHash(seen_op.crdt_op.hash())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn registers_stored_before_permission_changes_still_verify() -> eyre::Result<()> {
+        // The layouts of `Register` and `SignedRegister` before permission changes were added.
+        #[derive(Serialize)]
+        struct OldRegister<'a> {
+            crdt: &'a RegisterCrdt,
+            permissions: &'a Permissions,
+        }
+        #[derive(Serialize)]
+        struct OldSignedRegister<'a> {
+            base_register: OldRegister<'a>,
+            signature: Signature,
+            ops: BTreeSet<RegisterOp>,
+        }
+
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        let old_base = OldRegister {
+            crdt: &base.crdt,
+            permissions: &base.permissions,
+        };
+        let signature = owner_sk.sign(bincode::serialize(&old_base)?);
+        let (_, op) = base
+            .clone()
+            .write(random_register_entry(), BTreeSet::new(), &owner_sk)?;
+        let old = OldSignedRegister {
+            base_register: old_base,
+            signature: signature.clone(),
+            ops: BTreeSet::from([op]),
+        };
+
+        // records are stored as MessagePack
+        let bytes = rmp_serde::to_vec(&old)?;
+        let signed: SignedRegister = rmp_serde::from_slice(&bytes)?;
+        signed.verify()?;
+        assert!(signed.permission_ops().is_empty());
+        assert_eq!(signed.clone().register()?.size(), 1);
+
+        // the same signature is produced now, whatever permission changes were made since
+        let mut changed = base.clone();
+        let _ = changed.update_permissions(
+            PermissionChange::GrantWrite(SecretKey::random().public_key()),
+            &owner_sk,
+        )?;
+        assert_eq!(base.sign(&owner_sk)?, signature);
+        assert_eq!(changed.bytes()?, base.bytes()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn permissions_in_effect_survive_serialisation() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let writer = SecretKey::random().public_key();
+        let mut register = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        let _ = register.update_permissions(PermissionChange::GrantWrite(writer), &owner_sk)?;
+        assert!(register.permissions().can_write(&writer));
+
+        let deserialised: Register = rmp_serde::from_slice(&rmp_serde::to_vec(&register)?)?;
+        assert!(deserialised.permissions().can_write(&writer));
+        assert_eq!(deserialised, register);
+
+        Ok(())
+    }
+
     #[test]
     fn exceeding_max_reg_entries_errors() -> eyre::Result<()> {
         let meta = xor_name::rand::random();
@@ -7,10 +7,15 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::UniquePubkey;
+use crate::{Error, Result};
 
 use serde::{Deserialize, Serialize};
-use std::hash::Hash;
-use xor_name::XorName;
+use sn_url::{decode_url_of_kind, encode_url, SAFE_URL_SCHEME};
+use std::{fmt, hash::Hash, str::FromStr};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// The `<type>` of a `SpendAddress` in its `safe://` url form.
+pub const SPEND_URL_TYPE: &str = "spend";
 
 /// The address of a SignedSpend in the network.
 /// This is used to check if a CashNote is spent, note that the actual CashNote is not stored on the Network.
@@ -32,4 +37,50 @@ impl SpendAddress {
     pub fn xorname(&self) -> &XorName {
         &self.0
     }
+
+    /// Render this address in its canonical `safe://spend/` url form.
+    pub fn to_url(&self) -> String {
+        encode_url(SPEND_URL_TYPE, &self.0 .0)
+    }
+}
+
+impl fmt::Display for SpendAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_url())
+    }
+}
+
+impl FromStr for SpendAddress {
+    type Err = Error;
+
+    /// Parse either the `safe://spend/` url form or a legacy 64 character hex xorname.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let bytes = if s.starts_with(SAFE_URL_SCHEME) {
+            decode_url_of_kind(s, SPEND_URL_TYPE)
+                .map_err(|err| Error::SpendAddressParsingFailed(err.to_string()))?
+        } else {
+            hex::decode(s)
+                .map_err(|_| Error::SpendAddressParsingFailed(format!("{s:?}: invalid hex")))?
+        };
+        let bytes: [u8; XOR_NAME_LEN] = bytes
+            .try_into()
+            .map_err(|_| Error::SpendAddressParsingFailed(format!("{s:?}: invalid length")))?;
+        Ok(Self(XorName(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spend_address_url_round_trip() -> Result<()> {
+        let addr = SpendAddress::new(XorName::random(&mut rand::thread_rng()));
+        let url = addr.to_string();
+        assert!(url.starts_with(SAFE_URL_SCHEME));
+        assert_eq!(url.parse::<SpendAddress>()?, addr);
+        assert_eq!(hex::encode(addr.xorname()).parse::<SpendAddress>()?, addr);
+        Ok(())
+    }
 }
@@ -18,7 +18,7 @@ mod unique_keys;
 pub(crate) use builder::TransactionBuilder;
 pub(crate) use transaction::Input;
 
-pub use address::SpendAddress;
+pub use address::{SpendAddress, SPEND_URL_TYPE};
 pub use cashnote::CashNote;
 pub use nano::NanoTokens;
 pub use reason_hash::Hash;
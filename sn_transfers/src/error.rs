@@ -82,6 +82,9 @@ pub enum Error {
     #[error("Transfer deserialisation failed")]
     TransferDeserializationFailed,
 
+    #[error("Could not parse spend address {0}")]
+    SpendAddressParsingFailed(String),
+
     #[error("Bls error: {0}")]
     Blsttc(#[from] bls::error::Error),
 }
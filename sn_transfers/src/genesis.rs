@@ -107,10 +107,10 @@ fn create_genesis_wallet() -> LocalWallet {
         .expect("Genesis key hex shall be successfully parsed.");
     debug!("genesis wallet pubkey: {:?}", secret_key.public_key());
     let main_key = MainSecretKey::new(secret_key);
-    crate::wallet::store_new_keypair(&wallet_dir, &main_key)
+    crate::wallet::store_new_keypair(&wallet_dir, &main_key, None)
         .expect("Genesis key shall be successfully stored.");
 
-    LocalWallet::load_from(&root_dir)
+    LocalWallet::load_from(&root_dir, None)
         .expect("Faucet wallet (after genesis) shall be created successfully.")
 }
 
@@ -169,7 +169,7 @@ pub fn create_faucet_wallet() -> LocalWallet {
     let root_dir = get_faucet_dir();
 
     println!("Loading faucet wallet... {:#?}", root_dir);
-    LocalWallet::load_from(&root_dir).expect("Faucet wallet shall be created successfully.")
+    LocalWallet::load_from(&root_dir, None).expect("Faucet wallet shall be created successfully.")
 }
 
 // We need deterministic and fix path for the genesis wallet.
@@ -54,6 +54,15 @@ pub enum Error {
     /// Failed to decypher transfer with our key, maybe it was encrypted to another key
     #[error("Failed to decypher transfer with our key, maybe it was not for us")]
     FailedToDecypherTransfer,
+    /// The main key is encrypted, but no passphrase was provided to decrypt it
+    #[error("The main key is encrypted, a passphrase is required to load it")]
+    MainKeyPassphraseRequired,
+    /// Failed to encrypt the main key with a passphrase
+    #[error("Could not encrypt the main key with the passphrase")]
+    FailedToEncryptMainKey,
+    /// Failed to decrypt the main key, most likely because of a wrong passphrase
+    #[error("Could not decrypt the main key, the passphrase may be wrong")]
+    FailedToDecryptMainKey,
 
     /// Transfer error
     #[error("Transfer error: {0}")]
@@ -10,14 +10,37 @@ use super::error::{Error, Result};
 
 use crate::{MainPubkey, MainSecretKey};
 
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use hex::{decode, encode};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Filename for storing the node's reward (BLS hex-encoded) main secret key.
 const MAIN_SECRET_KEY_FILENAME: &str = "main_secret_key";
+/// Filename for storing the main secret key encrypted with a passphrase (hex-encoded).
+const ENCRYPTED_MAIN_SECRET_KEY_FILENAME: &str = "main_secret_key.encrypted";
 /// Filename for storing the node's reward (BLS hex-encoded) public key.
 const MAIN_PUBKEY_FILENAME: &str = "main_pubkey";
 
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// A main secret key encrypted with a key derived from a passphrase.
+#[derive(Serialize, Deserialize)]
+struct EncryptedMainKey {
+    /// Salt of the Argon2id derivation of the encryption key from the passphrase.
+    salt: [u8; SALT_LEN],
+    /// Nonce of the ChaCha20Poly1305 encryption.
+    nonce: [u8; NONCE_LEN],
+    /// The encrypted bytes of the main secret key.
+    ciphertext: Vec<u8>,
+}
+
 /// Parse a public address from a hex-encoded string.
 pub fn parse_main_pubkey<T: AsRef<[u8]>>(hex: T) -> Result<MainPubkey> {
     let public_key = bls_public_from_hex(hex)?;
@@ -25,17 +48,70 @@ pub fn parse_main_pubkey<T: AsRef<[u8]>>(hex: T) -> Result<MainPubkey> {
 }
 
 /// Writes the public address and main key (hex-encoded) to different locations at disk.
-pub(crate) fn store_new_keypair(wallet_dir: &Path, main_key: &MainSecretKey) -> Result<()> {
-    let secret_key_path = wallet_dir.join(MAIN_SECRET_KEY_FILENAME);
+/// The main key is encrypted with the passphrase, if one is provided.
+pub(crate) fn store_new_keypair(
+    wallet_dir: &Path,
+    main_key: &MainSecretKey,
+    passphrase: Option<&str>,
+) -> Result<()> {
     let public_key_path = wallet_dir.join(MAIN_PUBKEY_FILENAME);
-    std::fs::write(secret_key_path, encode(main_key.to_bytes()))?;
+    store_main_key(wallet_dir, main_key, passphrase)?;
     std::fs::write(public_key_path, encode(main_key.main_pubkey().to_bytes()))
         .map_err(|e| Error::FailedToHexEncodeKey(e.to_string()))?;
     Ok(())
 }
 
+/// Writes the main key to disk, encrypted with the passphrase if one is provided,
+/// removing the main key file stored in the other form, if any.
+pub(super) fn store_main_key(
+    wallet_dir: &Path,
+    main_key: &MainSecretKey,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let secret_key_path = wallet_dir.join(MAIN_SECRET_KEY_FILENAME);
+    let encrypted_key_path = wallet_dir.join(ENCRYPTED_MAIN_SECRET_KEY_FILENAME);
+    let (path, stale_path, contents) = match passphrase {
+        Some(passphrase) => {
+            let encrypted = encrypt_main_key(main_key, passphrase)?;
+            (encrypted_key_path, secret_key_path, encode(encrypted))
+        }
+        None => (
+            secret_key_path,
+            encrypted_key_path,
+            encode(main_key.to_bytes()),
+        ),
+    };
+
+    std::fs::write(path, contents)?;
+    if stale_path.is_file() {
+        std::fs::remove_file(stale_path)?;
+    }
+    Ok(())
+}
+
+/// Returns true if the main key in the wallet dir is encrypted with a passphrase.
+pub(super) fn main_key_is_encrypted(wallet_dir: &Path) -> bool {
+    wallet_dir
+        .join(ENCRYPTED_MAIN_SECRET_KEY_FILENAME)
+        .is_file()
+}
+
 /// Returns Some(sn_transfers::MainSecretKey) or None if file doesn't exist. It assumes it's hex-encoded.
-pub(super) fn get_main_key(wallet_dir: &Path) -> Result<Option<MainSecretKey>> {
+///
+/// If the main key is encrypted, it is decrypted with the given passphrase, which is required then.
+pub(super) fn get_main_key(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Option<MainSecretKey>> {
+    let encrypted_key_path = wallet_dir.join(ENCRYPTED_MAIN_SECRET_KEY_FILENAME);
+    if encrypted_key_path.is_file() {
+        let passphrase = passphrase.ok_or(Error::MainKeyPassphraseRequired)?;
+        let encrypted_hex_bytes = std::fs::read(&encrypted_key_path)?;
+        let encrypted = decode(encrypted_hex_bytes).map_err(|_| Error::FailedToDecodeHexToKey)?;
+        let secret = decrypt_main_key(&encrypted, passphrase)?;
+        return Ok(Some(MainSecretKey::new(secret)));
+    }
+
     let path = wallet_dir.join(MAIN_SECRET_KEY_FILENAME);
     if !path.is_file() {
         return Ok(None);
@@ -47,6 +123,54 @@ pub(super) fn get_main_key(wallet_dir: &Path) -> Result<Option<MainSecretKey>> {
     Ok(Some(MainSecretKey::new(secret)))
 }
 
+/// Encrypt the main key with a key derived from the passphrase, returning the serialised result.
+fn encrypt_main_key(main_key: &MainSecretKey, passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = passphrase_cipher(passphrase, &salt).ok_or(Error::FailedToEncryptMainKey)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), main_key.to_bytes().as_slice())
+        .map_err(|_| Error::FailedToEncryptMainKey)?;
+    let encrypted = EncryptedMainKey {
+        salt,
+        nonce,
+        ciphertext,
+    };
+    Ok(bincode::serialize(&encrypted)?)
+}
+
+/// Decrypt a main key serialised by `encrypt_main_key`.
+fn decrypt_main_key(bytes: &[u8], passphrase: &str) -> Result<bls::SecretKey> {
+    let encrypted: EncryptedMainKey = bincode::deserialize(bytes)?;
+    let cipher =
+        passphrase_cipher(passphrase, &encrypted.salt).ok_or(Error::FailedToDecryptMainKey)?;
+    let bytes = cipher
+        .decrypt(
+            Nonce::from_slice(&encrypted.nonce),
+            encrypted.ciphertext.as_slice(),
+        )
+        .map_err(|_| Error::FailedToDecryptMainKey)?;
+    let bytes_fixed_len: [u8; bls::SK_SIZE] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::FailedToParseBlsKey)?;
+    let sk = bls::SecretKey::from_bytes(bytes_fixed_len)?;
+    Ok(sk)
+}
+
+/// Derive the encryption key of the main key from the passphrase, with the memory-hard Argon2id.
+/// Returns None if the derivation failed, leaving the caller to report it.
+fn passphrase_cipher(passphrase: &str, salt: &[u8]) -> Option<ChaCha20Poly1305> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .ok()?;
+    Some(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
 /// Construct a BLS secret key from a hex-encoded string.
 pub fn bls_secret_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::SecretKey> {
     let bytes = decode(hex).map_err(|_| Error::FailedToDecodeHexToKey)?;
@@ -71,7 +195,7 @@ fn bls_public_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::PublicKey> {
 
 #[cfg(test)]
 mod test {
-    use super::{get_main_key, store_new_keypair, MainSecretKey};
+    use super::{get_main_key, main_key_is_encrypted, store_new_keypair, Error, MainSecretKey};
     use assert_fs::TempDir;
     use eyre::Result;
 
@@ -80,8 +204,36 @@ mod test {
         let main_key = MainSecretKey::random();
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
-        store_new_keypair(&root_dir, &main_key)?;
-        let secret_result = get_main_key(&root_dir)?.expect("There to be a key on disk.");
+        store_new_keypair(&root_dir, &main_key, None)?;
+        let secret_result = get_main_key(&root_dir, None)?.expect("There to be a key on disk.");
+        assert_eq!(secret_result.main_pubkey(), main_key.main_pubkey());
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_key_to_and_from_file() -> Result<()> {
+        let main_key = MainSecretKey::random();
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+        store_new_keypair(&root_dir, &main_key, None)?;
+        store_new_keypair(&root_dir, &main_key, Some("correct horse"))?;
+        assert!(main_key_is_encrypted(&root_dir));
+
+        let secret_result =
+            get_main_key(&root_dir, Some("correct horse"))?.expect("There to be a key on disk.");
+        assert_eq!(secret_result.main_pubkey(), main_key.main_pubkey());
+        assert!(matches!(
+            get_main_key(&root_dir, Some("battery staple")),
+            Err(Error::FailedToDecryptMainKey)
+        ));
+        assert!(matches!(
+            get_main_key(&root_dir, None),
+            Err(Error::MainKeyPassphraseRequired)
+        ));
+
+        store_new_keypair(&root_dir, &main_key, None)?;
+        assert!(!main_key_is_encrypted(&root_dir));
+        let secret_result = get_main_key(&root_dir, None)?.expect("There to be a key on disk.");
         assert_eq!(secret_result.main_pubkey(), main_key.main_pubkey());
         Ok(())
     }
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 use super::{
-    keys::{get_main_key, store_new_keypair},
+    keys::{get_main_key, main_key_is_encrypted, store_main_key, store_new_keypair},
     wallet_file::{
         get_unconfirmed_spend_requests, get_wallet, load_cash_notes_from_disk,
         load_created_cash_note, store_created_cash_notes, store_unconfirmed_spend_requests,
@@ -61,18 +61,10 @@ impl LocalWallet {
 
     /// reloads the wallet from disk.
     fn reload(&mut self) -> Result<()> {
-        // placeholder random MainSecretKey to take it out
-        let current_key = std::mem::replace(&mut self.key, MainSecretKey::random());
-        let (key, wallet, unconfirmed_spend_requests) =
-            load_from_path(&self.wallet_dir, Some(current_key))?;
-
-        // and move the original back in
-        *self = Self {
-            key,
-            wallet,
-            wallet_dir: self.wallet_dir.to_path_buf(),
-            unconfirmed_spend_requests,
-        };
+        // the main key is kept, so that an encrypted one doesn't need its passphrase again
+        let (wallet, unconfirmed_spend_requests) = load_state_from_path(&self.wallet_dir)?;
+        self.wallet = wallet;
+        self.unconfirmed_spend_requests = unconfirmed_spend_requests;
         Ok(())
     }
 
@@ -118,47 +110,71 @@ impl LocalWallet {
     }
 
     /// Loads a serialized wallet from a path and given main key.
-    pub fn load_from_main_key(root_dir: &Path, main_key: MainSecretKey) -> Result<Self> {
+    ///
+    /// The main key is stored encrypted with the passphrase, if one is given and there is no
+    /// main key yet. An existing main key is decrypted with the passphrase instead.
+    pub fn load_from_main_key(
+        root_dir: &Path,
+        main_key: MainSecretKey,
+        passphrase: Option<&str>,
+    ) -> Result<Self> {
         let wallet_dir = root_dir.join(WALLET_DIR_NAME);
         // This creates the received_cash_notes dir if it doesn't exist.
         std::fs::create_dir_all(&wallet_dir)?;
         // This creates the main_key file if it doesn't exist.
-        let (key, wallet, unconfirmed_spend_requests) =
-            load_from_path(&wallet_dir, Some(main_key))?;
-        Ok(Self {
-            key,
-            wallet,
-            wallet_dir: wallet_dir.to_path_buf(),
-            unconfirmed_spend_requests,
-        })
+        Self::load(wallet_dir, Some(main_key), passphrase)
     }
 
     /// Loads a serialized wallet from a path.
-    pub fn load_from(root_dir: &Path) -> Result<Self> {
+    ///
+    /// An encrypted main key is decrypted with the passphrase, which is required then.
+    /// A new main key is stored encrypted with the passphrase, if one is given.
+    pub fn load_from(root_dir: &Path, passphrase: Option<&str>) -> Result<Self> {
         let wallet_dir = root_dir.join(WALLET_DIR_NAME);
         // This creates the received_cash_notes dir if it doesn't exist.
         std::fs::create_dir_all(&wallet_dir)?;
-        let (key, wallet, unconfirmed_spend_requests) = load_from_path(&wallet_dir, None)?;
-        Ok(Self {
-            key,
-            wallet,
-            wallet_dir: wallet_dir.to_path_buf(),
-            unconfirmed_spend_requests,
-        })
+        Self::load(wallet_dir, None, passphrase)
     }
 
     /// Tries to loads a serialized wallet from a path, bailing out if it doesn't exist.
-    pub fn try_load_from(root_dir: &Path) -> Result<Self> {
-        let wallet_dir = root_dir.join(WALLET_DIR_NAME);
-        let (key, wallet, unconfirmed_spend_requests) = load_from_path(&wallet_dir, None)?;
+    ///
+    /// An encrypted main key is decrypted with the passphrase, which is required then.
+    pub fn try_load_from(root_dir: &Path, passphrase: Option<&str>) -> Result<Self> {
+        Self::load(root_dir.join(WALLET_DIR_NAME), None, passphrase)
+    }
+
+    /// Loads a serialized wallet from the wallet dir, see `load_from_path`.
+    fn load(
+        wallet_dir: PathBuf,
+        main_key: Option<MainSecretKey>,
+        passphrase: Option<&str>,
+    ) -> Result<Self> {
+        let (key, wallet, unconfirmed_spend_requests) =
+            load_from_path(&wallet_dir, main_key, passphrase)?;
         Ok(Self {
             key,
             wallet,
-            wallet_dir: wallet_dir.to_path_buf(),
+            wallet_dir,
             unconfirmed_spend_requests,
         })
     }
 
+    /// Returns true if the main key of the wallet at the path is encrypted with a passphrase.
+    pub fn is_encrypted(root_dir: &Path) -> bool {
+        main_key_is_encrypted(&root_dir.join(WALLET_DIR_NAME))
+    }
+
+    /// Encrypt the main key stored on disk with the passphrase,
+    /// replacing any previous form of it.
+    pub fn encrypt_main_key(&self, passphrase: &str) -> Result<()> {
+        store_main_key(&self.wallet_dir, &self.key, Some(passphrase))
+    }
+
+    /// Store the main key on disk in plain text, replacing its encrypted form.
+    pub fn decrypt_main_key(&self) -> Result<()> {
+        store_main_key(&self.wallet_dir, &self.key, None)
+    }
+
     pub fn address(&self) -> MainPubkey {
         self.key.main_pubkey()
     }
@@ -513,16 +529,23 @@ impl LocalWallet {
 fn load_from_path(
     wallet_dir: &Path,
     main_key: Option<MainSecretKey>,
+    passphrase: Option<&str>,
 ) -> Result<(MainSecretKey, KeyLessWallet, BTreeSet<SignedSpend>)> {
-    let key = match get_main_key(wallet_dir)? {
+    let key = match get_main_key(wallet_dir, passphrase)? {
         Some(key) => key,
         None => {
             let key = main_key.unwrap_or(MainSecretKey::random());
-            store_new_keypair(wallet_dir, &key)?;
+            store_new_keypair(wallet_dir, &key, passphrase)?;
             warn!("No main key found when loading wallet from path, generating a new one with pubkey: {:?}", key.main_pubkey());
             key
         }
     };
+    let (wallet, unconfirmed_spend_requests) = load_state_from_path(wallet_dir)?;
+    Ok((key, wallet, unconfirmed_spend_requests))
+}
+
+/// Loads the serialized wallet and unconfirmed spend requests from a path.
+fn load_state_from_path(wallet_dir: &Path) -> Result<(KeyLessWallet, BTreeSet<SignedSpend>)> {
     let unconfirmed_spend_requests = match get_unconfirmed_spend_requests(wallet_dir)? {
         Some(unconfirmed_spend_requests) => unconfirmed_spend_requests,
         None => Default::default(),
@@ -543,7 +566,7 @@ fn load_from_path(
         }
     };
 
-    Ok((key, wallet, unconfirmed_spend_requests))
+    Ok((wallet, unconfirmed_spend_requests))
 }
 
 impl KeyLessWallet {
@@ -571,7 +594,7 @@ impl KeyLessWallet {
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::{get_wallet, store_wallet, LocalWallet};
+    use super::{get_wallet, store_wallet, Error, LocalWallet};
     use crate::{
         genesis::{create_first_cash_note_from_key, GENESIS_CASHNOTE_AMOUNT},
         wallet::{local_store::WALLET_DIR_NAME, KeyLessWallet},
@@ -730,12 +753,12 @@ mod tests {
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
 
-        let mut depositor = LocalWallet::load_from(&root_dir)?;
+        let mut depositor = LocalWallet::load_from(&root_dir, None)?;
         let genesis =
             create_first_cash_note_from_key(&depositor.key).expect("Genesis creation to succeed.");
         depositor.deposit_and_store_to_disk(&vec![genesis])?;
 
-        let deserialized = LocalWallet::load_from(&root_dir)?;
+        let deserialized = LocalWallet::load_from(&root_dir, None)?;
 
         assert_eq!(depositor.address(), deserialized.address());
         assert_eq!(GENESIS_CASHNOTE_AMOUNT, depositor.balance().as_nano());
@@ -775,7 +798,7 @@ mod tests {
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
 
-        let mut sender = LocalWallet::load_from(&root_dir)?;
+        let mut sender = LocalWallet::load_from(&root_dir, None)?;
         let sender_cash_note =
             create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
         sender.deposit_and_store_to_disk(&vec![sender_cash_note])?;
@@ -807,7 +830,7 @@ mod tests {
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
 
-        let mut sender = LocalWallet::load_from(&root_dir)?;
+        let mut sender = LocalWallet::load_from(&root_dir, None)?;
         let sender_cash_note =
             create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
         sender.deposit_and_store_to_disk(&vec![sender_cash_note])?;
@@ -819,7 +842,7 @@ mod tests {
         let to = vec![(NanoTokens::from(send_amount), recipient_main_pubkey)];
         let _created_cash_notes = sender.local_send(to, None)?;
 
-        let deserialized = LocalWallet::load_from(&root_dir)?;
+        let deserialized = LocalWallet::load_from(&root_dir, None)?;
 
         assert_eq!(sender.address(), deserialized.address());
         assert_eq!(
@@ -879,7 +902,7 @@ mod tests {
         let sender_root_dir = create_temp_dir();
         let sender_root_dir = sender_root_dir.path().to_path_buf();
 
-        let mut sender = LocalWallet::load_from(&sender_root_dir)?;
+        let mut sender = LocalWallet::load_from(&sender_root_dir, None)?;
         let sender_cash_note =
             create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
         sender.deposit_and_store_to_disk(&vec![sender_cash_note])?;
@@ -889,7 +912,7 @@ mod tests {
         // Send to a new address.
         let recipient_root_dir = create_temp_dir();
         let recipient_root_dir = recipient_root_dir.path().to_path_buf();
-        let mut recipient = LocalWallet::load_from(&recipient_root_dir)?;
+        let mut recipient = LocalWallet::load_from(&recipient_root_dir, None)?;
         let recipient_main_pubkey = recipient.key.main_pubkey();
 
         let to = vec![(NanoTokens::from(send_amount), recipient_main_pubkey)];
@@ -936,7 +959,7 @@ mod tests {
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
 
-        let mut sender = LocalWallet::load_from(&root_dir)?;
+        let mut sender = LocalWallet::load_from(&root_dir, None)?;
         let sender_cash_note =
             create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
         sender.deposit_and_store_to_disk(&vec![sender_cash_note])?;
@@ -987,6 +1010,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn wallet_created_from_main_key_with_passphrase_is_encrypted() -> Result<()> {
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+        let main_key = MainSecretKey::random();
+        let main_pubkey = main_key.main_pubkey();
+
+        let created = LocalWallet::load_from_main_key(&root_dir, main_key, Some("passphrase"))?;
+        assert_eq!(main_pubkey, created.address());
+        assert!(LocalWallet::is_encrypted(&root_dir));
+
+        let loaded = LocalWallet::try_load_from(&root_dir, Some("passphrase"))?;
+        assert_eq!(main_pubkey, loaded.address());
+        assert!(matches!(
+            LocalWallet::try_load_from(&root_dir, Some("wrong")),
+            Err(Error::FailedToDecryptMainKey)
+        ));
+        assert!(matches!(
+            LocalWallet::try_load_from(&root_dir, None),
+            Err(Error::MainKeyPassphraseRequired)
+        ));
+
+        Ok(())
+    }
+
     fn create_temp_dir() -> TempDir {
         TempDir::new().expect("Should be able to create a temp dir.")
     }
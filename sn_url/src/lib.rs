@@ -0,0 +1,150 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Canonical text form of network addresses.
+//!
+//! An address is rendered as `safe://<type>/<payload>`, where `<type>` names the kind of data
+//! being addressed and `<payload>` is the lowercase, unpadded base32 encoding of the address
+//! bytes followed by a 4 byte checksum. The checksum covers the type as well as the bytes,
+//! so a chunk address pasted where a register address is expected is rejected.
+//!
+//! This only implements the encoding, each address type picks its own `<type>` and bytes.
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Errors of the `safe://` url encoding.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The provided String is not a valid `safe://` url
+    #[error("Could not parse url {0}")]
+    UrlParsingFailed(String),
+}
+
+/// A specialised `Result` type for the `safe://` url encoding.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The scheme every canonical address starts with.
+pub const SAFE_URL_SCHEME: &str = "safe://";
+
+const CHECKSUM_LEN: usize = 4;
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Render `bytes` as a `safe://<kind>/` url.
+pub fn encode_url(kind: &str, bytes: &[u8]) -> String {
+    let mut bytes = bytes.to_vec();
+    bytes.extend_from_slice(&checksum(kind, &bytes));
+    format!("{SAFE_URL_SCHEME}{kind}/{}", base32_encode(&bytes))
+}
+
+/// Parse a `safe://` url, returning its type and the address bytes once the checksum is verified.
+pub fn decode_url(url: &str) -> Result<(&str, Vec<u8>)> {
+    let rest = url
+        .trim()
+        .strip_prefix(SAFE_URL_SCHEME)
+        .ok_or_else(|| parsing_err(url, "missing `safe://` scheme"))?;
+    let (kind, payload) = rest
+        .split_once('/')
+        .ok_or_else(|| parsing_err(url, "missing address type"))?;
+
+    let mut bytes = base32_decode(payload).ok_or_else(|| parsing_err(url, "invalid base32"))?;
+    if bytes.len() < CHECKSUM_LEN {
+        return Err(parsing_err(url, "too short"));
+    }
+    let expected_checksum = bytes.split_off(bytes.len() - CHECKSUM_LEN);
+    if checksum(kind, &bytes)[..] != expected_checksum[..] {
+        return Err(parsing_err(url, "checksum mismatch"));
+    }
+    Ok((kind, bytes))
+}
+
+/// Parse a `safe://<kind>/` url, failing if it is of another type.
+pub fn decode_url_of_kind(url: &str, kind: &str) -> Result<Vec<u8>> {
+    let (found, bytes) = decode_url(url)?;
+    if found != kind {
+        return Err(parsing_err(url, &format!("not a {kind} address")));
+    }
+    Ok(bytes)
+}
+
+fn parsing_err(input: &str, reason: &str) -> Error {
+    Error::UrlParsingFailed(format!("{input:?}: {reason}"))
+}
+
+fn checksum(kind: &str, bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(kind.as_bytes());
+    hasher.update(bytes);
+    let digest = hasher.finalize();
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    checksum
+}
+
+/// RFC 4648 base32, lowercase and without padding.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c.to_ascii_lowercase())? as u16;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    // Any leftover bits are padding and must be zero for the encoding to be canonical.
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_with_bad_checksum_or_type_is_rejected() -> Result<()> {
+        let url = encode_url("chunk", &[7; 32]);
+        assert_eq!(decode_url(&url)?, ("chunk", vec![7; 32]));
+
+        let mut corrupted = url.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'a' { b'b' } else { b'a' };
+        let corrupted = String::from_utf8(corrupted).expect("still utf8");
+        assert!(decode_url(&corrupted).is_err());
+
+        let retyped = url.replacen("chunk", "spend", 1);
+        assert!(decode_url(&retyped).is_err());
+        assert!(decode_url_of_kind(&url, "spend").is_err());
+        Ok(())
+    }
+}